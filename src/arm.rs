@@ -1,4 +1,7 @@
+#[cfg(target_arch = "aarch64")]
 use core::arch::asm;
+#[cfg(not(target_arch = "aarch64"))]
+use core::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(C)]
@@ -99,6 +102,7 @@ pub fn cache_flush(address: *mut u8, size: usize) {
     }
 }
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 pub fn get_system_tick() -> u64 {
     let system_tick: u64;
@@ -111,6 +115,7 @@ pub fn get_system_tick() -> u64 {
     system_tick
 }
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 pub fn get_system_tick_frequency() -> u64 {
     let system_tick_freq: u64;
@@ -123,6 +128,22 @@ pub fn get_system_tick_frequency() -> u64 {
     system_tick_freq
 }
 
+// Host builds (tests, fuzzing) have no system counter: ticks just need to keep increasing there
+
+#[cfg(not(target_arch = "aarch64"))]
+static G_HOST_SYSTEM_TICK: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(not(target_arch = "aarch64"))]
+pub fn get_system_tick() -> u64 {
+    G_HOST_SYSTEM_TICK.fetch_add(1, Ordering::Relaxed)
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+pub fn get_system_tick_frequency() -> u64 {
+    // The Switch's fixed counter frequency
+    19200000
+}
+
 pub const fn ticks_to_nanoseconds(ticks: u64) -> u64 {
     (ticks * 625) / 12
 }
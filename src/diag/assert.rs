@@ -55,8 +55,7 @@ fn do_assert(level: AssertLevel, rc: ResultCode) -> bool {
             };
         }
         else if level == AssertLevel::Panic() {
-            let res: Result<()> = Err(rc);
-            res.unwrap();
+            panic!("Assertion failed: {}", rc);
         }
         else if level == AssertLevel::ProcessExit() {
             rrt0::exit(rc);
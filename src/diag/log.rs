@@ -1,6 +1,7 @@
 use crate::thread;
 use crate::result::*;
 use crate::mem;
+use crate::sync;
 use crate::ipc::sf;
 use alloc::string::String;
 
@@ -72,22 +73,27 @@ use crate::service;
 use crate::service::fspsrv;
 use crate::service::fspsrv::IFileSystemProxy;
 
-pub struct FsAccessLogLogger {
-    service: Result<mem::Shared<fspsrv::FileSystemProxy>>
-}
+// The fsp-srv session is kept open by the logger (every other fsp-srv user shares it), thus logging doesn't reconnect each time
+static mut G_FS_ACCESS_LOG_LOCK: sync::Mutex = sync::Mutex::new(true);
+static mut G_FS_ACCESS_LOG_FSPSRV: Option<service::ServiceRef<fspsrv::FileSystemProxy>> = None;
+
+pub struct FsAccessLogLogger;
 
 impl Logger for FsAccessLogLogger {
     fn new() -> Self {
-        Self { service: service::new_service_object() }
+        Self {}
     }
 
     fn log(&mut self, metadata: &LogMetadata) {
         let msg = format_plain_string_log_impl(metadata, "FsAccessLog");
-        match self.service {
-            Ok(ref mut fspsrv) => {
-                let _ = fspsrv.get().output_access_log_to_sd_card(sf::Buffer::from_const(msg.as_ptr(), msg.len()));
-            },
-            _ => {}
+        unsafe {
+            let _lock = sync::ScopedLock::new(&mut G_FS_ACCESS_LOG_LOCK);
+            if G_FS_ACCESS_LOG_FSPSRV.is_none() {
+                G_FS_ACCESS_LOG_FSPSRV = service::get_service().ok();
+            }
+            if let Some(fspsrv) = G_FS_ACCESS_LOG_FSPSRV.as_mut() {
+                let _ = fspsrv.call(|fspsrv| fspsrv.output_access_log_to_sd_card(sf::InBuffer::from_array(msg.as_bytes())));
+            }
         }
     }
 }
@@ -96,55 +102,72 @@ use crate::service::lm;
 use crate::service::lm::ILogService;
 use crate::service::lm::ILogger;
 
-pub struct LmLogger {
-    service: Result<mem::Shared<lm::LogService>>,
-    logger: Result<mem::Shared<lm::Logger>>
+// The logger object is opened once too, and re-opened if its session gets closed (lm is only used to open it, so it isn't kept open)
+// Shared objects don't have an atomic refcount, thus the logger is only used with the lock held and never cloned
+static mut G_LM_LOGGER_LOCK: sync::Mutex = sync::Mutex::new(true);
+static mut G_LM_LOGGER: mem::Shared<lm::Logger> = mem::Shared::empty();
+
+fn open_lm_logger() -> Result<()> {
+    unsafe {
+        let logger_obj = service::call_service(|log_service: &mut lm::LogService| log_service.open_logger(sf::ProcessId::new()))?;
+        G_LM_LOGGER = logger_obj.to::<lm::Logger>();
+        Ok(())
+    }
+}
+
+fn call_lm_logger<F: Fn(&mut lm::Logger) -> Result<()>>(f: F) -> Result<()> {
+    unsafe {
+        let _lock = sync::ScopedLock::new(&mut G_LM_LOGGER_LOCK);
+        if G_LM_LOGGER.is_null() {
+            open_lm_logger()?;
+        }
+
+        match f(G_LM_LOGGER.get()) {
+            Err(rc) if service::is_session_closed(rc) => {
+                open_lm_logger()?;
+                f(G_LM_LOGGER.get())
+            },
+            rc => rc
+        }
+    }
 }
 
+pub struct LmLogger;
+
 impl Logger for LmLogger {
     fn new() -> Self {
-        let mut service = service::new_service_object::<lm::LogService>();
-        let logger = match service {
-            Ok(ref mut service_obj) => match service_obj.get().open_logger(sf::ProcessId::new()) {
-                Ok(logger_obj) => Ok(logger_obj.to::<lm::Logger>()),
-                Err(rc) => Err(rc),
-            },
-            Err(rc) => Err(rc),
-        };
-        Self { service, logger }
+        Self {}
     }
 
     fn log(&mut self, metadata: &LogMetadata) {
-        if self.service.is_ok() && self.logger.is_ok() {
-            match &mut self.logger {
-                Ok(ref mut logger) => {
-                    let mut log_packet = logpacket::LogPacket::new();
-
-                    if let Ok(process_id) = svc::get_process_id(svc::CURRENT_PROCESS_PSEUDO_HANDLE) {
-                        log_packet.set_process_id(process_id);
-                    }
-
-                    let cur_thread = thread::get_current_thread();
-                    if let Ok(thread_id) = cur_thread.get_id() {
-                        log_packet.set_thread_id(thread_id);
-                    }
-
-                    log_packet.set_file_name(String::from(metadata.file_name));
-                    log_packet.set_function_name(String::from(metadata.fn_name));
-                    log_packet.set_line_number(metadata.line_no);
-                    log_packet.set_module_name(String::from("aarch64-switch-rs"));
-                    log_packet.set_text_log(metadata.msg.clone());
-                    let thread_name = match cur_thread.name.get_str() {
-                        Ok(name) => name,
-                        _ => "<unknown>",
-                    };
-                    log_packet.set_thread_name(String::from(thread_name));
-                    for packet in log_packet.encode_packet() {
-                        let _ = logger.get().log(sf::Buffer::from_const(packet.as_ptr(), packet.len()));
-                    }
-                },
-                _ => {}
-            }
+        let mut log_packet = logpacket::LogPacket::new();
+
+        if let Ok(process_id) = svc::get_process_id(svc::BorrowedHandle::current_process()) {
+            log_packet.set_process_id(process_id);
         }
+
+        let cur_thread = thread::get_current_thread();
+        if let Ok(thread_id) = cur_thread.get_id() {
+            log_packet.set_thread_id(thread_id);
+        }
+
+        log_packet.set_file_name(String::from(metadata.file_name));
+        log_packet.set_function_name(String::from(metadata.fn_name));
+        log_packet.set_line_number(metadata.line_no);
+        log_packet.set_module_name(String::from("aarch64-switch-rs"));
+        log_packet.set_text_log(metadata.msg.clone());
+        let thread_name = match cur_thread.name.get_str() {
+            Ok(name) => name,
+            _ => "<unknown>",
+        };
+        log_packet.set_thread_name(String::from(thread_name));
+
+        let packets = log_packet.encode_packet();
+        let _ = call_lm_logger(|logger| {
+            for packet in packets.iter() {
+                logger.log(sf::InBuffer::from_array(packet))?;
+            }
+            Ok(())
+        });
     }
-}
\ No newline at end of file
+}
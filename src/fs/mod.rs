@@ -0,0 +1,1109 @@
+use crate::result::*;
+use crate::results;
+use crate::mem;
+use crate::service;
+use crate::service::fspsrv;
+use crate::service::fspsrv::IFileSystemProxy;
+use crate::sync;
+use crate::ipc::sf;
+use alloc::vec::Vec;
+use alloc::string::String;
+use core::mem as cmem;
+
+enum PathSegmentType {
+    Invalid,
+    Root,
+    Normal
+}
+
+struct PathSegment {
+    name: String,
+    segment_type: PathSegmentType
+}
+
+impl PathSegment {
+    pub const fn from(name: String, segment_type: PathSegmentType) -> Self {
+        Self { name, segment_type }
+    }
+
+    pub const fn new() -> Self {
+        Self::from(String::new(), PathSegmentType::Invalid)
+    }
+}
+
+type UnpackedPath = Vec<PathSegment>;
+
+fn unpack_path_impl(path: String) -> UnpackedPath {
+    let mut unpacked_path: UnpackedPath = UnpackedPath::new();
+
+    for sub_path in path.split('/') {
+        let mut cur_segment = PathSegment::new();
+        if sub_path.ends_with(':') {
+            cur_segment.segment_type = PathSegmentType::Root;
+            cur_segment.name = String::from(sub_path);
+            unpacked_path.push(cur_segment);
+        }
+        else if sub_path == ".." {
+            unpacked_path.pop();
+        }
+        else {
+            cur_segment.segment_type = PathSegmentType::Normal;
+            cur_segment.name = String::from(sub_path);
+            unpacked_path.push(cur_segment);
+        }
+    }
+
+    unpacked_path
+}
+
+fn unpack_path(path: String) -> Result<UnpackedPath> {
+    let unpacked_path = unpack_path_impl(path);
+    result_return_if!(unpacked_path.is_empty(), 0xBAD);
+    Ok(unpacked_path)
+}
+
+fn pack_path(unpacked_path: UnpackedPath, add_root: bool) -> String {
+    let mut path = String::new();
+    if !add_root {
+        path.push('/');
+    }
+    
+    for path_segment in unpacked_path {
+        match path_segment.segment_type {
+            PathSegmentType::Root => {
+                if add_root {
+                    path = format!("{}{}/", path, path_segment.name);
+                }
+            },
+            PathSegmentType::Normal => path = format!("{}{}/", path, path_segment.name),
+            _ => {}
+        }
+    }
+    
+    // Minimum path must be "/"
+    if path.len() > 1 {
+        path.pop();
+    }
+
+    path
+}
+
+pub use fspsrv::FileAttribute;
+pub use fspsrv::DirectoryEntry;
+pub use fspsrv::DirectoryEntryType;
+pub use fspsrv::DirectoryOpenMode;
+pub use fspsrv::FileOpenMode;
+pub use fspsrv::FileReadOption;
+pub use fspsrv::FileWriteOption;
+pub use fspsrv::FileTimeStampRaw;
+pub use fspsrv::QueryId;
+pub use fspsrv::OperationId;
+pub use fspsrv::RangeInfo;
+
+// Any filesystem implementing these can be mounted, not only fs-srv ones
+// Paths given to filesystems are already processed, thus they're always absolute within the filesystem (like "/dir/file")
+
+pub trait IFile {
+    fn read(&mut self, offset: usize, out_buf: &mut [u8], option: FileReadOption) -> Result<usize>;
+    fn write(&mut self, offset: usize, buf: &[u8], option: FileWriteOption) -> Result<()>;
+    fn flush(&mut self) -> Result<()>;
+    fn set_size(&mut self, size: usize) -> Result<()>;
+    fn get_size(&mut self) -> Result<usize>;
+    fn operate_range(&mut self, operation_id: OperationId, offset: usize, size: usize) -> Result<RangeInfo>;
+}
+
+pub trait IDirectory {
+    fn read(&mut self, out_entries: &mut [DirectoryEntry]) -> Result<usize>;
+    fn get_entry_count(&mut self) -> Result<usize>;
+}
+
+pub trait IFileSystem {
+    fn create_file(&mut self, path: &str, attribute: FileAttribute, size: usize) -> Result<()>;
+    fn delete_file(&mut self, path: &str) -> Result<()>;
+    fn create_directory(&mut self, path: &str) -> Result<()>;
+    fn delete_directory(&mut self, path: &str) -> Result<()>;
+    fn delete_directory_recursively(&mut self, path: &str) -> Result<()>;
+    fn get_entry_type(&mut self, path: &str) -> Result<DirectoryEntryType>;
+    fn open_file(&mut self, path: &str, mode: FileOpenMode) -> Result<mem::Shared<dyn IFile>>;
+    fn open_directory(&mut self, path: &str, mode: DirectoryOpenMode) -> Result<mem::Shared<dyn IDirectory>>;
+    fn rename_file(&mut self, old_path: &str, new_path: &str) -> Result<()>;
+    fn rename_directory(&mut self, old_path: &str, new_path: &str) -> Result<()>;
+    fn clean_directory_recursively(&mut self, path: &str) -> Result<()>;
+    fn commit(&mut self) -> Result<()>;
+    fn get_free_space_size(&mut self, path: &str) -> Result<usize>;
+    fn get_total_space_size(&mut self, path: &str) -> Result<usize>;
+    fn get_file_time_stamp_raw(&mut self, path: &str) -> Result<FileTimeStampRaw>;
+    fn query_entry(&mut self, path: &str, query_id: QueryId, in_buf: &[u8], out_buf: &mut [u8]) -> Result<()>;
+}
+
+impl IFile for fspsrv::File {
+    fn read(&mut self, offset: usize, out_buf: &mut [u8], option: FileReadOption) -> Result<usize> {
+        fspsrv::IFile::read(self, option, offset, out_buf.len(), sf::OutBuffer::from_mut_array(out_buf))
+    }
+
+    fn write(&mut self, offset: usize, buf: &[u8], option: FileWriteOption) -> Result<()> {
+        fspsrv::IFile::write(self, option, offset, buf.len(), sf::InBuffer::from_array(buf))
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        fspsrv::IFile::flush(self)
+    }
+
+    fn set_size(&mut self, size: usize) -> Result<()> {
+        fspsrv::IFile::set_size(self, size)
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        fspsrv::IFile::get_size(self)
+    }
+
+    fn operate_range(&mut self, operation_id: OperationId, offset: usize, size: usize) -> Result<RangeInfo> {
+        fspsrv::IFile::operate_range(self, operation_id, offset, size)
+    }
+}
+
+impl IDirectory for fspsrv::Directory {
+    fn read(&mut self, out_entries: &mut [DirectoryEntry]) -> Result<usize> {
+        let read_count = fspsrv::IDirectory::read(self, sf::OutBuffer::from_mut_array(out_entries))?;
+        Ok(read_count as usize)
+    }
+
+    fn get_entry_count(&mut self) -> Result<usize> {
+        let count = fspsrv::IDirectory::get_entry_count(self)?;
+        Ok(count as usize)
+    }
+}
+
+impl IFileSystem for fspsrv::FileSystem {
+    fn create_file(&mut self, path: &str, attribute: FileAttribute, size: usize) -> Result<()> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::create_file(self, attribute, size, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn delete_file(&mut self, path: &str) -> Result<()> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::delete_file(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn create_directory(&mut self, path: &str) -> Result<()> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::create_directory(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn delete_directory(&mut self, path: &str) -> Result<()> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::delete_directory(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn delete_directory_recursively(&mut self, path: &str) -> Result<()> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::delete_directory_recursively(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn get_entry_type(&mut self, path: &str) -> Result<DirectoryEntryType> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::get_entry_type(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn open_file(&mut self, path: &str, mode: FileOpenMode) -> Result<mem::Shared<dyn IFile>> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        let file = fspsrv::IFileSystem::open_file(self, mode, sf::InBuffer::from_var(&path_buf))?.to::<fspsrv::File>();
+        Ok(file)
+    }
+
+    fn open_directory(&mut self, path: &str, mode: DirectoryOpenMode) -> Result<mem::Shared<dyn IDirectory>> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        let dir = fspsrv::IFileSystem::open_directory(self, mode, sf::InBuffer::from_var(&path_buf))?.to::<fspsrv::Directory>();
+        Ok(dir)
+    }
+
+    fn rename_file(&mut self, old_path: &str, new_path: &str) -> Result<()> {
+        let old_path_buf = fspsrv::Path::from_str(old_path)?;
+        let new_path_buf = fspsrv::Path::from_str(new_path)?;
+        fspsrv::IFileSystem::rename_file(self, sf::InBuffer::from_var(&old_path_buf), sf::InBuffer::from_var(&new_path_buf))
+    }
+
+    fn rename_directory(&mut self, old_path: &str, new_path: &str) -> Result<()> {
+        let old_path_buf = fspsrv::Path::from_str(old_path)?;
+        let new_path_buf = fspsrv::Path::from_str(new_path)?;
+        fspsrv::IFileSystem::rename_directory(self, sf::InBuffer::from_var(&old_path_buf), sf::InBuffer::from_var(&new_path_buf))
+    }
+
+    fn clean_directory_recursively(&mut self, path: &str) -> Result<()> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::clean_directory_recursively(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        fspsrv::IFileSystem::commit(self)
+    }
+
+    fn get_free_space_size(&mut self, path: &str) -> Result<usize> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::get_free_space_size(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn get_total_space_size(&mut self, path: &str) -> Result<usize> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::get_total_space_size(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn get_file_time_stamp_raw(&mut self, path: &str) -> Result<FileTimeStampRaw> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::get_file_time_stamp_raw(self, sf::InBuffer::from_var(&path_buf))
+    }
+
+    fn query_entry(&mut self, path: &str, query_id: QueryId, in_buf: &[u8], out_buf: &mut [u8]) -> Result<()> {
+        let path_buf = fspsrv::Path::from_str(path)?;
+        fspsrv::IFileSystem::query_entry(self, query_id, sf::OutBuffer::from_mut_array(out_buf), sf::InBuffer::from_array(in_buf), sf::InBuffer::from_var(&path_buf))
+    }
+}
+
+// Read-only data sources for filesystem formats we parse ourselves (see romfs)
+
+pub trait IStorage {
+    fn read(&mut self, offset: usize, out_buf: &mut [u8]) -> Result<usize>;
+    fn get_size(&mut self) -> Result<usize>;
+}
+
+// A region of a file, like a section of an executable
+
+pub struct FileStorage {
+    file: mem::Shared<dyn IFile>,
+    offset: usize,
+    size: usize
+}
+
+impl FileStorage {
+    pub fn new(file: mem::Shared<dyn IFile>, offset: usize, size: usize) -> Self {
+        Self { file, offset, size }
+    }
+
+    pub fn from_file(file: mem::Shared<dyn IFile>) -> Result<Self> {
+        let size = file.get().get_size()?;
+        Ok(Self::new(file, 0, size))
+    }
+}
+
+impl IStorage for FileStorage {
+    fn read(&mut self, offset: usize, out_buf: &mut [u8]) -> Result<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+
+        let read_size = out_buf.len().min(self.size - offset);
+        self.file.get().read(self.offset + offset, &mut out_buf[..read_size], FileReadOption::None())
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        Ok(self.size)
+    }
+}
+
+// Data already in memory, like images embedded in the executable with include_bytes!
+
+pub struct MemoryStorage {
+    data: &'static [u8]
+}
+
+impl MemoryStorage {
+    pub const fn new(data: &'static [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl IStorage for MemoryStorage {
+    fn read(&mut self, offset: usize, out_buf: &mut [u8]) -> Result<usize> {
+        if offset >= self.data.len() {
+            return Ok(0);
+        }
+
+        let read_size = out_buf.len().min(self.data.len() - offset);
+        out_buf[..read_size].copy_from_slice(&self.data[offset..offset + read_size]);
+        Ok(read_size)
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        Ok(self.data.len())
+    }
+}
+
+// A region of another storage, like an NRO asset section (see nro)
+
+pub struct SubStorage {
+    storage: mem::Shared<dyn IStorage>,
+    offset: usize,
+    size: usize
+}
+
+impl SubStorage {
+    pub fn new(storage: mem::Shared<dyn IStorage>, offset: usize, size: usize) -> Self {
+        Self { storage, offset, size }
+    }
+}
+
+impl IStorage for SubStorage {
+    fn read(&mut self, offset: usize, out_buf: &mut [u8]) -> Result<usize> {
+        if offset >= self.size {
+            return Ok(0);
+        }
+
+        let read_size = out_buf.len().min(self.size - offset);
+        self.storage.get().read(self.offset + offset, &mut out_buf[..read_size])
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        Ok(self.size)
+    }
+}
+
+struct Device {
+    root_name: PathSegment,
+    fs: mem::Shared<dyn IFileSystem>
+}
+
+impl Device {
+    pub fn from(root_name: PathSegment, fs: mem::Shared<dyn IFileSystem>) -> Self {
+        Self { root_name, fs }
+    }
+}
+
+pub struct File {
+    file: mem::Shared<dyn IFile>,
+    offset: usize,
+    flush_on_write: bool,
+    needs_flush: bool
+}
+
+pub enum Whence {
+    Start,
+    Current,
+    End
+}
+
+impl File {
+    pub fn new(file: mem::Shared<dyn IFile>) -> Self {
+        Self { file, offset: 0, flush_on_write: true, needs_flush: false }
+    }
+
+    pub fn get_file(&self) -> mem::Shared<dyn IFile> {
+        self.file.clone()
+    }
+
+    pub fn get_size(&mut self) -> Result<usize> {
+        self.file.get().get_size()
+    }
+
+    // Shrinks or extends (with zeros) the file, the current offset is left as is
+    pub fn set_size(&mut self, size: usize) -> Result<()> {
+        self.file.get().set_size(size)
+    }
+
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    // Seeking past the end is fine (writes there extend the file if it was opened for appending), but not before the start
+    pub fn seek(&mut self, offset: isize, whence: Whence) -> Result<usize> {
+        let base_offset = match whence {
+            Whence::Start => 0,
+            Whence::Current => self.offset,
+            Whence::End => self.get_size()?
+        };
+        let new_offset = match offset < 0 {
+            true => base_offset.checked_sub(offset.unsigned_abs()),
+            false => base_offset.checked_add(offset as usize)
+        };
+
+        self.offset = new_offset.ok_or(results::lib::fs::ResultInvalidSeekOffset::make())?;
+        Ok(self.offset)
+    }
+
+    pub unsafe fn read<T>(&mut self, buf: *mut T, size: usize) -> Result<usize> {
+        self.read_array(core::slice::from_raw_parts_mut(buf as *mut u8, size))
+    }
+
+    pub fn read_array<T>(&mut self, arr: &mut [T]) -> Result<usize> {
+        let size = arr.len() * cmem::size_of::<T>();
+        let buf = unsafe { core::slice::from_raw_parts_mut(arr.as_mut_ptr() as *mut u8, size) };
+        let read_size = self.file.get().read(self.offset, buf, FileReadOption::None())?;
+        // Reads near the end of the file might be shorter than requested
+        self.offset += read_size;
+        Ok(read_size)
+    }
+
+    pub fn read_val<T: Copy + Default>(&mut self) -> Result<T> {
+        let mut t: T = Default::default();
+        self.read_array(core::slice::from_mut(&mut t))?;
+        Ok(t)
+    }
+
+    // Flushing on every write is the safe default, but it's quite slow for many small writes (like log lines)
+    // Without it, data is flushed with flush() or when the file is dropped
+    pub fn set_flush_on_write(&mut self, flush_on_write: bool) {
+        self.flush_on_write = flush_on_write;
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.get().flush()?;
+        self.needs_flush = false;
+        Ok(())
+    }
+
+    pub unsafe fn write<T>(&mut self, buf: *const T, size: usize) -> Result<usize> {
+        self.write_array(core::slice::from_raw_parts(buf as *const u8, size))
+    }
+
+    pub fn write_array<T>(&mut self, arr: &[T]) -> Result<usize> {
+        let size = arr.len() * cmem::size_of::<T>();
+        let buf = unsafe { core::slice::from_raw_parts(arr.as_ptr() as *const u8, size) };
+        let option = match self.flush_on_write {
+            true => FileWriteOption::Flush(),
+            false => FileWriteOption::None()
+        };
+        self.file.get().write(self.offset, buf, option)?;
+        self.needs_flush = !self.flush_on_write;
+        self.offset += size;
+        // Writes are never partial: either everything was written or the command failed (like writing past the end without append mode)
+        Ok(size)
+    }
+
+    pub fn write_val<T: Copy>(&mut self, t: T) -> Result<usize> {
+        self.write_array(core::slice::from_ref(&t))
+    }
+
+    pub fn operate_range(&mut self, operation_id: OperationId, offset: usize, size: usize) -> Result<RangeInfo> {
+        self.file.get().operate_range(operation_id, offset, size)
+    }
+
+    pub fn invalidate_cache(&mut self) -> Result<()> {
+        let size = self.get_size()?;
+        self.operate_range(OperationId::InvalidateCache, 0, size)?;
+        Ok(())
+    }
+
+    pub fn query_range(&mut self, offset: usize, size: usize) -> Result<RangeInfo> {
+        self.operate_range(OperationId::QueryRange, offset, size)
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if self.needs_flush {
+            // Nothing to do if this fails, flush() should be used when errors matter
+            let _ = self.flush();
+        }
+    }
+}
+
+pub struct Directory {
+    dir: mem::Shared<dyn IDirectory>,
+    offset: usize,
+    entry_count: usize,
+    entries: Vec<DirectoryEntry>
+}
+
+impl Directory {
+    pub fn new(dir: mem::Shared<dyn IDirectory>) -> Result<Self> {
+        let entry_count = dir.get().get_entry_count()?;
+
+        Ok(Self { dir, offset: 0, entry_count, entries: Vec::new() })
+    }
+
+    fn refresh(&mut self) -> Result<()> {
+        if self.offset >= self.entries.len() {
+            let new_count = 16;
+            let mut new_entries: Vec<DirectoryEntry> = vec![unsafe { core::mem::zeroed() }; new_count];
+            let read_count = self.dir.get().read(&mut new_entries)?;
+            new_entries.truncate(read_count);
+
+            self.entries.append(&mut new_entries);
+        }
+
+        Ok(())
+    }
+
+    pub fn rewind(&mut self) -> Result<()> {
+        self.offset = 0;
+        self.refresh()
+    }
+
+    pub fn rel(&self) -> (usize, usize) {
+        (self.offset, self.entry_count)
+    }
+
+    pub fn next(&mut self) -> Result<Option<DirectoryEntry>> {
+        if self.offset >= self.entry_count {
+            Ok(None)
+        }
+        else {
+            self.refresh()?;
+            // The directory might have changed since we got the entry count
+            if self.offset >= self.entries.len() {
+                Ok(None)
+            }
+            else {
+                let entry = self.entries[self.offset];
+                self.offset += 1;
+                Ok(Some(entry))
+            }
+        }
+    }
+}
+
+static mut G_FSPSRV_SESSION: sync::Locked<mem::Shared<fspsrv::FileSystemProxy>> = sync::Locked::new(false, mem::Shared::empty());
+static mut G_DEVICES: sync::Locked<Vec<Device>> = sync::Locked::new(false, Vec::new());
+
+fn find_device_by_name(name: &PathSegment) -> Result<mem::Shared<dyn IFileSystem>> {
+    unsafe {
+        for device in G_DEVICES.get() {
+            if device.root_name.name == name.name {
+                return Ok(device.fs.clone());
+            }
+        }
+        Err(results::lib::fs::ResultDeviceNotFound::make())
+    }
+}
+
+pub fn initialize() -> Result<()> {
+    unsafe {
+        G_FSPSRV_SESSION.set(service::new_service_object()?);
+    }
+    Ok(())
+}
+
+pub fn is_initialized() -> bool {
+    unsafe {
+        !G_FSPSRV_SESSION.get().is_null()
+    }
+}
+
+pub fn finalize() {
+    unsafe {
+        G_DEVICES.get().clear();
+        G_FSPSRV_SESSION.get().reset();
+    }
+}
+
+pub fn mount(name: &str, fs: mem::Shared<dyn IFileSystem>) -> Result<()> {
+    let root_name = PathSegment::from(format!("{}:", name), PathSegmentType::Root);
+    unsafe {
+        G_DEVICES.get().push(Device::from(root_name, fs));
+    }
+
+    Ok(())
+}
+
+pub fn open_sd_card_filesystem() -> Result<mem::Shared<dyn IFileSystem>> {
+    result_return_unless!(is_initialized(), results::lib::ResultNotInitialized);
+
+    let sd_fs = unsafe { G_FSPSRV_SESSION.get().get().open_sd_card_filesystem()?.to::<fspsrv::FileSystem>() };
+    Ok(sd_fs)
+}
+
+pub fn mount_sd_card(name: &str) -> Result<()> {
+    let sd_fs = open_sd_card_filesystem()?;
+    mount(name, sd_fs)
+}
+
+pub fn unmount(name: &str) {
+    let root_name = format!("{}:", name);
+    unsafe {
+        G_DEVICES.get().retain(|dev| dev.root_name.name != root_name);
+    }
+}
+
+pub fn create_file(path: String, size: usize, attribute: FileAttribute) -> Result<()> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+    fs.get().create_file(&processed_path, attribute, size)
+}
+
+pub fn delete_file(path: String) -> Result<()> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+    fs.get().delete_file(&processed_path)
+}
+
+pub fn create_directory(path: String) -> Result<()> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+    fs.get().create_directory(&processed_path)
+}
+
+pub fn delete_directory(path: String) -> Result<()> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+    fs.get().delete_directory_recursively(&processed_path)
+}
+
+pub fn get_entry_type(path: String) -> Result<DirectoryEntryType> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+    fs.get().get_entry_type(&processed_path)
+}
+
+// Renames (or moves) a file or a directory within the same device
+// Filesystems don't replace existing entries, thus the new path must not exist
+pub fn rename(old_path: String, new_path: String) -> Result<()> {
+    let old_unpacked_path = unpack_path(old_path)?;
+    let new_unpacked_path = unpack_path(new_path)?;
+    result_return_unless!(old_unpacked_path.first().unwrap().name == new_unpacked_path.first().unwrap().name, results::lib::fs::ResultCrossDeviceRename);
+
+    let fs = find_device_by_name(old_unpacked_path.first().unwrap())?;
+    let processed_old_path = pack_path(old_unpacked_path, false);
+    let processed_new_path = pack_path(new_unpacked_path, false);
+    match fs.get().get_entry_type(&processed_old_path)? {
+        DirectoryEntryType::File => fs.get().rename_file(&processed_old_path, &processed_new_path),
+        DirectoryEntryType::Directory => fs.get().rename_directory(&processed_old_path, &processed_new_path)
+    }
+}
+
+// Deletes everything inside the directory, but not the directory itself
+pub fn clean_directory(path: String) -> Result<()> {
+    let (fs, processed_path) = format_path(path)?;
+    fs.get().clean_directory_recursively(&processed_path)
+}
+
+// Filesystems with journaling (like savedata) only keep changes once they're committed
+pub fn commit(path: String) -> Result<()> {
+    let (fs, _) = format_path(path)?;
+    fs.get().commit()
+}
+
+pub fn free_space(path: String) -> Result<usize> {
+    let (fs, processed_path) = format_path(path)?;
+    fs.get().get_free_space_size(&processed_path)
+}
+
+pub fn total_space(path: String) -> Result<usize> {
+    let (fs, processed_path) = format_path(path)?;
+    fs.get().get_total_space_size(&processed_path)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+    pub entry_type: DirectoryEntryType,
+    pub size: usize,
+    pub time_stamp: Option<FileTimeStampRaw>
+}
+
+impl Metadata {
+    pub fn is_file(&self) -> bool {
+        self.entry_type == DirectoryEntryType::File
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.entry_type == DirectoryEntryType::Directory
+    }
+}
+
+pub fn metadata(path: String) -> Result<Metadata> {
+    let (fs, processed_path) = format_path(path)?;
+    let entry_type = fs.get().get_entry_type(&processed_path)?;
+    match entry_type {
+        DirectoryEntryType::File => {
+            let size = fs.get().open_file(&processed_path, FileOpenMode::Read())?.get().get_size()?;
+            // Not every filesystem keeps timestamps (savedata doesn't, for instance)
+            let time_stamp = fs.get().get_file_time_stamp_raw(&processed_path).ok();
+            Ok(Metadata { entry_type, size, time_stamp })
+        },
+        DirectoryEntryType::Directory => Ok(Metadata { entry_type, size: 0, time_stamp: None })
+    }
+}
+
+bit_enum! {
+    FileOpenOption (u32) {
+        Create = bit!(0),
+        Read = bit!(1),
+        Write = bit!(2),
+        Append = bit!(3)
+    }
+}
+
+fn convert_file_open_option(option: FileOpenOption) -> FileOpenMode {
+    let mut mode = FileOpenMode::None();
+    if option.contains(FileOpenOption::Read()) {
+        mode |= FileOpenMode::Read();
+    }
+    if option.contains(FileOpenOption::Write()) {
+        mode |= FileOpenMode::Write();
+    }
+    if option.contains(FileOpenOption::Append()) {
+        mode |= FileOpenMode::Append();
+    }
+    mode
+}
+
+pub fn open_file(path: String, option: FileOpenOption) -> Result<File> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+
+    let mode = convert_file_open_option(option);
+    let file = match fs.get().open_file(&processed_path, mode) {
+        Ok(file) => file,
+        Err(rc) => {
+            if results::fs::ResultPathNotFound::matches(rc) && option.contains(FileOpenOption::Create()) {
+                // Create the file if it doesn't exist and we were told to do so
+                fs.get().create_file(&processed_path, FileAttribute::None(), 0)?;
+                fs.get().open_file(&processed_path, mode)?
+            }
+            else {
+                return Err(rc);
+            }
+        }
+    };
+    let offset : usize = match option.contains(FileOpenOption::Append()) {
+        true => file.get().get_size().unwrap_or(0),
+        false => 0
+    };
+
+    let mut file = File::new(file);
+    file.offset = offset;
+    Ok(file)
+}
+
+pub fn open_directory(path: String, mode: DirectoryOpenMode) -> Result<Directory> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+
+    let dir = fs.get().open_directory(&processed_path, mode)?;
+    Directory::new(dir)
+}
+
+pub fn format_path(path: String) -> Result<(mem::Shared<dyn IFileSystem>, String)> {
+    let unpacked_path = unpack_path(path)?;
+    let fs = find_device_by_name(unpacked_path.first().unwrap())?;
+    let processed_path = pack_path(unpacked_path, false);
+    
+    Ok((fs, processed_path))
+}
+
+pub mod romfs;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Devices are global, thus tests mounting them can't run at the same time
+    static DEVICES_LOCK: Mutex<()> = Mutex::new(());
+
+    fn get_parent_path(path: &str) -> &str {
+        match path.rfind('/') {
+            Some(0) | None => "/",
+            Some(idx) => &path[..idx]
+        }
+    }
+
+    fn is_within(path: &str, dir_path: &str) -> bool {
+        (dir_path == "/") || path.strip_prefix(dir_path).map_or(false, |sub_path| sub_path.starts_with('/'))
+    }
+
+    struct MemoryFile {
+        data: mem::Shared<Vec<u8>>
+    }
+
+    impl IFile for MemoryFile {
+        fn read(&mut self, offset: usize, out_buf: &mut [u8], _option: FileReadOption) -> Result<usize> {
+            let data = self.data.get();
+            if offset >= data.len() {
+                return Ok(0);
+            }
+
+            let read_size = out_buf.len().min(data.len() - offset);
+            out_buf[..read_size].copy_from_slice(&data[offset..offset + read_size]);
+            Ok(read_size)
+        }
+
+        fn write(&mut self, offset: usize, buf: &[u8], _option: FileWriteOption) -> Result<()> {
+            let data = self.data.get();
+            if (offset + buf.len()) > data.len() {
+                data.resize(offset + buf.len(), 0);
+            }
+            data[offset..offset + buf.len()].copy_from_slice(buf);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_size(&mut self, size: usize) -> Result<()> {
+            self.data.get().resize(size, 0);
+            Ok(())
+        }
+
+        fn get_size(&mut self) -> Result<usize> {
+            Ok(self.data.get().len())
+        }
+
+        fn operate_range(&mut self, _operation_id: OperationId, _offset: usize, _size: usize) -> Result<RangeInfo> {
+            Err(results::lib::ResultNotSupported::make())
+        }
+    }
+
+    struct MemoryDirectory {
+        entries: Vec<DirectoryEntry>,
+        offset: usize
+    }
+
+    impl IDirectory for MemoryDirectory {
+        fn read(&mut self, out_entries: &mut [DirectoryEntry]) -> Result<usize> {
+            let read_count = out_entries.len().min(self.entries.len() - self.offset);
+            out_entries[..read_count].copy_from_slice(&self.entries[self.offset..self.offset + read_count]);
+            self.offset += read_count;
+            Ok(read_count)
+        }
+
+        fn get_entry_count(&mut self) -> Result<usize> {
+            Ok(self.entries.len())
+        }
+    }
+
+    // Entries by path: directories have no data, while files share theirs with every file opened for them
+    struct MemoryFileSystem {
+        entries: Vec<(String, Option<mem::Shared<Vec<u8>>>)>
+    }
+
+    impl MemoryFileSystem {
+        fn new() -> Self {
+            Self { entries: vec![(String::from("/"), None)] }
+        }
+
+        fn find_entry(&self, path: &str) -> Result<usize> {
+            self.entries.iter().position(|(entry_path, _)| entry_path == path).ok_or_else(results::fs::ResultPathNotFound::make)
+        }
+
+        fn add_entry(&mut self, path: &str, data: Option<mem::Shared<Vec<u8>>>) -> Result<()> {
+            result_return_if!(self.find_entry(path).is_ok(), results::fs::ResultPathAlreadyExists);
+            result_return_unless!(self.get_entry_type(get_parent_path(path))? == DirectoryEntryType::Directory, results::fs::ResultPathNotFound);
+            self.entries.push((String::from(path), data));
+            Ok(())
+        }
+
+        fn rename_entry(&mut self, old_path: &str, new_path: &str) -> Result<()> {
+            self.find_entry(old_path)?;
+            result_return_if!(self.find_entry(new_path).is_ok(), results::fs::ResultPathAlreadyExists);
+            for (entry_path, _) in self.entries.iter_mut() {
+                if (entry_path == old_path) || is_within(entry_path, old_path) {
+                    *entry_path = format!("{}{}", new_path, &entry_path[old_path.len()..]);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl IFileSystem for MemoryFileSystem {
+        fn create_file(&mut self, path: &str, _attribute: FileAttribute, size: usize) -> Result<()> {
+            self.add_entry(path, Some(mem::Shared::new(vec![0; size])))
+        }
+
+        fn delete_file(&mut self, path: &str) -> Result<()> {
+            result_return_unless!(self.get_entry_type(path)? == DirectoryEntryType::File, results::fs::ResultPathNotFound);
+            self.entries.retain(|(entry_path, _)| entry_path != path);
+            Ok(())
+        }
+
+        fn create_directory(&mut self, path: &str) -> Result<()> {
+            self.add_entry(path, None)
+        }
+
+        fn delete_directory(&mut self, path: &str) -> Result<()> {
+            self.delete_directory_recursively(path)
+        }
+
+        fn delete_directory_recursively(&mut self, path: &str) -> Result<()> {
+            result_return_unless!(self.get_entry_type(path)? == DirectoryEntryType::Directory, results::fs::ResultPathNotFound);
+            self.entries.retain(|(entry_path, _)| (entry_path != path) && !is_within(entry_path, path));
+            Ok(())
+        }
+
+        fn get_entry_type(&mut self, path: &str) -> Result<DirectoryEntryType> {
+            match self.entries[self.find_entry(path)?].1 {
+                Some(_) => Ok(DirectoryEntryType::File),
+                None => Ok(DirectoryEntryType::Directory)
+            }
+        }
+
+        fn open_file(&mut self, path: &str, _mode: FileOpenMode) -> Result<mem::Shared<dyn IFile>> {
+            match self.entries[self.find_entry(path)?].1.as_ref() {
+                Some(data) => Ok(mem::Shared::new(MemoryFile { data: data.clone() })),
+                None => Err(results::fs::ResultPathNotFound::make())
+            }
+        }
+
+        fn open_directory(&mut self, path: &str, mode: DirectoryOpenMode) -> Result<mem::Shared<dyn IDirectory>> {
+            result_return_unless!(self.get_entry_type(path)? == DirectoryEntryType::Directory, results::fs::ResultPathNotFound);
+
+            let mut entries: Vec<DirectoryEntry> = Vec::new();
+            for (entry_path, data) in self.entries.iter() {
+                if (entry_path == path) || (get_parent_path(entry_path) != path) {
+                    continue;
+                }
+
+                let (entry_type, file_size) = match data {
+                    Some(data) => (DirectoryEntryType::File, data.get().len()),
+                    None => (DirectoryEntryType::Directory, 0)
+                };
+                let included = match entry_type {
+                    DirectoryEntryType::File => mode.contains(DirectoryOpenMode::ReadFiles()),
+                    DirectoryEntryType::Directory => mode.contains(DirectoryOpenMode::ReadDirectories())
+                };
+                if included {
+                    let name = fspsrv::Path::from_str(&entry_path[entry_path.rfind('/').unwrap() + 1..])?;
+                    entries.push(DirectoryEntry { name, attr: 0, pad: [0; 2], entry_type, pad_2: [0; 3], file_size });
+                }
+            }
+            Ok(mem::Shared::new(MemoryDirectory { entries, offset: 0 }))
+        }
+
+        fn rename_file(&mut self, old_path: &str, new_path: &str) -> Result<()> {
+            self.rename_entry(old_path, new_path)
+        }
+
+        fn rename_directory(&mut self, old_path: &str, new_path: &str) -> Result<()> {
+            self.rename_entry(old_path, new_path)
+        }
+
+        fn clean_directory_recursively(&mut self, path: &str) -> Result<()> {
+            result_return_unless!(self.get_entry_type(path)? == DirectoryEntryType::Directory, results::fs::ResultPathNotFound);
+            self.entries.retain(|(entry_path, _)| !is_within(entry_path, path) || (entry_path == "/"));
+            Ok(())
+        }
+
+        fn commit(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_free_space_size(&mut self, _path: &str) -> Result<usize> {
+            Ok(usize::MAX)
+        }
+
+        fn get_total_space_size(&mut self, _path: &str) -> Result<usize> {
+            Ok(self.entries.iter().filter_map(|(_, data)| data.as_ref()).map(|data| data.get().len()).sum())
+        }
+
+        fn get_file_time_stamp_raw(&mut self, _path: &str) -> Result<FileTimeStampRaw> {
+            Err(results::lib::ResultNotSupported::make())
+        }
+
+        fn query_entry(&mut self, _path: &str, _query_id: QueryId, _in_buf: &[u8], _out_buf: &mut [u8]) -> Result<()> {
+            Err(results::lib::ResultNotSupported::make())
+        }
+    }
+
+    fn with_memory_fs<F: FnOnce()>(name: &str, f: F) {
+        let _lock = DEVICES_LOCK.lock().unwrap();
+        mount(name, mem::Shared::new(MemoryFileSystem::new())).unwrap();
+        f();
+        unmount(name);
+    }
+
+    #[test]
+    fn path_processing() {
+        with_memory_fs("mem", || {
+            assert_eq!(format_path(String::from("mem:/")).unwrap().1, "/");
+            assert_eq!(format_path(String::from("mem:/dir/file")).unwrap().1, "/dir/file");
+            assert_eq!(format_path(String::from("mem:/dir/../other/file")).unwrap().1, "/other/file");
+            assert!(results::lib::fs::ResultDeviceNotFound::matches(format_path(String::from("other:/file")).err().unwrap()));
+        });
+        assert!(results::lib::fs::ResultDeviceNotFound::matches(format_path(String::from("mem:/file")).err().unwrap()));
+    }
+
+    #[test]
+    fn file_read_write() {
+        with_memory_fs("mem", || {
+            create_directory(String::from("mem:/dir")).unwrap();
+            let mut file = open_file(String::from("mem:/dir/file"), FileOpenOption::Create() | FileOpenOption::Read() | FileOpenOption::Write()).unwrap();
+            assert_eq!(file.write_array(b"hello").unwrap(), 5);
+            assert_eq!(file.write_val(0x21u8).unwrap(), 1);
+
+            assert_eq!(file.seek(0, Whence::Start).unwrap(), 0);
+            let mut data = [0u8; 0x10];
+            assert_eq!(file.read_array(&mut data).unwrap(), 6);
+            assert_eq!(&data[..6], b"hello!");
+            assert_eq!(file.read_array(&mut data).unwrap(), 0);
+
+            assert_eq!(file.seek(-1, Whence::End).unwrap(), 5);
+            assert_eq!(file.read_val::<u8>().unwrap(), 0x21);
+            assert!(results::lib::fs::ResultInvalidSeekOffset::matches(file.seek(-1, Whence::Start).err().unwrap()));
+
+            let file_metadata = metadata(String::from("mem:/dir/file")).unwrap();
+            assert!(file_metadata.is_file());
+            assert_eq!(file_metadata.size, 6);
+            assert!(file_metadata.time_stamp.is_none());
+            assert!(metadata(String::from("mem:/dir")).unwrap().is_directory());
+
+            // Appending starts at the end of the file
+            let mut file = open_file(String::from("mem:/dir/file"), FileOpenOption::Write() | FileOpenOption::Append()).unwrap();
+            assert_eq!(file.get_offset(), 6);
+            file.write_array(b"?").unwrap();
+            assert_eq!(file.get_size().unwrap(), 7);
+
+            assert!(results::fs::ResultPathNotFound::matches(open_file(String::from("mem:/dir/missing"), FileOpenOption::Read()).err().unwrap()));
+            assert!(results::fs::ResultPathNotFound::matches(create_file(String::from("mem:/missing/file"), 0, FileAttribute::None()).err().unwrap()));
+        });
+    }
+
+    #[test]
+    fn directory_listing() {
+        with_memory_fs("mem", || {
+            create_directory(String::from("mem:/dir")).unwrap();
+            create_directory(String::from("mem:/dir/sub")).unwrap();
+            // More entries than a single batch read by Directory
+            for i in 0..20 {
+                create_file(format!("mem:/dir/file_{}", i), i, FileAttribute::None()).unwrap();
+            }
+
+            let mut dir = open_directory(String::from("mem:/dir"), DirectoryOpenMode::ReadFiles() | DirectoryOpenMode::ReadDirectories()).unwrap();
+            assert_eq!(dir.rel(), (0, 21));
+            let mut file_count = 0;
+            while let Some(entry) = dir.next().unwrap() {
+                match entry.entry_type {
+                    DirectoryEntryType::File => {
+                        assert_eq!(entry.name.get_str().unwrap(), format!("file_{}", entry.file_size));
+                        file_count += 1;
+                    },
+                    DirectoryEntryType::Directory => assert_eq!(entry.name.get_str().unwrap(), "sub")
+                };
+            }
+            assert_eq!(file_count, 20);
+            assert_eq!(dir.rel(), (21, 21));
+
+            let mut dir = open_directory(String::from("mem:/dir/sub"), DirectoryOpenMode::ReadFiles()).unwrap();
+            assert!(dir.next().unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn rename_and_delete() {
+        with_memory_fs("mem", || {
+            create_directory(String::from("mem:/dir")).unwrap();
+            create_file(String::from("mem:/dir/file"), 4, FileAttribute::None()).unwrap();
+            create_file(String::from("mem:/other"), 0, FileAttribute::None()).unwrap();
+
+            rename(String::from("mem:/dir"), String::from("mem:/renamed")).unwrap();
+            assert_eq!(get_entry_type(String::from("mem:/renamed/file")).unwrap(), DirectoryEntryType::File);
+            assert!(get_entry_type(String::from("mem:/dir/file")).is_err());
+
+            assert!(results::fs::ResultPathAlreadyExists::matches(rename(String::from("mem:/renamed/file"), String::from("mem:/other")).err().unwrap()));
+            assert!(results::lib::fs::ResultCrossDeviceRename::matches(rename(String::from("mem:/other"), String::from("sd:/other")).err().unwrap()));
+
+            clean_directory(String::from("mem:/renamed")).unwrap();
+            assert_eq!(get_entry_type(String::from("mem:/renamed")).unwrap(), DirectoryEntryType::Directory);
+            assert!(get_entry_type(String::from("mem:/renamed/file")).is_err());
+
+            delete_directory(String::from("mem:/renamed")).unwrap();
+            delete_file(String::from("mem:/other")).unwrap();
+            let mut dir = open_directory(String::from("mem:/"), DirectoryOpenMode::ReadFiles() | DirectoryOpenMode::ReadDirectories()).unwrap();
+            assert!(dir.next().unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,635 @@
+use crate::result::*;
+use crate::results;
+use crate::mem;
+use crate::hbl;
+use crate::fs;
+use crate::nro;
+use crate::service::fspsrv;
+use alloc::vec::Vec;
+use core::mem as cmem;
+use core::ptr;
+
+// RomFS images are read-only filesystems made of directory/file hash tables (for path lookups), directory/file metadata tables and the file data itself
+// Only the tables are kept in memory, file data is read from the storage when files are read
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct Header {
+    pub header_size: u64,
+    pub dir_hash_table_offset: u64,
+    pub dir_hash_table_size: u64,
+    pub dir_meta_table_offset: u64,
+    pub dir_meta_table_size: u64,
+    pub file_hash_table_offset: u64,
+    pub file_hash_table_size: u64,
+    pub file_meta_table_offset: u64,
+    pub file_meta_table_size: u64,
+    pub file_data_offset: u64
+}
+const_assert!(cmem::size_of::<Header>() == 0x50);
+
+// Entries are followed by their name (not NUL-terminated), padded to 4 bytes
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct DirectoryEntryHeader {
+    pub parent_offset: u32,
+    pub sibling_offset: u32,
+    pub child_dir_offset: u32,
+    pub child_file_offset: u32,
+    pub hash_sibling_offset: u32,
+    pub name_len: u32
+}
+const_assert!(cmem::size_of::<DirectoryEntryHeader>() == 0x18);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct FileEntryHeader {
+    pub parent_offset: u32,
+    pub sibling_offset: u32,
+    pub data_offset: u64,
+    pub data_size: u64,
+    pub hash_sibling_offset: u32,
+    pub name_len: u32
+}
+const_assert!(cmem::size_of::<FileEntryHeader>() == 0x20);
+
+pub const EMPTY_ENTRY_OFFSET: u32 = u32::MAX;
+pub const ROOT_DIRECTORY_OFFSET: u32 = 0;
+
+pub const fn calculate_path_hash(parent_offset: u32, name: &[u8]) -> u32 {
+    let mut hash = parent_offset ^ 123456789;
+    let mut i = 0;
+    while i < name.len() {
+        hash = hash.rotate_right(5) ^ (name[i] as u32);
+        i += 1;
+    }
+    hash
+}
+
+fn read_storage_exact(storage: &mem::Shared<dyn fs::IStorage>, offset: usize, out_buf: &mut [u8]) -> Result<()> {
+    let read_size = storage.get().read(offset, out_buf)?;
+    result_return_unless!(read_size == out_buf.len(), results::lib::fs::ResultInvalidRomFs);
+    Ok(())
+}
+
+fn read_storage_table(storage: &mem::Shared<dyn fs::IStorage>, offset: u64, size: u64) -> Result<Vec<u8>> {
+    let storage_size = storage.get().get_size()? as u64;
+    result_return_unless!(offset.checked_add(size).map_or(false, |end_offset| end_offset <= storage_size), results::lib::fs::ResultInvalidRomFs);
+
+    let mut table: Vec<u8> = vec![0; size as usize];
+    read_storage_exact(storage, offset as usize, &mut table)?;
+    Ok(table)
+}
+
+fn read_hash_table(storage: &mem::Shared<dyn fs::IStorage>, offset: u64, size: u64) -> Result<Vec<u32>> {
+    let table = read_storage_table(storage, offset, size)?;
+    Ok(table.chunks_exact(cmem::size_of::<u32>()).map(|bucket| u32::from_le_bytes([bucket[0], bucket[1], bucket[2], bucket[3]])).collect())
+}
+
+fn read_entry<T: Copy>(meta_table: &[u8], offset: u32) -> Result<(T, &str)> {
+    let offset = offset as usize;
+    let name_offset = offset.checked_add(cmem::size_of::<T>()).ok_or(results::lib::fs::ResultInvalidRomFs::make())?;
+    result_return_unless!(name_offset <= meta_table.len(), results::lib::fs::ResultInvalidRomFs);
+
+    // The name length is always the last field of both entry types
+    let entry = unsafe { ptr::read_unaligned(meta_table.as_ptr().add(offset) as *const T) };
+    let name_len = unsafe { ptr::read_unaligned(meta_table.as_ptr().add(name_offset - cmem::size_of::<u32>()) as *const u32) } as usize;
+    let name_data = meta_table.get(name_offset..name_offset + name_len).ok_or(results::lib::fs::ResultInvalidRomFs::make())?;
+    let name = core::str::from_utf8(name_data).map_err(|_| results::lib::fs::ResultInvalidRomFs::make())?;
+    Ok((entry, name))
+}
+
+pub struct RomFs {
+    storage: mem::Shared<dyn fs::IStorage>,
+    header: Header,
+    dir_hash_table: Vec<u32>,
+    dir_meta_table: Vec<u8>,
+    file_hash_table: Vec<u32>,
+    file_meta_table: Vec<u8>
+}
+
+impl RomFs {
+    pub fn new(storage: mem::Shared<dyn fs::IStorage>) -> Result<Self> {
+        let mut header: Header = Default::default();
+        read_storage_exact(&storage, 0, unsafe { core::slice::from_raw_parts_mut(&mut header as *mut _ as *mut u8, cmem::size_of::<Header>()) })?;
+        result_return_unless!(header.header_size == cmem::size_of::<Header>() as u64, results::lib::fs::ResultInvalidRomFs);
+
+        let dir_hash_table = read_hash_table(&storage, header.dir_hash_table_offset, header.dir_hash_table_size)?;
+        let dir_meta_table = read_storage_table(&storage, header.dir_meta_table_offset, header.dir_meta_table_size)?;
+        let file_hash_table = read_hash_table(&storage, header.file_hash_table_offset, header.file_hash_table_size)?;
+        let file_meta_table = read_storage_table(&storage, header.file_meta_table_offset, header.file_meta_table_size)?;
+        // Every image has at least the root directory
+        result_return_if!(dir_hash_table.is_empty() || dir_meta_table.is_empty(), results::lib::fs::ResultInvalidRomFs);
+
+        Ok(Self { storage, header, dir_hash_table, dir_meta_table, file_hash_table, file_meta_table })
+    }
+
+    pub fn get_header(&self) -> Header {
+        self.header
+    }
+
+    pub fn get_directory_entry(&self, offset: u32) -> Result<(DirectoryEntryHeader, &str)> {
+        read_entry(&self.dir_meta_table, offset)
+    }
+
+    pub fn get_file_entry(&self, offset: u32) -> Result<(FileEntryHeader, &str)> {
+        read_entry(&self.file_meta_table, offset)
+    }
+
+    // Hash chains (and sibling lists) can't be longer than the entry count, which keeps corrupted images from making us loop forever
+
+    fn find_child_directory(&self, parent_offset: u32, name: &str) -> Result<Option<u32>> {
+        let hash = calculate_path_hash(parent_offset, name.as_bytes());
+        let mut cur_offset = self.dir_hash_table[hash as usize % self.dir_hash_table.len()];
+        let mut remaining_entries = self.dir_meta_table.len() / cmem::size_of::<DirectoryEntryHeader>();
+        while (cur_offset != EMPTY_ENTRY_OFFSET) && (remaining_entries > 0) {
+            let (entry, entry_name) = self.get_directory_entry(cur_offset)?;
+            if (entry.parent_offset == parent_offset) && (entry_name == name) {
+                return Ok(Some(cur_offset));
+            }
+            cur_offset = entry.hash_sibling_offset;
+            remaining_entries -= 1;
+        }
+        Ok(None)
+    }
+
+    fn find_child_file(&self, parent_offset: u32, name: &str) -> Result<Option<u32>> {
+        if self.file_hash_table.is_empty() {
+            return Ok(None);
+        }
+
+        let hash = calculate_path_hash(parent_offset, name.as_bytes());
+        let mut cur_offset = self.file_hash_table[hash as usize % self.file_hash_table.len()];
+        let mut remaining_entries = self.file_meta_table.len() / cmem::size_of::<FileEntryHeader>();
+        while (cur_offset != EMPTY_ENTRY_OFFSET) && (remaining_entries > 0) {
+            let (entry, entry_name) = self.get_file_entry(cur_offset)?;
+            if (entry.parent_offset == parent_offset) && (entry_name == name) {
+                return Ok(Some(cur_offset));
+            }
+            cur_offset = entry.hash_sibling_offset;
+            remaining_entries -= 1;
+        }
+        Ok(None)
+    }
+
+    fn find_directory_impl<'a>(&self, path_segments: impl Iterator<Item = &'a str>) -> Result<Option<u32>> {
+        let mut dir_offset = ROOT_DIRECTORY_OFFSET;
+        for segment in path_segments {
+            match self.find_child_directory(dir_offset, segment)? {
+                Some(child_dir_offset) => dir_offset = child_dir_offset,
+                None => return Ok(None)
+            };
+        }
+        Ok(Some(dir_offset))
+    }
+
+    pub fn find_directory(&self, path: &str) -> Result<Option<u32>> {
+        self.find_directory_impl(path.split('/').filter(|segment| !segment.is_empty()))
+    }
+
+    pub fn find_file(&self, path: &str) -> Result<Option<u32>> {
+        let trimmed_path = path.trim_end_matches('/');
+        let (dir_path, file_name) = match trimmed_path.rfind('/') {
+            Some(separator_idx) => (&trimmed_path[..separator_idx], &trimmed_path[separator_idx + 1..]),
+            None => ("", trimmed_path)
+        };
+        if file_name.is_empty() {
+            return Ok(None);
+        }
+
+        match self.find_directory(dir_path)? {
+            Some(dir_offset) => self.find_child_file(dir_offset, file_name),
+            None => Ok(None)
+        }
+    }
+
+    fn make_directory_entry(name: &str, entry_type: fs::DirectoryEntryType, file_size: usize) -> Result<fs::DirectoryEntry> {
+        Ok(fs::DirectoryEntry { name: fspsrv::Path::from_str(name)?, attr: 0, pad: [0; 2], entry_type, pad_2: [0; 3], file_size })
+    }
+
+    fn list_directory(&self, dir_offset: u32, mode: fs::DirectoryOpenMode) -> Result<Vec<fs::DirectoryEntry>> {
+        let (dir_entry, _) = self.get_directory_entry(dir_offset)?;
+        let mut entries: Vec<fs::DirectoryEntry> = Vec::new();
+
+        if mode.contains(fs::DirectoryOpenMode::ReadDirectories()) {
+            let mut cur_offset = dir_entry.child_dir_offset;
+            let mut remaining_entries = self.dir_meta_table.len() / cmem::size_of::<DirectoryEntryHeader>();
+            while (cur_offset != EMPTY_ENTRY_OFFSET) && (remaining_entries > 0) {
+                let (child_dir_entry, name) = self.get_directory_entry(cur_offset)?;
+                entries.push(Self::make_directory_entry(name, fs::DirectoryEntryType::Directory, 0)?);
+                cur_offset = child_dir_entry.sibling_offset;
+                remaining_entries -= 1;
+            }
+        }
+
+        if mode.contains(fs::DirectoryOpenMode::ReadFiles()) {
+            let mut cur_offset = dir_entry.child_file_offset;
+            let mut remaining_entries = self.file_meta_table.len() / cmem::size_of::<FileEntryHeader>();
+            while (cur_offset != EMPTY_ENTRY_OFFSET) && (remaining_entries > 0) {
+                let (child_file_entry, name) = self.get_file_entry(cur_offset)?;
+                let file_size = match mode.contains(fs::DirectoryOpenMode::NoFileSizes()) {
+                    true => 0,
+                    false => child_file_entry.data_size as usize
+                };
+                entries.push(Self::make_directory_entry(name, fs::DirectoryEntryType::File, file_size)?);
+                cur_offset = child_file_entry.sibling_offset;
+                remaining_entries -= 1;
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+impl fs::IFileSystem for RomFs {
+    fn create_file(&mut self, _path: &str, _attribute: fs::FileAttribute, _size: usize) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn delete_file(&mut self, _path: &str) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn create_directory(&mut self, _path: &str) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn delete_directory(&mut self, _path: &str) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn delete_directory_recursively(&mut self, _path: &str) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn get_entry_type(&mut self, path: &str) -> Result<fs::DirectoryEntryType> {
+        if self.find_file(path)?.is_some() {
+            Ok(fs::DirectoryEntryType::File)
+        }
+        else if self.find_directory(path)?.is_some() {
+            Ok(fs::DirectoryEntryType::Directory)
+        }
+        else {
+            Err(results::fs::ResultPathNotFound::make())
+        }
+    }
+
+    fn open_file(&mut self, path: &str, mode: fs::FileOpenMode) -> Result<mem::Shared<dyn fs::IFile>> {
+        result_return_if!(mode.contains(fs::FileOpenMode::Write()) || mode.contains(fs::FileOpenMode::Append()), results::lib::fs::ResultReadOnlyFileSystem);
+
+        let file_offset = self.find_file(path)?.ok_or(results::fs::ResultPathNotFound::make())?;
+        let (file_entry, _) = self.get_file_entry(file_offset)?;
+        // Entries come from the image itself: make sure the file data is actually within the storage
+        let data_offset = self.header.file_data_offset.checked_add(file_entry.data_offset).ok_or(results::lib::fs::ResultInvalidRomFs::make())?;
+        let data_end_offset = data_offset.checked_add(file_entry.data_size).ok_or(results::lib::fs::ResultInvalidRomFs::make())?;
+        result_return_unless!(data_end_offset <= self.storage.get().get_size()? as u64, results::lib::fs::ResultInvalidRomFs);
+
+        Ok(mem::Shared::new(RomFsFile { storage: self.storage.clone(), data_offset: data_offset as usize, data_size: file_entry.data_size as usize }))
+    }
+
+    fn open_directory(&mut self, path: &str, mode: fs::DirectoryOpenMode) -> Result<mem::Shared<dyn fs::IDirectory>> {
+        let dir_offset = self.find_directory(path)?.ok_or(results::fs::ResultPathNotFound::make())?;
+        let entries = self.list_directory(dir_offset, mode)?;
+        Ok(mem::Shared::new(RomFsDirectory { entries, offset: 0 }))
+    }
+
+    fn rename_file(&mut self, _old_path: &str, _new_path: &str) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn rename_directory(&mut self, _old_path: &str, _new_path: &str) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn clean_directory_recursively(&mut self, _path: &str) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        // Nothing to commit
+        Ok(())
+    }
+
+    fn get_free_space_size(&mut self, _path: &str) -> Result<usize> {
+        Ok(0)
+    }
+
+    fn get_total_space_size(&mut self, _path: &str) -> Result<usize> {
+        self.storage.get().get_size()
+    }
+
+    fn get_file_time_stamp_raw(&mut self, _path: &str) -> Result<fs::FileTimeStampRaw> {
+        Err(results::lib::ResultNotSupported::make())
+    }
+
+    fn query_entry(&mut self, _path: &str, _query_id: fs::QueryId, _in_buf: &[u8], _out_buf: &mut [u8]) -> Result<()> {
+        Err(results::lib::ResultNotSupported::make())
+    }
+}
+
+pub struct RomFsFile {
+    storage: mem::Shared<dyn fs::IStorage>,
+    data_offset: usize,
+    data_size: usize
+}
+
+impl fs::IFile for RomFsFile {
+    fn read(&mut self, offset: usize, out_buf: &mut [u8], _option: fs::FileReadOption) -> Result<usize> {
+        if offset >= self.data_size {
+            return Ok(0);
+        }
+
+        let read_size = out_buf.len().min(self.data_size - offset);
+        self.storage.get().read(self.data_offset + offset, &mut out_buf[..read_size])
+    }
+
+    fn write(&mut self, _offset: usize, _buf: &[u8], _option: fs::FileWriteOption) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // Nothing to flush
+        Ok(())
+    }
+
+    fn set_size(&mut self, _size: usize) -> Result<()> {
+        Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+    }
+
+    fn get_size(&mut self) -> Result<usize> {
+        Ok(self.data_size)
+    }
+
+    fn operate_range(&mut self, operation_id: fs::OperationId, _offset: usize, _size: usize) -> Result<fs::RangeInfo> {
+        match operation_id {
+            // There's no cache to invalidate, and the data is never encrypted
+            fs::OperationId::InvalidateCache | fs::OperationId::QueryRange => Ok(fs::RangeInfo::new()),
+            fs::OperationId::Clear | fs::OperationId::ClearSignature => Err(results::lib::fs::ResultReadOnlyFileSystem::make())
+        }
+    }
+}
+
+// Entries are gathered when the directory is opened, the image can't change anyway
+
+pub struct RomFsDirectory {
+    entries: Vec<fs::DirectoryEntry>,
+    offset: usize
+}
+
+impl fs::IDirectory for RomFsDirectory {
+    fn read(&mut self, out_entries: &mut [fs::DirectoryEntry]) -> Result<usize> {
+        let read_count = out_entries.len().min(self.entries.len() - self.offset);
+        out_entries[..read_count].copy_from_slice(&self.entries[self.offset..self.offset + read_count]);
+        self.offset += read_count;
+        Ok(read_count)
+    }
+
+    fn get_entry_count(&mut self) -> Result<usize> {
+        Ok(self.entries.len())
+    }
+}
+
+pub fn mount(name: &str, storage: mem::Shared<dyn fs::IStorage>) -> Result<()> {
+    let romfs = RomFs::new(storage)?;
+    fs::mount(name, mem::Shared::new(romfs))
+}
+
+// hbloader gives us the path of our NRO in the SD card, which we open ourselves (the program might not have mounted the SD card)
+
+fn open_self_romfs_storage() -> Result<fs::SubStorage> {
+    let program_path = hbl::get_program_path();
+    let sd_program_path = program_path.strip_prefix("sdmc:").ok_or(results::lib::fs::ResultProgramPathUnavailable::make())?;
+
+    let sd_fs = fs::open_sd_card_filesystem()?;
+    let program_file = sd_fs.get().open_file(sd_program_path, fs::FileOpenMode::Read())?;
+    let program_nro = nro::Nro::from_file(program_file)?;
+    program_nro.get_romfs_storage()
+}
+
+pub fn mount_self(name: &str) -> Result<()> {
+    let storage = open_self_romfs_storage()?;
+    mount(name, mem::Shared::new(storage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::IFileSystem;
+    use alloc::boxed::Box;
+    use alloc::string::String;
+
+    fn align_up(value: usize, align: usize) -> usize {
+        (value + align - 1) & !(align - 1)
+    }
+
+    fn push_value<T: Copy>(image: &mut Vec<u8>, value: T) {
+        image.extend_from_slice(unsafe { core::slice::from_raw_parts(&value as *const T as *const u8, cmem::size_of::<T>()) });
+    }
+
+    fn push_name(table: &mut Vec<u8>, name: &str) {
+        table.extend_from_slice(name.as_bytes());
+        table.resize(align_up(table.len(), 4), 0);
+    }
+
+    fn split_path(path: &str) -> (&str, &str) {
+        match path.rfind('/') {
+            Some(separator_idx) => (&path[..separator_idx], &path[separator_idx + 1..]),
+            None => ("", path)
+        }
+    }
+
+    // Parent directories must come before their children, the root directory ("") is always there
+    // Every directory/file hashes to one of bucket_count buckets, thus a single bucket chains every entry together
+    fn build_image(dir_paths: &[&str], files: &[(&str, &[u8])], bucket_count: usize) -> Vec<u8> {
+        let dir_paths: Vec<&str> = core::iter::once("").chain(dir_paths.iter().copied()).collect();
+        let find_dir = |path: &str| dir_paths.iter().position(|dir_path| *dir_path == path).unwrap();
+
+        let mut dir_offsets: Vec<u32> = Vec::new();
+        let mut cur_offset = 0;
+        for dir_path in dir_paths.iter() {
+            dir_offsets.push(cur_offset as u32);
+            cur_offset += cmem::size_of::<DirectoryEntryHeader>() + align_up(split_path(dir_path).1.len(), 4);
+        }
+        let mut file_offsets: Vec<u32> = Vec::new();
+        let mut cur_offset = 0;
+        for (file_path, _) in files.iter() {
+            file_offsets.push(cur_offset as u32);
+            cur_offset += cmem::size_of::<FileEntryHeader>() + align_up(split_path(file_path).1.len(), 4);
+        }
+
+        // The root directory is its own parent, but not its own child
+        let dir_parents: Vec<usize> = dir_paths.iter().map(|dir_path| match dir_path.is_empty() {
+            true => usize::MAX,
+            false => find_dir(split_path(dir_path).0)
+        }).collect();
+        let file_parents: Vec<usize> = files.iter().map(|(file_path, _)| find_dir(split_path(file_path).0)).collect();
+        let first_of = |parents: &[usize], offsets: &[u32], parent: usize, after: Option<usize>| -> u32 {
+            let start = after.map_or(0, |after| after + 1);
+            (start..parents.len()).find(|i| parents[*i] == parent).map_or(EMPTY_ENTRY_OFFSET, |i| offsets[i])
+        };
+
+        let mut dir_hash_table = vec![EMPTY_ENTRY_OFFSET; bucket_count];
+        let mut dir_meta_table: Vec<u8> = Vec::new();
+        for (i, dir_path) in dir_paths.iter().enumerate() {
+            let name = split_path(dir_path).1;
+            let parent_offset = dir_offsets.get(dir_parents[i]).copied().unwrap_or(ROOT_DIRECTORY_OFFSET);
+            let bucket = calculate_path_hash(parent_offset, name.as_bytes()) as usize % bucket_count;
+            let entry = DirectoryEntryHeader {
+                parent_offset,
+                sibling_offset: first_of(&dir_parents, &dir_offsets, dir_parents[i], Some(i)),
+                child_dir_offset: first_of(&dir_parents, &dir_offsets, i, None),
+                child_file_offset: first_of(&file_parents, &file_offsets, i, None),
+                hash_sibling_offset: dir_hash_table[bucket],
+                name_len: name.len() as u32
+            };
+            dir_hash_table[bucket] = dir_offsets[i];
+            push_value(&mut dir_meta_table, entry);
+            push_name(&mut dir_meta_table, name);
+        }
+
+        let mut file_hash_table = vec![EMPTY_ENTRY_OFFSET; bucket_count];
+        let mut file_meta_table: Vec<u8> = Vec::new();
+        let mut file_data: Vec<u8> = Vec::new();
+        for (i, (file_path, data)) in files.iter().enumerate() {
+            let name = split_path(file_path).1;
+            let parent_offset = dir_offsets[file_parents[i]];
+            let bucket = calculate_path_hash(parent_offset, name.as_bytes()) as usize % bucket_count;
+            let entry = FileEntryHeader {
+                parent_offset,
+                sibling_offset: first_of(&file_parents, &file_offsets, file_parents[i], Some(i)),
+                data_offset: file_data.len() as u64,
+                data_size: data.len() as u64,
+                hash_sibling_offset: file_hash_table[bucket],
+                name_len: name.len() as u32
+            };
+            file_hash_table[bucket] = file_offsets[i];
+            push_value(&mut file_meta_table, entry);
+            push_name(&mut file_meta_table, name);
+            file_data.extend_from_slice(data);
+            file_data.resize(align_up(file_data.len(), 0x10), 0);
+        }
+
+        let dir_hash_table_offset = cmem::size_of::<Header>();
+        let dir_meta_table_offset = dir_hash_table_offset + bucket_count * cmem::size_of::<u32>();
+        let file_hash_table_offset = dir_meta_table_offset + dir_meta_table.len();
+        let file_meta_table_offset = file_hash_table_offset + bucket_count * cmem::size_of::<u32>();
+        let file_data_offset = align_up(file_meta_table_offset + file_meta_table.len(), 0x10);
+        let header = Header {
+            header_size: cmem::size_of::<Header>() as u64,
+            dir_hash_table_offset: dir_hash_table_offset as u64,
+            dir_hash_table_size: (bucket_count * cmem::size_of::<u32>()) as u64,
+            dir_meta_table_offset: dir_meta_table_offset as u64,
+            dir_meta_table_size: dir_meta_table.len() as u64,
+            file_hash_table_offset: file_hash_table_offset as u64,
+            file_hash_table_size: (bucket_count * cmem::size_of::<u32>()) as u64,
+            file_meta_table_offset: file_meta_table_offset as u64,
+            file_meta_table_size: file_meta_table.len() as u64,
+            file_data_offset: file_data_offset as u64
+        };
+
+        let mut image: Vec<u8> = Vec::new();
+        push_value(&mut image, header);
+        dir_hash_table.iter().for_each(|bucket| push_value(&mut image, *bucket));
+        image.extend_from_slice(&dir_meta_table);
+        file_hash_table.iter().for_each(|bucket| push_value(&mut image, *bucket));
+        image.extend_from_slice(&file_meta_table);
+        image.resize(file_data_offset, 0);
+        image.extend_from_slice(&file_data);
+        image
+    }
+
+    const DIR_PATHS: &[&str] = &["a", "a/b", "a/b/c", "empty"];
+    const FILES: &[(&str, &[u8])] = &[("a/b/c/deep.txt", b"deep"), ("x", b"root x"), ("a/x", b"nested x"), ("a/b/file.bin", &[1, 2, 3])];
+
+    fn open_image(image: Vec<u8>) -> Result<RomFs> {
+        // MemoryStorage only takes static data, leaking it is fine for tests
+        RomFs::new(mem::Shared::new(fs::MemoryStorage::new(Box::leak(image.into_boxed_slice()))))
+    }
+
+    fn read_file(romfs: &mut RomFs, path: &str) -> Result<Vec<u8>> {
+        let file = romfs.open_file(path, fs::FileOpenMode::Read())?;
+        let mut data = vec![0; file.get().get_size()?];
+        let read_size = file.get().read(0, &mut data, fs::FileReadOption::None())?;
+        data.truncate(read_size);
+        Ok(data)
+    }
+
+    fn list_names(romfs: &mut RomFs, path: &str) -> Vec<String> {
+        let mut dir = fs::Directory::new(romfs.open_directory(path, fs::DirectoryOpenMode::ReadDirectories() | fs::DirectoryOpenMode::ReadFiles()).unwrap()).unwrap();
+        let mut names: Vec<String> = Vec::new();
+        while let Some(entry) = dir.next().unwrap() {
+            names.push(String::from(entry.name.get_str().unwrap()));
+        }
+        names
+    }
+
+    fn check_image(romfs: &mut RomFs) {
+        assert_eq!(read_file(romfs, "/a/b/c/deep.txt").unwrap(), b"deep");
+        assert_eq!(read_file(romfs, "/a/b/file.bin").unwrap(), [1, 2, 3]);
+        // Same name, different parents
+        assert_eq!(read_file(romfs, "/x").unwrap(), b"root x");
+        assert_eq!(read_file(romfs, "/a/x").unwrap(), b"nested x");
+
+        assert_eq!(romfs.get_entry_type("/a/b/c").unwrap(), fs::DirectoryEntryType::Directory);
+        assert_eq!(romfs.get_entry_type("/a/b/c/deep.txt").unwrap(), fs::DirectoryEntryType::File);
+        assert_eq!(romfs.get_entry_type("/").unwrap(), fs::DirectoryEntryType::Directory);
+        for missing_path in ["/b", "/a/c", "/a/b/c/x", "/empty/x", "/a/b/c/deep.txt/x"] {
+            assert!(results::fs::ResultPathNotFound::matches(romfs.get_entry_type(missing_path).err().unwrap()), "{}", missing_path);
+        }
+
+        assert_eq!(list_names(romfs, "/"), ["a", "empty", "x"]);
+        assert_eq!(list_names(romfs, "/a"), ["b", "x"]);
+        assert_eq!(list_names(romfs, "/a/b"), ["c", "file.bin"]);
+        assert!(list_names(romfs, "/empty").is_empty());
+    }
+
+    #[test]
+    fn nested_paths() {
+        let mut romfs = open_image(build_image(DIR_PATHS, FILES, 7)).unwrap();
+        check_image(&mut romfs);
+
+        assert!(results::lib::fs::ResultReadOnlyFileSystem::matches(romfs.open_file("/x", fs::FileOpenMode::Write()).err().unwrap()));
+        assert!(results::lib::fs::ResultReadOnlyFileSystem::matches(romfs.create_file("/y", fs::FileAttribute::None(), 0).err().unwrap()));
+    }
+
+    #[test]
+    fn hash_collisions() {
+        // Every entry ends up in the same bucket, thus lookups have to go through the whole chain
+        let mut romfs = open_image(build_image(DIR_PATHS, FILES, 1)).unwrap();
+        check_image(&mut romfs);
+    }
+
+    #[test]
+    fn hash_chain_loop() {
+        let mut image = build_image(DIR_PATHS, FILES, 1);
+        // Make the last directory (the head of the single chain) its own hash sibling
+        let romfs = open_image(image.clone()).unwrap();
+        let chain_head_offset = romfs.dir_hash_table[0];
+        let hash_sibling_field_offset = romfs.get_header().dir_meta_table_offset as usize + chain_head_offset as usize + 0x10;
+        image[hash_sibling_field_offset..hash_sibling_field_offset + 4].copy_from_slice(&chain_head_offset.to_le_bytes());
+
+        let romfs = open_image(image).unwrap();
+        assert_eq!(romfs.find_directory("/missing").unwrap(), None);
+        assert!(romfs.find_directory("/empty").unwrap().is_some());
+    }
+
+    #[test]
+    fn invalid_images() {
+        let image = build_image(DIR_PATHS, FILES, 3);
+
+        let mut bad_header_image = image.clone();
+        bad_header_image[0] = 0x40;
+        assert!(results::lib::fs::ResultInvalidRomFs::matches(open_image(bad_header_image).err().unwrap()));
+
+        // Tables past the end of the storage
+        let truncated_image = image[..0x60].to_vec();
+        assert!(results::lib::fs::ResultInvalidRomFs::matches(open_image(truncated_image).err().unwrap()));
+
+        // File data past the end of the storage is only noticed when opening the file
+        let file_data_end = image.len() - 0x10;
+        let mut romfs = open_image(image[..file_data_end].to_vec()).unwrap();
+        assert!(results::lib::fs::ResultInvalidRomFs::matches(romfs.open_file("/a/b/file.bin", fs::FileOpenMode::Read()).err().unwrap()));
+        assert_eq!(read_file(&mut romfs, "/x").unwrap(), b"root x");
+    }
+}
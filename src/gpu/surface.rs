@@ -35,8 +35,8 @@ pub struct Surface<NS: nv::INvDrvService + 'static> {
     nvhost_fd: nv::Fd,
     nvmap_fd: nv::Fd,
     nvhostctrl_fd: nv::Fd,
-    vsync_event_handle: svc::Handle,
-    buffer_event_handle: svc::Handle
+    vsync_event_handle: svc::OwnedHandle,
+    buffer_event_handle: svc::OwnedHandle
 }
 
 impl<NS: nv::INvDrvService> Surface<NS> {
@@ -44,9 +44,9 @@ impl<NS: nv::INvDrvService> Surface<NS> {
         let mut binder = binder::Binder::new(binder_handle, hos_binder_driver)?;
         binder.increase_refcounts()?;
         let _ = binder.connect(ConnectionApi::Cpu, false)?;
-        let vsync_event_handle = application_display_service.get().get_display_vsync_event(display_id)?;
-        let buffer_event_handle = binder.get_native_handle(dispdrv::NativeHandleType::BufferEvent)?;
-        let mut surface = Self { binder, nvdrv_srv, application_display_service, width, height, buffer_data: alloc::Buffer::empty(), single_buffer_size: 0, buffer_count, slot_has_requested: [false; MAX_BUFFERS], graphic_buf: Default::default(), color_fmt, pixel_fmt, layout, display_id, layer_id, layer_destroy_fn, nvhost_fd, nvmap_fd, nvhostctrl_fd, vsync_event_handle: vsync_event_handle.handle, buffer_event_handle: buffer_event_handle.handle };
+        let vsync_event_handle = application_display_service.get().get_display_vsync_event(display_id)?.into_owned()?;
+        let buffer_event_handle = binder.get_native_handle(dispdrv::NativeHandleType::BufferEvent)?.into_owned()?;
+        let mut surface = Self { binder, nvdrv_srv, application_display_service, width, height, buffer_data: alloc::Buffer::empty(), single_buffer_size: 0, buffer_count, slot_has_requested: [false; MAX_BUFFERS], graphic_buf: Default::default(), color_fmt, pixel_fmt, layout, display_id, layer_id, layer_destroy_fn, nvhost_fd, nvmap_fd, nvhostctrl_fd, vsync_event_handle, buffer_event_handle };
         surface.initialize()?;
         Ok(surface)
     }
@@ -58,7 +58,10 @@ impl<NS: nv::INvDrvService> Surface<NS> {
             ioctl::IoctlFd::NvHostCtrl => self.nvhostctrl_fd,
         };
 
-        let err = self.nvdrv_srv.get().ioctl(fd, I::get_id(), sf::Buffer::from_var(i), sf::Buffer::from_var(i))?;
+        // Ioctls are sent and received through the same memory, which can't be expressed as two safe buffers
+        let err = unsafe {
+            self.nvdrv_srv.get().ioctl(fd, I::get_id(), sf::InBuffer::from_const(i as *const I, cmem::size_of::<I>()), sf::OutBuffer::from_mut(i as *mut I, cmem::size_of::<I>()))?
+        };
         nv::convert_error_code(err)
     }
 
@@ -142,10 +145,7 @@ impl<NS: nv::INvDrvService> Surface<NS> {
         self.buffer_data.release();
         (self.layer_destroy_fn)(self.layer_id, self.application_display_service.clone())?;
 
-        self.application_display_service.get().close_display(self.display_id)?;
-
-        svc::close_handle(self.buffer_event_handle)?;
-        svc::close_handle(self.vsync_event_handle)
+        self.application_display_service.get().close_display(self.display_id)
     }
 
     pub fn dequeue_buffer(&mut self, is_async: bool) -> Result<(*mut u8, usize, i32, bool, MultiFence)> {
@@ -218,13 +218,13 @@ impl<NS: nv::INvDrvService> Surface<NS> {
     }
 
     pub fn wait_buffer_event(&mut self, timeout: i64) -> Result<()> {
-        svc::wait_synchronization(&self.buffer_event_handle, 1, timeout)?;
-        svc::reset_signal(self.buffer_event_handle)
+        svc::wait_synchronization(&self.buffer_event_handle.as_raw(), 1, timeout)?;
+        svc::reset_signal(self.buffer_event_handle.as_borrowed())
     }
 
     pub fn wait_vsync_event(&mut self, timeout: i64) -> Result<()> {
-        svc::wait_synchronization(&self.vsync_event_handle, 1, timeout)?;
-        svc::reset_signal(self.vsync_event_handle)
+        svc::wait_synchronization(&self.vsync_event_handle.as_raw(), 1, timeout)?;
+        svc::reset_signal(self.vsync_event_handle.as_borrowed())
     }
 
     pub fn get_width(&self) -> u32 {
@@ -191,6 +191,29 @@ pub fn get_next_load_argv() -> &'static str {
     }
 }
 
+static mut G_ARGV: &'static str = "";
+
+pub(crate) fn set_argv(argv: &'static str) {
+    unsafe {
+        G_ARGV = argv;
+    }
+}
+
+pub fn get_argv() -> &'static str {
+    unsafe {
+        G_ARGV
+    }
+}
+
+// The first argument hbloader gives us is the path of our own NRO (like "sdmc:/switch/app.nro"), quoted if it contains spaces
+pub fn get_program_path() -> &'static str {
+    let argv = get_argv().trim_start();
+    match argv.strip_prefix('"') {
+        Some(quoted_argv) => quoted_argv.split('"').next().unwrap_or(""),
+        None => argv.split_whitespace().next().unwrap_or("")
+    }
+}
+
 static mut G_RANDOM_SEED: (u64, u64) = (0, 0);
 
 pub(crate) fn set_random_seed(seed: (u64, u64)) {
@@ -3,6 +3,14 @@ use crate::results;
 use crate::ipc::sf;
 use crate::service;
 use crate::mem;
+use crate::version;
+
+// Commands which don't exist in the current system version are never sent (the version is unknown until the runtime sets it, see version::set_version)
+pub fn check_command_version(ver_intv: version::VersionInterval) -> Result<()> {
+    let cur_version = version::get_version();
+    result_return_if!((cur_version != version::Version::empty()) && !ver_intv.contains(cur_version), results::lib::ipc::ResultUnsupportedCommandVersion);
+    Ok(())
+}
 
 pub trait CommandParameter<O> {
     fn before_request_write(var: &Self, walker: &mut DataWalker, ctx: &mut CommandContext) -> Result<()>;
@@ -26,9 +34,23 @@ impl<T: Copy> CommandParameter<T> for T {
     }
 }
 
-impl<const A: BufferAttribute, const S: usize> CommandParameter<sf::Buffer<A, S>> for sf::Buffer<A, S> {
+impl<'a, const A: BufferAttribute, const S: usize> CommandParameter<sf::InBuffer<'a, A, S>> for sf::InBuffer<'a, A, S> {
+    fn before_request_write(buffer: &Self, _walker: &mut DataWalker, ctx: &mut CommandContext) -> Result<()> {
+        ctx.add_buffer::<A, S>(buffer.get_address(), buffer.get_size())
+    }
+
+    fn before_send_sync_request(_buffer: &Self, _walker: &mut DataWalker, _ctx: &mut CommandContext) -> Result<()> {
+        Ok(())
+    }
+
+    fn after_response_read(_walker: &mut DataWalker, _ctx: &mut CommandContext) -> Result<Self> {
+        Err(results::hipc::ResultUnsupportedOperation::make())
+    }
+}
+
+impl<'a, const A: BufferAttribute, const S: usize> CommandParameter<sf::OutBuffer<'a, A, S>> for sf::OutBuffer<'a, A, S> {
     fn before_request_write(buffer: &Self, _walker: &mut DataWalker, ctx: &mut CommandContext) -> Result<()> {
-        ctx.add_buffer(buffer.clone())
+        ctx.add_buffer::<A, S>(buffer.get_address(), buffer.get_size())
     }
 
     fn before_send_sync_request(_buffer: &Self, _walker: &mut DataWalker, _ctx: &mut CommandContext) -> Result<()> {
@@ -43,7 +65,7 @@ impl<const A: BufferAttribute, const S: usize> CommandParameter<sf::Buffer<A, S>
 
 impl<const M: HandleMode> CommandParameter<sf::Handle<M>> for sf::Handle<M> {
     fn before_request_write(handle: &Self, _walker: &mut DataWalker, ctx: &mut CommandContext) -> Result<()> {
-        ctx.in_params.add_handle(handle.clone())
+        ctx.in_params.add_handle(handle)
     }
 
     fn before_send_sync_request(_handle: &Self, _walker: &mut DataWalker, _ctx: &mut CommandContext) -> Result<()> {
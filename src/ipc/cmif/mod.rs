@@ -91,6 +91,15 @@ pub fn convert_command_type(command_type: u32) -> CommandType {
     }
 }
 
+pub fn convert_domain_command_type(command_type: u8) -> Option<DomainCommandType> {
+    match command_type {
+        0 => Some(DomainCommandType::Invalid),
+        1 => Some(DomainCommandType::SendMessage),
+        2 => Some(DomainCommandType::Close),
+        _ => None
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(C)]
 pub struct DataHeader {
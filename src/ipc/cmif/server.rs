@@ -3,52 +3,44 @@ use crate::results;
 use super::*;
 use core::mem as cmem;
 
-#[inline(always)]
-pub fn read_command_from_msg_buffer(ctx: &mut CommandContext) -> CommandType {
-    unsafe {
-        let mut ipc_buf = get_msg_buffer();
-
-        let command_header = ipc_buf as *mut CommandHeader;
-        ipc_buf = command_header.offset(1) as *mut u8;
-
-        let command_type = (*command_header).get_command_type();
-        let data_size = (*command_header).get_data_word_count() * cmem::size_of::<u32>() as u32;
-        ctx.in_params.data_size = data_size;
-
-        if (*command_header).get_has_special_header() {
-            let special_header = ipc_buf as *mut CommandSpecialHeader;
-            ipc_buf = special_header.offset(1) as *mut u8;
+// Requests come from other processes: they are parsed within the bounds of the given message (the message buffer itself or a copy of it), whatever their headers say
+pub fn read_command_from_msg(ctx: &mut CommandContext, msg: &[u8]) -> Result<CommandType> {
+    let mut reader = BoundedReader::new(msg);
+
+    let command_header: CommandHeader = reader.read()?;
+    let command_type = command_header.get_command_type();
+    let data_size = command_header.get_data_word_count() * cmem::size_of::<u32>() as u32;
+    ctx.in_params.data_size = data_size;
+    ctx.in_params.data_words_size = data_size;
+
+    if command_header.get_has_special_header() {
+        let special_header: CommandSpecialHeader = reader.read()?;
+
+        ctx.in_params.send_process_id = special_header.get_send_process_id();
+        if ctx.in_params.send_process_id {
+            ctx.in_params.process_id = reader.read()?;
+        }
 
-            ctx.in_params.send_process_id = (*special_header).get_send_process_id();
-            if ctx.in_params.send_process_id {
-                let process_id_ptr = ipc_buf as *mut u64;
-                ctx.in_params.process_id = *process_id_ptr;
-                ipc_buf = process_id_ptr.offset(1) as *mut u8;
-            }
+        reader.read_array(special_header.get_copy_handle_count(), &mut ctx.in_params.copy_handles, results::lib::ipc::ResultCopyHandlesFull::make())?;
+        reader.read_array(special_header.get_move_handle_count(), &mut ctx.in_params.move_handles, results::lib::ipc::ResultMoveHandlesFull::make())?;
+    }
 
-            let copy_handle_count = (*special_header).get_copy_handle_count();
-            ipc_buf = read_array_from_buffer(ipc_buf, copy_handle_count, &mut ctx.in_params.copy_handles);
-            let move_handle_count = (*special_header).get_move_handle_count();
-            ipc_buf = read_array_from_buffer(ipc_buf, move_handle_count, &mut ctx.in_params.move_handles);
-        }
+    reader.read_array(command_header.get_send_static_count(), &mut ctx.send_statics, results::lib::ipc::ResultInvalidSendStaticCount::make())?;
+    reader.read_array(command_header.get_send_buffer_count(), &mut ctx.send_buffers, results::lib::ipc::ResultInvalidSendBufferCount::make())?;
+    reader.read_array(command_header.get_receive_buffer_count(), &mut ctx.receive_buffers, results::lib::ipc::ResultInvalidReceiveBufferCount::make())?;
+    reader.read_array(command_header.get_exchange_buffer_count(), &mut ctx.exchange_buffers, results::lib::ipc::ResultInvalidExchangeBufferCount::make())?;
 
-        let send_static_count = (*command_header).get_send_static_count();
-        ipc_buf = read_array_from_buffer(ipc_buf, send_static_count, &mut ctx.send_statics);
-        let send_buffer_count = (*command_header).get_send_buffer_count();
-        ipc_buf = read_array_from_buffer(ipc_buf, send_buffer_count, &mut ctx.send_buffers);
-        let receive_buffer_count = (*command_header).get_receive_buffer_count();
-        ipc_buf = read_array_from_buffer(ipc_buf, receive_buffer_count, &mut ctx.receive_buffers);
-        let exchange_buffer_count = (*command_header).get_exchange_buffer_count();
-        ipc_buf = read_array_from_buffer(ipc_buf, exchange_buffer_count, &mut ctx.exchange_buffers);
+    ctx.in_params.data_words_offset = reader.get_ptr();
+    reader.skip(data_size as usize)?;
 
-        ctx.in_params.data_words_offset = ipc_buf;
-        ipc_buf = ipc_buf.offset(data_size as isize);
+    reader.read_array(command_header.get_receive_static_count(), &mut ctx.receive_statics, results::lib::ipc::ResultInvalidReceiveStaticCount::make())?;
 
-        let receive_static_count = (*command_header).get_receive_static_count();
-        read_array_from_buffer(ipc_buf, receive_static_count, &mut ctx.receive_statics);
+    Ok(convert_command_type(command_type))
+}
 
-        convert_command_type(command_type)
-    }
+#[inline(always)]
+pub fn read_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<CommandType> {
+    read_command_from_msg(ctx, get_msg_buffer_slice())
 }
 
 #[inline(always)]
@@ -87,44 +79,64 @@ pub fn write_command_response_on_msg_buffer(ctx: &mut CommandContext, command_ty
     }
 }
 
-#[inline(always)]
-pub fn read_request_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<(u32, DomainCommandType, DomainObjectId)> {
-    unsafe {
-        let mut domain_command_type = DomainCommandType::Invalid;
-        let mut domain_object_id: DomainObjectId = 0;
-        let ipc_buf = get_msg_buffer();
-        let mut data_offset = get_aligned_data_offset(ctx.in_params.data_words_offset, ipc_buf);
+// Positions the reader at the (aligned) start of the data words read by read_command_from_msg
+fn seek_data_offset(ctx: &CommandContext, reader: &mut BoundedReader, msg: &[u8]) -> Result<()> {
+    let data_words_offset = (ctx.in_params.data_words_offset as usize).wrapping_sub(msg.as_ptr() as usize);
+    result_return_if!(data_words_offset.saturating_add(ctx.in_params.data_size as usize) > msg.len(), results::lib::ipc::ResultInvalidMessageSize);
 
-        let mut data_header = data_offset as *mut DataHeader;
-        if ctx.object_info.is_domain() {
-            let domain_header = data_offset as *mut DomainInDataHeader;
-            data_offset = domain_header.offset(1) as *mut u8;
-            ctx.in_params.data_size -= cmem::size_of::<DomainInDataHeader>() as u32;
+    let data_offset = (data_words_offset + DATA_PADDING as usize - 1) & !(DATA_PADDING as usize - 1);
+    reader.seek(data_offset)
+}
 
-            domain_command_type = (*domain_header).command_type;
-            let object_count = (*domain_header).object_count;
-            domain_object_id = (*domain_header).domain_object_id;
-            let objects_offset = data_offset.offset((*domain_header).data_size as isize);
-            read_array_from_buffer(objects_offset, object_count as u32, &mut ctx.in_params.objects);
+pub fn read_request_command_from_msg(ctx: &mut CommandContext, msg: &[u8]) -> Result<(u32, DomainCommandType, DomainObjectId)> {
+    let mut reader = BoundedReader::new(msg);
+    seek_data_offset(ctx, &mut reader, msg)?;
 
-            data_header = data_offset as *mut DataHeader;
-        }
+    let mut domain_command_type = DomainCommandType::Invalid;
+    let mut domain_object_id: DomainObjectId = 0;
+    if ctx.object_info.is_domain() {
+        result_return_if!(ctx.in_params.data_size < (DATA_PADDING + cmem::size_of::<DomainInDataHeader>() as u32), results::lib::ipc::ResultInvalidMessageSize);
 
-        let mut rq_id: u32 = 0;
-        if ctx.in_params.data_size >= DATA_PADDING {
-            ctx.in_params.data_size -= DATA_PADDING;
-            if ctx.in_params.data_size >= cmem::size_of::<DataHeader>() as u32 {
-                result_return_unless!((*data_header).magic == IN_DATA_HEADER_MAGIC, results::cmif::ResultInvalidInputHeader);
+        // The command type is checked before reading the whole header, since not every value is a valid DomainCommandType
+        let raw_command_type: u8 = reader.read_at(reader.get_offset())?;
+        domain_command_type = match convert_domain_command_type(raw_command_type) {
+            Some(command_type) => command_type,
+            None => return Err(results::lib::ipc::ResultInvalidDomainCommandType::make())
+        };
 
-                rq_id = (*data_header).value;
-                data_offset = data_header.offset(1) as *mut u8;
-                ctx.in_params.data_size -= cmem::size_of::<DataHeader>() as u32;
-            }
-        }
+        let domain_header: DomainInDataHeader = reader.read()?;
+        ctx.in_params.data_size -= cmem::size_of::<DomainInDataHeader>() as u32;
+        domain_object_id = domain_header.domain_object_id;
 
-        ctx.in_params.data_offset = data_offset;
-        Ok((rq_id, domain_command_type, domain_object_id))
+        // The object IDs follow the domain data, all of it must be within the data words
+        let domain_data_size = domain_header.data_size as u32 + domain_header.object_count as u32 * cmem::size_of::<DomainObjectId>() as u32;
+        result_return_if!(domain_data_size > (ctx.in_params.data_size - DATA_PADDING), results::lib::ipc::ResultInvalidMessageSize);
+
+        let data_offset = reader.get_offset();
+        reader.skip(domain_header.data_size as usize)?;
+        reader.read_array(domain_header.object_count as u32, &mut ctx.in_params.objects, results::lib::ipc::ResultDomainObjectsFull::make())?;
+        reader.seek(data_offset)?;
+    }
+
+    let mut rq_id: u32 = 0;
+    if ctx.in_params.data_size >= DATA_PADDING {
+        ctx.in_params.data_size -= DATA_PADDING;
+        if ctx.in_params.data_size >= cmem::size_of::<DataHeader>() as u32 {
+            let data_header: DataHeader = reader.read()?;
+            result_return_unless!(data_header.magic == IN_DATA_HEADER_MAGIC, results::cmif::ResultInvalidInputHeader);
+
+            rq_id = data_header.value;
+            ctx.in_params.data_size -= cmem::size_of::<DataHeader>() as u32;
+        }
     }
+
+    ctx.in_params.data_offset = reader.get_ptr();
+    Ok((rq_id, domain_command_type, domain_object_id))
+}
+
+#[inline(always)]
+pub fn read_request_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<(u32, DomainCommandType, DomainObjectId)> {
+    read_request_command_from_msg(ctx, get_msg_buffer_slice())
 }
 
 #[inline(always)]
@@ -160,22 +172,23 @@ pub fn write_request_command_response_on_msg_buffer(ctx: &mut CommandContext, re
     }
 }
 
-#[inline(always)]
-pub fn read_control_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<ControlRequestId> {
-    unsafe {
-        let ipc_buf = get_msg_buffer();
-        let mut data_offset = get_aligned_data_offset(ctx.in_params.data_words_offset, ipc_buf);
+// Note: the raw ID is returned, as clients can send any value (see ControlRequestId for the known ones)
+pub fn read_control_command_from_msg(ctx: &mut CommandContext, msg: &[u8]) -> Result<u32> {
+    let mut reader = BoundedReader::new(msg);
+    seek_data_offset(ctx, &mut reader, msg)?;
+    result_return_if!(ctx.in_params.data_size < (DATA_PADDING + cmem::size_of::<DataHeader>() as u32), results::lib::ipc::ResultInvalidMessageSize);
 
-        let data_header = data_offset as *mut DataHeader;
-        data_offset = data_header.offset(1) as *mut u8;
+    let data_header: DataHeader = reader.read()?;
+    result_return_unless!(data_header.magic == IN_DATA_HEADER_MAGIC, results::cmif::ResultInvalidInputHeader);
 
-        result_return_unless!((*data_header).magic == IN_DATA_HEADER_MAGIC, results::cmif::ResultInvalidInputHeader);
-        let control_rq_id = (*data_header).value;
+    ctx.in_params.data_offset = reader.get_ptr();
+    ctx.in_params.data_size -= DATA_PADDING + cmem::size_of::<DataHeader>() as u32;
+    Ok(data_header.value)
+}
 
-        ctx.in_params.data_offset = data_offset;
-        ctx.in_params.data_size -= DATA_PADDING + cmem::size_of::<DataHeader>() as u32;
-        Ok(cmem::transmute(control_rq_id))
-    }
+#[inline(always)]
+pub fn read_control_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<u32> {
+    read_control_command_from_msg(ctx, get_msg_buffer_slice())
 }
 
 #[inline(always)]
@@ -0,0 +1,319 @@
+use crate::result::*;
+use crate::results;
+use crate::svc;
+use super::*;
+use alloc::vec::Vec;
+use core::fmt;
+
+// Parses raw messages (as found in the message buffer) into something readable, mostly for debugging purposes
+// Nothing is validated against what the message should contain: anything past the end of the given buffer is just left out
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Payload {
+    // Close requests and anything else we can't tell apart (like TIPC responses or corrupted messages) only have their raw data words
+    None,
+    CmifRequest { domain_header: Option<cmif::DomainInDataHeader>, data_header: cmif::DataHeader },
+    CmifResponse { domain_header: Option<cmif::DomainOutDataHeader>, data_header: cmif::DataHeader },
+    TipcRequest { request_id: u32 },
+    TipcClose
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Message {
+    pub header: CommandHeader,
+    pub special_header: Option<CommandSpecialHeader>,
+    pub process_id: Option<u64>,
+    pub copy_handles: Vec<svc::Handle>,
+    pub move_handles: Vec<svc::Handle>,
+    pub send_statics: Vec<SendStaticDescriptor>,
+    pub send_buffers: Vec<BufferDescriptor>,
+    pub receive_buffers: Vec<BufferDescriptor>,
+    pub exchange_buffers: Vec<BufferDescriptor>,
+    pub data_words: Vec<u32>,
+    pub receive_statics: Vec<ReceiveStaticDescriptor>,
+    pub payload: Payload,
+    pub truncated: bool
+}
+
+struct MessageReader<'a> {
+    msg: &'a [u8],
+    offset: usize,
+    truncated: bool
+}
+
+impl<'a> MessageReader<'a> {
+    fn new(msg: &'a [u8]) -> Self {
+        Self { msg, offset: 0, truncated: false }
+    }
+
+    fn read_at<T: Copy>(&self, offset: usize) -> Option<T> {
+        if (offset + core::mem::size_of::<T>()) > self.msg.len() {
+            return None;
+        }
+        unsafe {
+            Some((self.msg.as_ptr().add(offset) as *const T).read_unaligned())
+        }
+    }
+
+    fn read<T: Copy>(&mut self) -> Option<T> {
+        let value = self.read_at(self.offset);
+        match value {
+            Some(_) => self.offset += core::mem::size_of::<T>(),
+            None => self.truncated = true
+        };
+        value
+    }
+
+    fn read_array<T: Copy>(&mut self, count: u32) -> Vec<T> {
+        let mut array: Vec<T> = Vec::new();
+        for _ in 0..count {
+            match self.read() {
+                Some(value) => array.push(value),
+                None => break
+            };
+        }
+        array
+    }
+}
+
+fn decode_cmif_payload(msg: &[u8], data_words_offset: usize, data_word_count: u32) -> Payload {
+    let reader = MessageReader::new(msg);
+    let data_offset = (data_words_offset + DATA_PADDING as usize - 1) & !(DATA_PADDING as usize - 1);
+    let data_end = data_words_offset + data_word_count as usize * core::mem::size_of::<u32>();
+
+    let read_data_header = |offset: usize| -> Option<cmif::DataHeader> {
+        if (offset + core::mem::size_of::<cmif::DataHeader>()) > data_end {
+            return None;
+        }
+        reader.read_at::<cmif::DataHeader>(offset)
+    };
+
+    // Domain messages have their domain header right before the data header
+    let domain_data_offset = data_offset + core::mem::size_of::<cmif::DomainInDataHeader>();
+    for (offset, is_domain) in [(data_offset, false), (domain_data_offset, true)] {
+        if let Some(data_header) = read_data_header(offset) {
+            match data_header.magic {
+                cmif::IN_DATA_HEADER_MAGIC => {
+                    // Not every value is a valid DomainCommandType, thus it's checked before reading the whole header
+                    let domain_header = match is_domain && reader.read_at::<u8>(data_offset).and_then(cmif::convert_domain_command_type).is_some() {
+                        true => reader.read_at::<cmif::DomainInDataHeader>(data_offset),
+                        false => None
+                    };
+                    return Payload::CmifRequest { domain_header, data_header };
+                },
+                cmif::OUT_DATA_HEADER_MAGIC => {
+                    let domain_header = match is_domain {
+                        true => reader.read_at::<cmif::DomainOutDataHeader>(data_offset),
+                        false => None
+                    };
+                    return Payload::CmifResponse { domain_header, data_header };
+                },
+                _ => {}
+            };
+        }
+    }
+
+    Payload::None
+}
+
+pub fn decode_message(msg: &[u8]) -> Result<Message> {
+    let mut reader = MessageReader::new(msg);
+    let header: CommandHeader = match reader.read() {
+        Some(header) => header,
+        None => return Err(results::lib::ipc::ResultInvalidMessageSize::make())
+    };
+
+    let mut special_header: Option<CommandSpecialHeader> = None;
+    let mut process_id: Option<u64> = None;
+    let mut copy_handles: Vec<svc::Handle> = Vec::new();
+    let mut move_handles: Vec<svc::Handle> = Vec::new();
+    if header.get_has_special_header() {
+        special_header = reader.read::<CommandSpecialHeader>();
+        if let Some(special_header) = special_header {
+            if special_header.get_send_process_id() {
+                process_id = reader.read();
+            }
+            copy_handles = reader.read_array(special_header.get_copy_handle_count());
+            move_handles = reader.read_array(special_header.get_move_handle_count());
+        }
+    }
+
+    let send_statics = reader.read_array(header.get_send_static_count());
+    let send_buffers = reader.read_array(header.get_send_buffer_count());
+    let receive_buffers = reader.read_array(header.get_receive_buffer_count());
+    let exchange_buffers = reader.read_array(header.get_exchange_buffer_count());
+
+    let data_words_offset = reader.offset;
+    let data_words = reader.read_array(header.get_data_word_count());
+
+    // A count of 0xFF means a single receive static covering the whole buffer
+    let receive_static_count = match header.get_receive_static_count() {
+        0xFF => 1,
+        count => count
+    };
+    let receive_statics = reader.read_array(receive_static_count);
+
+    let command_type = header.get_command_type();
+    let payload = match command_type {
+        tipc_command_type if tipc_command_type == tipc::CommandType::CloseSession as u32 => Payload::TipcClose,
+        tipc_command_type if tipc_command_type >= tipc::CommandType::Request as u32 => Payload::TipcRequest { request_id: tipc_command_type - tipc::CommandType::Request as u32 },
+        _ => match cmif::convert_command_type(command_type) {
+            cmif::CommandType::Close => Payload::None,
+            _ => decode_cmif_payload(msg, data_words_offset, header.get_data_word_count())
+        }
+    };
+
+    Ok(Message { header, special_header, process_id, copy_handles, move_handles, send_statics, send_buffers, receive_buffers, exchange_buffers, data_words, receive_statics, payload, truncated: reader.truncated })
+}
+
+fn fmt_buffers(f: &mut fmt::Formatter<'_>, kind: &str, buffers: &[BufferDescriptor]) -> fmt::Result {
+    for (i, buffer) in buffers.iter().enumerate() {
+        writeln!(f, "  {} buffer #{}: address {:p}, size {:#X}, flags {}", kind, i, buffer.get_address(), buffer.get_size(), buffer.get_flags())?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let command_type = self.header.get_command_type();
+        match command_type {
+            tipc_command_type if tipc_command_type == tipc::CommandType::CloseSession as u32 => writeln!(f, "Command type: TIPC close ({})", command_type)?,
+            tipc_command_type if tipc_command_type >= tipc::CommandType::Request as u32 => writeln!(f, "Command type: TIPC request ({})", command_type)?,
+            _ => writeln!(f, "Command type: {:?} ({})", cmif::convert_command_type(command_type), command_type)?
+        };
+
+        if let Some(special_header) = self.special_header {
+            writeln!(f, "Special header: send process ID {}, {} copy handles, {} move handles", special_header.get_send_process_id(), special_header.get_copy_handle_count(), special_header.get_move_handle_count())?;
+        }
+        if let Some(process_id) = self.process_id {
+            writeln!(f, "  Process ID: {:#X}", process_id)?;
+        }
+        for (i, handle) in self.copy_handles.iter().enumerate() {
+            writeln!(f, "  Copy handle #{}: {:#X}", i, handle)?;
+        }
+        for (i, handle) in self.move_handles.iter().enumerate() {
+            writeln!(f, "  Move handle #{}: {:#X}", i, handle)?;
+        }
+
+        for (i, send_static) in self.send_statics.iter().enumerate() {
+            writeln!(f, "  Send static #{}: index {}, address {:p}, size {:#X}", i, send_static.get_index(), send_static.get_address(), send_static.get_size())?;
+        }
+        fmt_buffers(f, "Send", &self.send_buffers)?;
+        fmt_buffers(f, "Receive", &self.receive_buffers)?;
+        fmt_buffers(f, "Exchange", &self.exchange_buffers)?;
+        if self.header.get_receive_static_count() == 0xFF {
+            writeln!(f, "  Receive statics: single buffer")?;
+        }
+        for (i, receive_static) in self.receive_statics.iter().enumerate() {
+            writeln!(f, "  Receive static #{}: address {:p}, size {:#X}", i, receive_static.get_address(), receive_static.get_size())?;
+        }
+
+        match self.payload {
+            Payload::None => {},
+            Payload::CmifRequest { domain_header, data_header } => {
+                if let Some(domain_header) = domain_header {
+                    writeln!(f, "Domain header: {:?} for object {}, {} in objects, data size {:#X}", domain_header.command_type, domain_header.domain_object_id, domain_header.object_count, domain_header.data_size)?;
+                }
+                writeln!(f, "Data header (SFCI): version {}, request ID {}, token {:#X}", data_header.version, data_header.value, data_header.token)?;
+            },
+            Payload::CmifResponse { domain_header, data_header } => {
+                if let Some(domain_header) = domain_header {
+                    writeln!(f, "Domain header: {} out objects", domain_header.out_object_count)?;
+                }
+                writeln!(f, "Data header (SFCO): version {}, result {} ({:?}), token {:#X}", data_header.version, ResultCode::new(data_header.value), ResultCode::new(data_header.value), data_header.token)?;
+            },
+            Payload::TipcRequest { request_id } => writeln!(f, "TIPC request ID: {}", request_id)?,
+            Payload::TipcClose => {}
+        };
+
+        write!(f, "Data words ({}):", self.data_words.len())?;
+        for (i, data_word) in self.data_words.iter().enumerate() {
+            if (i % 4) == 0 {
+                write!(f, "\n  ")?;
+            }
+            write!(f, "{:08X} ", data_word)?;
+        }
+
+        if self.truncated {
+            write!(f, "\n(truncated message)")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SFCI: u32 = cmif::IN_DATA_HEADER_MAGIC;
+    const SFCO: u32 = cmif::OUT_DATA_HEADER_MAGIC;
+
+    fn to_bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    // sm GetServiceHandle("fsp-srv"): CMIF request, no special header, data aligned right after the header
+    const SM_GET_SERVICE_HANDLE: [u32; 12] = [0x00000004, 0x0000000A, 0, 0, SFCI, 0, 1, 0, 0x2D707366, 0x00767273, 0, 0];
+
+    // ...and its response, moving the session handle
+    const SM_GET_SERVICE_HANDLE_RESPONSE: [u32; 12] = [0x00000000, 0x80000008, 0x00000020, 0x0001B4C2, SFCO, 0, 0, 0, 0, 0, 0, 0];
+
+    // A domain request (command 5 for object 2) with a 0x10-byte domain header before the data header
+    const DOMAIN_REQUEST: [u32; 14] = [0x00000004, 0x0000000C, 0, 0, 0x00100001, 2, 0, 0, SFCI, 0, 5, 0, 0, 0];
+
+    #[test]
+    fn decode_cmif_request() {
+        let message = decode_message(&to_bytes(&SM_GET_SERVICE_HANDLE)).unwrap();
+        assert_eq!(message.header.get_command_type(), cmif::CommandType::Request as u32);
+        assert!(message.special_header.is_none());
+        assert_eq!(message.data_words.len(), 10);
+        assert_eq!(&message.data_words[6..8], &[0x2D707366, 0x00767273]);
+        assert_eq!(message.payload, Payload::CmifRequest { domain_header: None, data_header: cmif::DataHeader { magic: SFCI, version: 0, value: 1, token: 0 } });
+        assert!(!message.truncated);
+        assert!(format!("{}", message).contains("Data header (SFCI): version 0, request ID 1"));
+    }
+
+    #[test]
+    fn decode_cmif_response_with_handles() {
+        let message = decode_message(&to_bytes(&SM_GET_SERVICE_HANDLE_RESPONSE)).unwrap();
+        let special_header = message.special_header.unwrap();
+        assert!(!special_header.get_send_process_id());
+        assert!(message.process_id.is_none());
+        assert!(message.copy_handles.is_empty());
+        assert_eq!(message.move_handles, [0x0001B4C2]);
+        assert_eq!(message.payload, Payload::CmifResponse { domain_header: None, data_header: cmif::DataHeader { magic: SFCO, version: 0, value: 0, token: 0 } });
+        assert!(!message.truncated);
+    }
+
+    #[test]
+    fn decode_domain_request() {
+        let message = decode_message(&to_bytes(&DOMAIN_REQUEST)).unwrap();
+        let domain_header = cmif::DomainInDataHeader { command_type: cmif::DomainCommandType::SendMessage, object_count: 0, data_size: 0x10, domain_object_id: 2, pad: 0, token: 0 };
+        assert_eq!(message.payload, Payload::CmifRequest { domain_header: Some(domain_header), data_header: cmif::DataHeader { magic: SFCI, version: 0, value: 5, token: 0 } });
+    }
+
+    #[test]
+    fn decode_tipc_messages() {
+        let message = decode_message(&to_bytes(&[tipc::CommandType::Request as u32 + 3, 0x00000002, 0xAAAA, 0xBBBB])).unwrap();
+        assert_eq!(message.payload, Payload::TipcRequest { request_id: 3 });
+        assert_eq!(message.data_words, [0xAAAA, 0xBBBB]);
+
+        let message = decode_message(&to_bytes(&[tipc::CommandType::CloseSession as u32, 0])).unwrap();
+        assert_eq!(message.payload, Payload::TipcClose);
+    }
+
+    #[test]
+    fn decode_truncated_messages() {
+        // Only the words actually in the buffer are decoded, the data header isn't
+        let message = decode_message(&to_bytes(&SM_GET_SERVICE_HANDLE[..6])).unwrap();
+        assert!(message.truncated);
+        assert_eq!(message.data_words.len(), 4);
+        assert_eq!(message.payload, Payload::None);
+
+        let message = decode_message(&to_bytes(&SM_GET_SERVICE_HANDLE_RESPONSE[..3])).unwrap();
+        assert!(message.truncated);
+        assert!(message.move_handles.is_empty());
+
+        assert_eq!(decode_message(&[0; 4]), Err(results::lib::ipc::ResultInvalidMessageSize::make()));
+    }
+}
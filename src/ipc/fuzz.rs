@@ -0,0 +1,238 @@
+use crate::result::*;
+use crate::svc;
+use crate::mem;
+use crate::version;
+use crate::ipc::sf;
+use crate::ipc::server;
+use crate::ipc::transport::loopback;
+use super::*;
+use core::mem as cmem;
+
+// Entry points for fuzzing the server side of IPC with arbitrary messages (from cargo-fuzz, a plain corpus runner or anything else able to call them)
+// Whatever the message contains, nothing here is expected to panic or touch memory outside of the message and our own buffers
+
+const FUZZ_POINTER_BUFFER_SIZE: usize = 0x100;
+
+ipc_sf_define_interface_trait! {
+    trait IFuzzTarget [cmif_tipc] {
+        get_value [0, version::VersionInterval::all()]: (value: u64) => (value: u64);
+        get_buffer_sizes [1, version::VersionInterval::all()]: (in_buf: sf::InMapAliasBuffer, out_buf: sf::OutPointerBuffer, auto_buf: sf::InAutoSelectBuffer) => (total_size: u64);
+        get_process_id [2, version::VersionInterval::all()]: (process_id: sf::ProcessId) => (process_id: u64);
+    }
+}
+
+// Buffers come from the message itself, thus they're never accessed here (only their sizes are)
+pub struct FuzzTarget {
+    session: sf::Session
+}
+
+impl IFuzzTargetServer for FuzzTarget {
+    fn get_value(&mut self, value: u64) -> Result<u64> {
+        Ok(value)
+    }
+
+    fn get_buffer_sizes(&mut self, in_buf: sf::InMapAliasBuffer, out_buf: sf::OutPointerBuffer, auto_buf: sf::InAutoSelectBuffer) -> Result<u64> {
+        Ok((in_buf.get_size() + out_buf.get_size() + auto_buf.get_size()) as u64)
+    }
+
+    fn get_process_id(&mut self, process_id: sf::ProcessId) -> Result<u64> {
+        Ok(process_id.process_id)
+    }
+}
+
+impl sf::IObject for FuzzTarget {
+    fn get_session(&mut self) -> &mut sf::Session {
+        &mut self.session
+    }
+
+    fn get_command_table(&self) -> sf::CommandMetadataTable {
+        Self::get_interface_command_table()
+    }
+}
+
+impl server::IServerObject for FuzzTarget {
+    fn new() -> Self {
+        Self { session: sf::Session::new() }
+    }
+}
+
+#[inline(always)]
+fn make_fuzz_message(data: &[u8]) -> [u8; transport::MSG_BUFFER_SIZE] {
+    let mut msg: [u8; transport::MSG_BUFFER_SIZE] = [0; transport::MSG_BUFFER_SIZE];
+    let size = data.len().min(msg.len());
+    msg[..size].copy_from_slice(&data[..size]);
+    msg
+}
+
+// Parses the message as a request for every kind of session (CMIF, CMIF domain and TIPC)
+pub fn fuzz_request_parsing(data: &[u8]) {
+    let msg = make_fuzz_message(data);
+
+    for object_info in [ObjectInfo::from_handle_with_protocol(1, CommandProtocol::Cmif), ObjectInfo::from_domain_object_id(1, 1), ObjectInfo::from_handle_with_protocol(1, CommandProtocol::Tipc)] {
+        let mut ctx = CommandContext::new_server(object_info, ptr::null_mut(), 0);
+        match object_info.uses_tipc_protocol() {
+            true => {
+                if tipc::server::read_command_from_msg(&mut ctx, &msg).is_ok() {
+                    let _ = tipc::server::read_request_command_from_msg(&mut ctx, &msg);
+                }
+            },
+            false => match cmif::server::read_command_from_msg(&mut ctx, &msg) {
+                Ok(cmif::CommandType::Request | cmif::CommandType::RequestWithContext) => {
+                    let _ = cmif::server::read_request_command_from_msg(&mut ctx, &msg);
+                },
+                Ok(cmif::CommandType::Control | cmif::CommandType::ControlWithContext) => {
+                    let _ = cmif::server::read_control_command_from_msg(&mut ctx, &msg);
+                },
+                _ => {}
+            }
+        };
+    }
+}
+
+// Handles and receive static addresses in the message are just values which might belong to anything in our process:
+// handles would get closed (unused handles are closed by the server), and replies would get copied to receive statics (by the loopback transport)
+fn sanitize_fuzz_message(msg: &mut [u8; transport::MSG_BUFFER_SIZE]) -> Result<()> {
+    let mut reader = BoundedReader::new(msg);
+    let command_header: CommandHeader = reader.read()?;
+
+    let mut handles_range = (0, 0);
+    if command_header.get_has_special_header() {
+        let special_header: CommandSpecialHeader = reader.read()?;
+        if special_header.get_send_process_id() {
+            reader.skip(cmem::size_of::<u64>())?;
+        }
+        let handle_count = (special_header.get_copy_handle_count() + special_header.get_move_handle_count()) as usize;
+        handles_range = (reader.get_offset(), (reader.get_offset() + handle_count * cmem::size_of::<svc::Handle>()).min(msg.len()));
+        reader.seek(handles_range.1)?;
+    }
+
+    let descriptors_size = cmem::size_of::<SendStaticDescriptor>() * command_header.get_send_static_count() as usize + cmem::size_of::<BufferDescriptor>() * (command_header.get_send_buffer_count() + command_header.get_receive_buffer_count() + command_header.get_exchange_buffer_count()) as usize;
+    let receive_statics_offset = reader.get_offset() + descriptors_size + cmem::size_of::<u32>() * command_header.get_data_word_count() as usize;
+
+    let mut receive_statics: [Option<ReceiveStaticDescriptor>; 0x10] = [None; 0x10];
+    for (i, receive_static) in receive_statics.iter_mut().enumerate().take(command_header.get_receive_static_count() as usize) {
+        *receive_static = reader.read_at(receive_statics_offset + i * cmem::size_of::<ReceiveStaticDescriptor>()).ok();
+    }
+
+    msg[handles_range.0..handles_range.1].fill(0);
+    for (i, receive_static) in receive_statics.iter().enumerate() {
+        if let Some(receive_static) = receive_static {
+            let offset = receive_statics_offset + i * cmem::size_of::<ReceiveStaticDescriptor>();
+            let cleared_receive_static = ReceiveStaticDescriptor::new(ptr::null(), receive_static.get_size());
+            unsafe {
+                (msg.as_mut_ptr().add(offset) as *mut ReceiveStaticDescriptor).write_unaligned(cleared_receive_static);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Sends the message to a new FuzzTarget session, processed by a ServerManager through the loopback transport (no kernel objects involved)
+// Any result is fine, what matters is that the call returns
+pub fn fuzz_request_dispatch(data: &[u8], protocol: CommandProtocol) -> Result<()> {
+    let mut msg = make_fuzz_message(data);
+    // A message we can't even sanitize can't be parsed by the server either, thus it's still worth sending
+    let _ = sanitize_fuzz_message(&mut msg);
+
+    let mut loopback_transport = loopback::LoopbackTransport::<FUZZ_POINTER_BUFFER_SIZE>::new()?;
+    let object_info = loopback_transport.connect::<FuzzTarget>(protocol)?;
+    let server_manager = loopback_transport.get_server_manager();
+    transport::set_transport(mem::Shared::new(loopback_transport));
+
+    unsafe {
+        ptr::copy(msg.as_ptr(), get_msg_buffer(), msg.len());
+    }
+    let rc = transport::send_sync_request(object_info.handle);
+
+    // Sessions must be closed while the loopback transport is still in place, their handles mean nothing to the kernel
+    server_manager.get().close_all();
+    transport::reset_transport();
+    rc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    const SFCI: u32 = cmif::IN_DATA_HEADER_MAGIC;
+
+    // Valid requests for every command, plus messages no client would send (huge counts, headers claiming more than the message)
+    const CORPUS: &[&[u32]] = &[
+        // get_value(0x1234)
+        &[0x00000004, 0x0000000A, 0, 0, SFCI, 0, 0, 0, 0x1234, 0, 0, 0],
+        // get_buffer_sizes with an empty send buffer and no receive statics
+        &[0x00100004, 0x0000000C, 0, 0, 0, 0, 0, 0, SFCI, 0, 1, 0, 0, 0, 0],
+        // get_process_id, sending the process ID
+        &[0x00000004, 0x8000000C, 0x00000001, 0x55, 0, 0, 0, 0, SFCI, 0, 2, 0, 0, 0, 0, 0, 0],
+        // Copy handles which aren't ours, and a single receive static covering the whole buffer
+        &[0x00000004, 0x80000B0A, 0x00000004, 0xDEAD, 0xBEEF, 0, SFCI, 0, 0, 0, 0, 0, 0, 0, 0x1000, 0x0100_0000],
+        // QueryPointerBufferSize (control command)
+        &[0x00000005, 0x00000008, 0, 0, SFCI, 0, 3, 0, 0, 0],
+        // Close
+        &[0x00000002, 0],
+        // TIPC get_value(0x1234) and close
+        &[0x00000010, 0x00000002, 0x1234, 0],
+        &[0x0000000F, 0],
+        // Every count at its maximum
+        &[0xFFFFFFFF, 0xFFFFFFFF, 0xFFFFFFFF, 0xFFFFFFFF],
+        // Data words past the end of the message buffer
+        &[0x00000004, 0x000003FF, 0, 0, SFCI, 0, 0, 0],
+        // Wrong data header magic
+        &[0x00000004, 0x0000000A, 0, 0, 0x12345678, 0, 0, 0, 0, 0, 0, 0]
+    ];
+
+    fn to_bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    // Small deterministic xorshift, so that failures are reproducible
+    fn mutate(data: &mut [u8], state: &mut u64) {
+        for _ in 0..4 {
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            let index = (*state as usize) % data.len();
+            data[index] ^= (*state >> 32) as u8;
+        }
+    }
+
+    #[test]
+    fn parse_corpus() {
+        for words in CORPUS {
+            let data = to_bytes(words);
+            // Every truncation of the message too
+            for size in 0..=data.len() {
+                fuzz_request_parsing(&data[..size]);
+            }
+        }
+    }
+
+    #[test]
+    fn dispatch_corpus() {
+        for words in CORPUS {
+            let data = to_bytes(words);
+            for protocol in [CommandProtocol::Cmif, CommandProtocol::Tipc] {
+                let _ = fuzz_request_dispatch(&data, protocol);
+            }
+        }
+
+        // Valid requests do get processed
+        assert!(fuzz_request_dispatch(&to_bytes(CORPUS[0]), CommandProtocol::Cmif).is_ok());
+        assert!(fuzz_request_dispatch(&to_bytes(CORPUS[6]), CommandProtocol::Tipc).is_ok());
+    }
+
+    #[test]
+    fn mutated_corpus() {
+        let mut state: u64 = 0x9E3779B97F4A7C15;
+        for words in CORPUS {
+            let mut data = to_bytes(words);
+            for _ in 0..0x40 {
+                mutate(&mut data, &mut state);
+                fuzz_request_parsing(&data);
+                let _ = fuzz_request_dispatch(&data, CommandProtocol::Cmif);
+                let _ = fuzz_request_dispatch(&data, CommandProtocol::Tipc);
+            }
+        }
+    }
+}
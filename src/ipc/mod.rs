@@ -1,10 +1,11 @@
 use crate::result::*;
 use crate::results;
 use crate::svc;
-use crate::thread;
+use crate::sync;
 use core::ptr;
 use core::mem;
 use arrayvec::ArrayVec;
+use alloc::vec::Vec;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(u8)]
@@ -32,6 +33,10 @@ impl ObjectInfo {
         Self { handle, domain_object_id: 0, owns_handle: true, protocol: CommandProtocol::Cmif }
     }
 
+    pub const fn from_handle_with_protocol(handle: svc::Handle, protocol: CommandProtocol) -> Self {
+        Self { handle, domain_object_id: 0, owns_handle: true, protocol }
+    }
+
     pub const fn from_domain_object_id(parent_handle: svc::Handle, domain_object_id: cmif::DomainObjectId) -> Self {
         Self { handle: parent_handle, domain_object_id, owns_handle: false, protocol: CommandProtocol::Cmif }
     }
@@ -66,12 +71,65 @@ impl ObjectInfo {
         ipc_client_send_control_command!([*self; cmif::ControlRequestId::QueryPointerBufferSize] () => (pointer_buffer_size: u16))
     }
 
+    pub fn get_pointer_buffer_size(&mut self) -> Result<u16> {
+        if let Some(pointer_buffer_size) = find_cached_pointer_buffer_size(self.handle) {
+            return Ok(pointer_buffer_size);
+        }
+
+        let pointer_buffer_size = self.query_pointer_buffer_size()?;
+        cache_pointer_buffer_size(self.handle, pointer_buffer_size);
+        Ok(pointer_buffer_size)
+    }
+
+    pub fn copy_from_current_domain(&mut self, domain_object_id: cmif::DomainObjectId) -> Result<sf::MoveHandle> {
+        if self.uses_tipc_protocol() {
+            return Err(results::lib::ResultNotSupported::make());
+        }
+        ipc_client_send_control_command!([*self; cmif::ControlRequestId::CopyFromCurrentDomain] (domain_object_id) => (handle: sf::MoveHandle))
+    }
+
     pub fn clone_current_object(&mut self) -> Result<sf::MoveHandle> {
         if self.uses_tipc_protocol() {
             return Err(results::lib::ResultNotSupported::make());
         }
         ipc_client_send_control_command!([*self; cmif::ControlRequestId::CloneCurrentObject] () => (cloned_handle: sf::MoveHandle))
     }
+
+    pub fn clone_current_object_ex(&mut self, tag: u32) -> Result<sf::MoveHandle> {
+        if self.uses_tipc_protocol() {
+            return Err(results::lib::ResultNotSupported::make());
+        }
+        ipc_client_send_control_command!([*self; cmif::ControlRequestId::CloneCurrentObjectEx] (tag) => (cloned_handle: sf::MoveHandle))
+    }
+}
+
+// Pointer buffer sizes don't change during a session's lifetime, thus they're only queried once per session (domain objects share their parent session's one)
+
+static mut G_POINTER_BUFFER_SIZES: sync::Locked<Vec<(svc::Handle, u16)>> = sync::Locked::new(true, Vec::new());
+
+fn find_cached_pointer_buffer_size(handle: svc::Handle) -> Option<u16> {
+    unsafe {
+        let _lock = sync::ScopedLock::new(G_POINTER_BUFFER_SIZES.get_lock());
+        G_POINTER_BUFFER_SIZES.get().iter().find(|(cached_handle, _)| *cached_handle == handle).map(|(_, pointer_buffer_size)| *pointer_buffer_size)
+    }
+}
+
+fn cache_pointer_buffer_size(handle: svc::Handle, pointer_buffer_size: u16) {
+    unsafe {
+        let _lock = sync::ScopedLock::new(G_POINTER_BUFFER_SIZES.get_lock());
+        let cached_sizes = G_POINTER_BUFFER_SIZES.get();
+        if !cached_sizes.iter().any(|(cached_handle, _)| *cached_handle == handle) {
+            cached_sizes.push((handle, pointer_buffer_size));
+        }
+    }
+}
+
+// Called whenever a handle gets closed (see transport::close_handle and svc::OwnedHandle), since its value might be reused for a different session later
+pub fn forget_pointer_buffer_size(handle: svc::Handle) {
+    unsafe {
+        let _lock = sync::ScopedLock::new(G_POINTER_BUFFER_SIZES.get_lock());
+        G_POINTER_BUFFER_SIZES.get().retain(|(cached_handle, _)| *cached_handle != handle);
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -129,6 +187,10 @@ impl BufferDescriptor {
         let size_high = read_bits!(24, 27, self.bits);
         self.size_low as usize | ((size_high as usize) << 32)
     }
+
+    pub const fn get_flags(&self) -> u32 {
+        read_bits!(0, 1, self.bits)
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -166,6 +228,10 @@ impl SendStaticDescriptor {
     pub const fn get_size(&self) -> usize {
         read_bits!(16, 31, self.bits) as usize
     }
+
+    pub const fn get_index(&self) -> u32 {
+        read_bits!(0, 5, self.bits)
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -342,16 +408,22 @@ const MAX_COUNT: usize = 8;
 #[derive(Copy, Clone)]
 pub struct DataWalker {
     ptr: *mut u8,
-    cur_offset: isize
+    cur_offset: isize,
+    // Only enforced by try_advance_get, for data coming from other processes
+    size: isize
 }
 
 impl DataWalker {
     pub fn empty() -> Self {
-        Self { ptr: ptr::null_mut(), cur_offset: 0 }
+        Self { ptr: ptr::null_mut(), cur_offset: 0, size: isize::MAX }
     }
 
     pub fn new(ptr: *mut u8) -> Self {
-        Self { ptr, cur_offset: 0 }
+        Self { ptr, cur_offset: 0, size: isize::MAX }
+    }
+
+    pub fn new_bounded(ptr: *mut u8, size: usize) -> Self {
+        Self { ptr, cur_offset: 0, size: size as isize }
     }
 
     pub fn advance<T>(&mut self) {
@@ -369,11 +441,20 @@ impl DataWalker {
             let offset = self.cur_offset;
             self.cur_offset += core::mem::size_of::<T>() as isize;
 
+            // Offsets are aligned relative to the data start, which itself might not be aligned (like TIPC data, right after the header)
             let data_ref = self.ptr.offset(offset) as *const T;
-            data_ref.read_volatile()
+            data_ref.read_unaligned()
         }
     }
 
+    pub fn try_advance_get<T>(&mut self) -> Result<T> {
+        let align_of_type = core::mem::align_of::<T>() as isize;
+        let offset = (self.cur_offset + align_of_type - 1) / align_of_type * align_of_type;
+        result_return_if!((offset + core::mem::size_of::<T>() as isize) > self.size, results::lib::ipc::ResultInvalidMessageSize);
+
+        Ok(self.advance_get())
+    }
+
     pub fn advance_set<T>(&mut self, t: T) {
         unsafe {
             let align_of_type = core::mem::align_of::<T>() as isize;
@@ -383,7 +464,7 @@ impl DataWalker {
             self.cur_offset += core::mem::size_of::<T>() as isize;
 
             let data_ref = self.ptr.offset(offset) as *mut T;
-            data_ref.write_volatile(t);
+            data_ref.write_unaligned(t);
         }
     }
 
@@ -403,9 +484,12 @@ impl DataWalker {
 
 #[inline(always)]
 pub fn get_msg_buffer() -> *mut u8 {
-    unsafe {
-        (*thread::get_thread_local_region()).msg_buffer.as_mut_ptr()
-    }
+    transport::get_msg_buffer()
+}
+
+#[inline(always)]
+pub fn get_msg_buffer_slice() -> &'static [u8] {
+    unsafe { core::slice::from_raw_parts(get_msg_buffer(), transport::MSG_BUFFER_SIZE) }
 }
 
 #[inline(always)]
@@ -427,6 +511,68 @@ pub fn write_array_to_buffer<T: Copy>(buffer: *mut u8, count: u32, array: &Array
     }
 }
 
+// Bounded reading of raw messages, for the ones coming from other processes (thus never reading past the given message whatever its headers say)
+pub struct BoundedReader<'a> {
+    msg: &'a [u8],
+    offset: usize
+}
+
+impl<'a> BoundedReader<'a> {
+    pub const fn new(msg: &'a [u8]) -> Self {
+        Self { msg, offset: 0 }
+    }
+
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn get_ptr(&self) -> *mut u8 {
+        unsafe { self.msg.as_ptr().add(self.offset) as *mut u8 }
+    }
+
+    pub fn get_remaining_size(&self) -> usize {
+        self.msg.len() - self.offset
+    }
+
+    pub fn seek(&mut self, offset: usize) -> Result<()> {
+        result_return_if!(offset > self.msg.len(), results::lib::ipc::ResultInvalidMessageSize);
+        self.offset = offset;
+        Ok(())
+    }
+
+    pub fn skip(&mut self, size: usize) -> Result<()> {
+        result_return_if!(size > self.get_remaining_size(), results::lib::ipc::ResultInvalidMessageSize);
+        self.offset += size;
+        Ok(())
+    }
+
+    pub fn read_at<T: Copy>(&self, offset: usize) -> Result<T> {
+        result_return_if!(offset.checked_add(mem::size_of::<T>()).map_or(true, |end| end > self.msg.len()), results::lib::ipc::ResultInvalidMessageSize);
+        unsafe {
+            Ok((self.msg.as_ptr().add(offset) as *const T).read_unaligned())
+        }
+    }
+
+    pub fn read<T: Copy>(&mut self) -> Result<T> {
+        let value = self.read_at(self.offset)?;
+        self.offset += mem::size_of::<T>();
+        Ok(value)
+    }
+
+    pub fn read_array<T: Copy>(&mut self, count: u32, array: &mut ArrayVec<[T; MAX_COUNT]>, invalid_count_rc: ResultCode) -> Result<()> {
+        if count as usize > MAX_COUNT {
+            return Err(invalid_count_rc);
+        }
+
+        array.clear();
+        for _ in 0..count {
+            let value = self.read()?;
+            array.push(value);
+        }
+        Ok(())
+    }
+}
+
 #[inline(always)]
 pub fn get_aligned_data_offset(data_words_offset: *mut u8, base_offset: *mut u8) -> *mut u8 {
     let align = DATA_PADDING as usize - 1;
@@ -440,6 +586,7 @@ pub struct CommandIn {
     pub data_size: u32,
     pub data_offset: *mut u8,
     pub data_words_offset: *mut u8,
+    pub data_words_size: u32,
     pub objects_offset: *mut u8,
     copy_handles: ArrayVec<[svc::Handle; MAX_COUNT]>,
     move_handles: ArrayVec<[svc::Handle; MAX_COUNT]>,
@@ -449,7 +596,7 @@ pub struct CommandIn {
 
 impl CommandIn {
     pub fn empty() -> Self {
-        Self { send_process_id: false, process_id: 0, data_size: 0, data_offset: ptr::null_mut(), data_words_offset: ptr::null_mut(), objects_offset: ptr::null_mut(), copy_handles: ArrayVec::new(), move_handles: ArrayVec::new(), objects: ArrayVec::new(), out_pointer_sizes: ArrayVec::new() }
+        Self { send_process_id: false, process_id: 0, data_size: 0, data_offset: ptr::null_mut(), data_words_offset: ptr::null_mut(), data_words_size: 0, objects_offset: ptr::null_mut(), copy_handles: ArrayVec::new(), move_handles: ArrayVec::new(), objects: ArrayVec::new(), out_pointer_sizes: ArrayVec::new() }
     }
     
     pub fn add_copy_handle(&mut self, handle: svc::Handle) -> Result<()> {
@@ -466,10 +613,11 @@ impl CommandIn {
         }
     }
 
-    pub fn add_handle<const M: HandleMode>(&mut self, handle: sf::Handle<M>) -> Result<()> {
+    pub fn add_handle<const M: HandleMode>(&mut self, handle: &sf::Handle<M>) -> Result<()> {
+        // Moved handles get closed on our side by the kernel once sent
         match M {
-            HandleMode::Copy => self.add_copy_handle(handle.handle),
-            HandleMode::Move => self.add_move_handle(handle.handle)
+            HandleMode::Copy => self.add_copy_handle(handle.as_raw()),
+            HandleMode::Move => self.add_move_handle(handle.release())
         }
     }
 
@@ -495,6 +643,49 @@ impl CommandIn {
             Err(_) => Err(results::lib::ipc::ResultPointerSizesFull::make())
         }
     }
+
+    pub fn pop_copy_handle(&mut self) -> Result<svc::Handle> {
+        match self.copy_handles.pop_at(0) {
+            Some(handle) => Ok(handle),
+            None => Err(results::cmif::ResultInvalidInObjectCount::make())
+        }
+    }
+
+    pub fn pop_move_handle(&mut self) -> Result<svc::Handle> {
+        match self.move_handles.pop_at(0) {
+            Some(handle) => Ok(handle),
+            None => Err(results::cmif::ResultInvalidInObjectCount::make())
+        }
+    }
+
+    pub fn pop_handle<const M: HandleMode>(&mut self) -> Result<sf::Handle<M>> {
+        let handle = match M {
+            HandleMode::Copy => self.pop_copy_handle()?,
+            HandleMode::Move => self.pop_move_handle()?,
+        };
+        unsafe {
+            Ok(sf::Handle::from_raw(handle, true))
+        }
+    }
+
+    pub fn pop_domain_object(&mut self) -> Result<cmif::DomainObjectId> {
+        match self.objects.pop_at(0) {
+            Some(domain_object_id) => Ok(domain_object_id),
+            None => Err(results::cmif::ResultInvalidInObjectCount::make())
+        }
+    }
+
+    pub fn validate_handles_consumed(&mut self) -> Result<()> {
+        if self.copy_handles.is_empty() && self.move_handles.is_empty() {
+            return Ok(());
+        }
+
+        // The client sent more handles than the command takes: close them, since nobody else will
+        for handle in self.copy_handles.drain(..).chain(self.move_handles.drain(..)) {
+            let _ = transport::close_handle(handle);
+        }
+        Err(results::cmif::ResultInvalidInObjectCount::make())
+    }
 }
 
 pub struct CommandOut {
@@ -529,10 +720,12 @@ impl CommandOut {
 
     pub fn pop_handle<const M: HandleMode>(&mut self) -> Result<sf::Handle<M>> {
         let handle = match M {
-            HandleMode::Copy => sf::Handle::from(self.pop_copy_handle()?),
-            HandleMode::Move => sf::Handle::from(self.pop_move_handle()?),
+            HandleMode::Copy => self.pop_copy_handle()?,
+            HandleMode::Move => self.pop_move_handle()?,
         };
-        Ok(handle)
+        unsafe {
+            Ok(sf::Handle::from_raw(handle, true))
+        }
     }
 
     pub fn push_copy_handle(&mut self, handle: svc::Handle) -> Result<()> {
@@ -549,10 +742,11 @@ impl CommandOut {
         }
     }
 
-    pub fn push_handle<const M: HandleMode>(&mut self, handle: sf::Handle<M>) -> Result<()> {
+    pub fn push_handle<const M: HandleMode>(&mut self, handle: &sf::Handle<M>) -> Result<()> {
+        // Same as above, moved handles get closed on our side by the kernel once the reply is sent
         match M {
-            HandleMode::Copy => self.push_copy_handle(handle.handle),
-            HandleMode::Move => self.push_move_handle(handle.handle)
+            HandleMode::Copy => self.push_copy_handle(handle.as_raw()),
+            HandleMode::Move => self.push_move_handle(handle.release())
         }
     }
 
@@ -581,15 +775,17 @@ pub struct CommandContext {
     receive_buffers: ArrayVec<[BufferDescriptor; MAX_COUNT]>,
     exchange_buffers: ArrayVec<[BufferDescriptor; MAX_COUNT]>,
     pointer_buffer: *mut u8,
+    pointer_buffer_size: usize,
     in_pointer_buffer_offset: usize,
     out_pointer_buffer_offset: usize,
     pointer_size_walker: DataWalker,
-    pointer_size_walker_initialized: bool
+    pointer_size_walker_initialized: bool,
+    out_pointer_index: u32
 }
 
 impl CommandContext {
     pub fn empty() -> Self {
-        Self { object_info: ObjectInfo::new(), in_params: CommandIn::empty(), out_params: CommandOut::empty(), send_statics: ArrayVec::new(), receive_statics: ArrayVec::new(), send_buffers: ArrayVec::new(), receive_buffers: ArrayVec::new(), exchange_buffers: ArrayVec::new(), pointer_buffer: core::ptr::null_mut(), in_pointer_buffer_offset: 0, out_pointer_buffer_offset: 0, pointer_size_walker: DataWalker::empty(), pointer_size_walker_initialized: false }
+        Self { object_info: ObjectInfo::new(), in_params: CommandIn::empty(), out_params: CommandOut::empty(), send_statics: ArrayVec::new(), receive_statics: ArrayVec::new(), send_buffers: ArrayVec::new(), receive_buffers: ArrayVec::new(), exchange_buffers: ArrayVec::new(), pointer_buffer: core::ptr::null_mut(), pointer_buffer_size: 0, in_pointer_buffer_offset: 0, out_pointer_buffer_offset: 0, pointer_size_walker: DataWalker::empty(), pointer_size_walker_initialized: false, out_pointer_index: 0 }
     }
 
     pub fn new_client(object_info: ObjectInfo) -> Self {
@@ -607,19 +803,31 @@ impl CommandContext {
                 }
                 data_size = (data_size + 1) & !1;
                 let out_pointer_sizes_offset = unsafe { self.in_params.data_words_offset.offset(data_size) };
-                self.pointer_size_walker = DataWalker::new(out_pointer_sizes_offset);
+                // The sizes are within the data words sent by the client
+                let out_pointer_sizes_size = (self.in_params.data_words_size as isize - data_size).max(0);
+                self.pointer_size_walker = DataWalker::new_bounded(out_pointer_sizes_offset, out_pointer_sizes_size as usize);
             }
             self.pointer_size_walker_initialized = true;
         }
     }
     
-    pub fn new_server(object_info: ObjectInfo, pointer_buffer: *mut u8) -> Self {
+    pub fn new_server(object_info: ObjectInfo, pointer_buffer: *mut u8, pointer_buffer_size: usize) -> Self {
         let mut ctx = Self::empty();
         ctx.object_info = object_info;
         ctx.pointer_buffer = pointer_buffer;
+        ctx.pointer_buffer_size = pointer_buffer_size;
         ctx
     }
 
+    // Out pointer buffers are carved out of our pointer buffer with the sizes the client asked for
+    fn allocate_out_pointer_buffer(&mut self, buf_size: usize) -> Result<*mut u8> {
+        result_return_if!(buf_size > (self.pointer_buffer_size - self.out_pointer_buffer_offset), results::lib::ipc::ResultBufferTooSmall);
+
+        let buf = unsafe { self.pointer_buffer.add(self.out_pointer_buffer_offset) };
+        self.out_pointer_buffer_offset += buf_size;
+        Ok(buf)
+    }
+
     pub fn add_send_static(&mut self, send_static: SendStaticDescriptor) -> Result<()> {
         match self.send_statics.try_push(send_static) {
             Ok(()) => Ok(()),
@@ -655,43 +863,36 @@ impl CommandContext {
         }
     }
 
-    pub fn add_buffer<const A: BufferAttribute, const S: usize>(&mut self, buffer: sf::Buffer<A, S>) -> Result<()> {
+    pub fn add_buffer<const A: BufferAttribute, const S: usize>(&mut self, buffer_address: *const u8, buffer_size: usize) -> Result<()> {
         let is_in = A.contains(BufferAttribute::In());
         let is_out = A.contains(BufferAttribute::Out());
 
         if A.contains(BufferAttribute::AutoSelect()) {
-            if self.pointer_buffer.is_null() {
-                self.pointer_buffer = self.object_info.query_pointer_buffer_size()? as *mut u8;
-            }
-
-            let pointer_buf_size = self.pointer_buffer as usize;
-            let mut buffer_in_static = false;
-            if pointer_buf_size > 0 {
-                let left_size = pointer_buf_size - self.in_pointer_buffer_offset;
-                buffer_in_static = buffer.size <= left_size;
-            }
+            // Use the server's pointer buffer if the buffer still fits there, map-alias it otherwise (the descriptor of the other kind is sent empty)
+            let pointer_buf_size = self.object_info.get_pointer_buffer_size()? as usize;
+            let buffer_in_static = buffer_size <= pointer_buf_size.saturating_sub(self.in_pointer_buffer_offset);
             if buffer_in_static {
-                self.in_pointer_buffer_offset += buffer.size;
+                self.in_pointer_buffer_offset += buffer_size;
             }
             
             if is_in {
                 if buffer_in_static {
                     self.add_send_buffer(BufferDescriptor::new(ptr::null(), 0, BufferFlags::Normal))?;
-                    self.add_send_static(SendStaticDescriptor::new(buffer.buf, buffer.size, self.send_statics.len() as u32))?;
+                    self.add_send_static(SendStaticDescriptor::new(buffer_address, buffer_size, self.send_statics.len() as u32))?;
                 }
                 else {
-                    self.add_send_buffer(BufferDescriptor::new(buffer.buf, buffer.size, BufferFlags::Normal))?;
+                    self.add_send_buffer(BufferDescriptor::new(buffer_address, buffer_size, BufferFlags::Normal))?;
                     self.add_send_static(SendStaticDescriptor::new(ptr::null(), 0, self.send_statics.len() as u32))?;
                 }
             }
             else if is_out {
                 if buffer_in_static {
                     self.add_receive_buffer(BufferDescriptor::new(ptr::null(), 0, BufferFlags::Normal))?;
-                    self.add_receive_static(ReceiveStaticDescriptor::new(buffer.buf, buffer.size))?;
-                    self.in_params.add_out_pointer_size(buffer.size as u16)?;
+                    self.add_receive_static(ReceiveStaticDescriptor::new(buffer_address, buffer_size))?;
+                    self.in_params.add_out_pointer_size(buffer_size as u16)?;
                 }
                 else {
-                    self.add_receive_buffer(BufferDescriptor::new(buffer.buf, buffer.size, BufferFlags::Normal))?;
+                    self.add_receive_buffer(BufferDescriptor::new(buffer_address, buffer_size, BufferFlags::Normal))?;
                     self.add_receive_static(ReceiveStaticDescriptor::new(ptr::null(), 0))?;
                     self.in_params.add_out_pointer_size(0)?;
                 }
@@ -699,12 +900,12 @@ impl CommandContext {
         }
         else if A.contains(BufferAttribute::Pointer()) {
             if is_in {
-                self.add_send_static(SendStaticDescriptor::new(buffer.buf, buffer.size, self.send_statics.len() as u32))?;
+                self.add_send_static(SendStaticDescriptor::new(buffer_address, buffer_size, self.send_statics.len() as u32))?;
             }
             else if is_out {
-                self.add_receive_static(ReceiveStaticDescriptor::new(buffer.buf, buffer.size))?;
+                self.add_receive_static(ReceiveStaticDescriptor::new(buffer_address, buffer_size))?;
                 if !A.contains(BufferAttribute::FixedSize()) {
-                    self.in_params.add_out_pointer_size(buffer.size as u16)?;
+                    self.in_params.add_out_pointer_size(buffer_size as u16)?;
                 }
             }
         }
@@ -716,7 +917,7 @@ impl CommandContext {
             else if A.contains(BufferAttribute::MapTransferAllowsNonDevice()) {
                 flags = BufferFlags::NonDevice;
             }
-            let buf_desc = BufferDescriptor::new(buffer.buf, buffer.size, flags);
+            let buf_desc = BufferDescriptor::new(buffer_address, buffer_size, flags);
             if is_in && is_out {
                 self.add_exchange_buffer(buf_desc)?;
             }
@@ -769,7 +970,8 @@ impl CommandContext {
         }
     }
 
-    pub fn pop_buffer<const A: BufferAttribute, const S: usize>(&mut self, raw_data_walker: &mut DataWalker) -> Result<sf::Buffer<A, S>> {
+    // Note: the popped buffers (address and size) point to the received request (our pointer buffer or memory mapped by the kernel), thus they are only valid while it is being handled
+    pub fn pop_buffer<const A: BufferAttribute, const S: usize>(&mut self, raw_data_walker: &mut DataWalker) -> Result<(*mut u8, usize)> {
         let is_in = A.contains(BufferAttribute::In());
         let is_out = A.contains(BufferAttribute::Out());
 
@@ -778,23 +980,30 @@ impl CommandContext {
                 if let Ok(static_desc) = self.pop_send_static() {
                     if let Ok(send_desc) = self.pop_send_buffer() {
                         if !static_desc.get_address().is_null() && (static_desc.get_size() > 0) {
-                            return Ok(sf::Buffer::from_mut(static_desc.get_address(), static_desc.get_size()));
+                            return Ok((static_desc.get_address(), static_desc.get_size()));
                         }
                         if !send_desc.get_address().is_null() && (send_desc.get_size() > 0) {
-                            return Ok(sf::Buffer::from_mut(send_desc.get_address(), send_desc.get_size()));
+                            return Ok((send_desc.get_address(), send_desc.get_size()));
                         }
                     }
                 }
             }
             else if is_out {
-                if let Ok(static_desc) = self.pop_receive_static() {
+                // The receive static is the client's buffer (in its own address space), the sent out pointer size tells us whether the buffer was sent that way (otherwise it's zero)
+                if self.pop_receive_static().is_ok() {
                     if let Ok(recv_desc) = self.pop_receive_buffer() {
-                        if !static_desc.get_address().is_null() && (static_desc.get_size() > 0) {
-                            return Ok(sf::Buffer::from_mut(static_desc.get_address(), static_desc.get_size()));
-                        }
+                        self.ensure_pointer_size_walker(raw_data_walker);
+                        let buf_size = self.pointer_size_walker.try_advance_get::<u16>()? as usize;
+                        let out_pointer_index = self.out_pointer_index;
+                        self.out_pointer_index += 1;
+
                         if !recv_desc.get_address().is_null() && (recv_desc.get_size() > 0) {
-                            return Ok(sf::Buffer::from_mut(recv_desc.get_address(), recv_desc.get_size()));
+                            return Ok((recv_desc.get_address(), recv_desc.get_size()));
                         }
+
+                        let buf = self.allocate_out_pointer_buffer(buf_size)?;
+                        self.add_send_static(SendStaticDescriptor::new(buf, buf_size, out_pointer_index))?;
+                        return Ok((buf, buf_size));
                     }
                 }
             }
@@ -802,7 +1011,7 @@ impl CommandContext {
         else if A.contains(BufferAttribute::Pointer()) {
             if is_in {
                 if let Ok(static_desc) = self.pop_send_static() {
-                    return Ok(sf::Buffer::from_mut(static_desc.get_address(), static_desc.get_size()));
+                    return Ok((static_desc.get_address(), static_desc.get_size()));
                 }
             }
             else if is_out {
@@ -810,29 +1019,32 @@ impl CommandContext {
                     true => S,
                     false => {
                         self.ensure_pointer_size_walker(raw_data_walker);
-                        self.pointer_size_walker.advance_get::<u16>() as usize
+                        self.pointer_size_walker.try_advance_get::<u16>()? as usize
                     }
                 };
 
-                let buf = unsafe { self.pointer_buffer.add(self.out_pointer_buffer_offset) };
-                self.out_pointer_buffer_offset += buf_size;
-                return Ok(sf::Buffer::from_mut(buf, buf_size));
+                let buf = self.allocate_out_pointer_buffer(buf_size)?;
+
+                // Out pointer buffers are sent back as send statics, targeting the client's receive static at the same index
+                self.add_send_static(SendStaticDescriptor::new(buf, buf_size, self.out_pointer_index))?;
+                self.out_pointer_index += 1;
+                return Ok((buf, buf_size));
             }
         }
         else if A.contains(BufferAttribute::MapAlias()) {
             if is_in && is_out {
                 if let Ok(exch_desc) = self.pop_exchange_buffer() {
-                    return Ok(sf::Buffer::from_mut(exch_desc.get_address(), exch_desc.get_size()));
+                    return Ok((exch_desc.get_address(), exch_desc.get_size()));
                 }
             }
             else if is_in {
                 if let Ok(send_desc) = self.pop_send_buffer() {
-                    return Ok(sf::Buffer::from_mut(send_desc.get_address(), send_desc.get_size()));
+                    return Ok((send_desc.get_address(), send_desc.get_size()));
                 }
             }
             else if is_out {
                 if let Ok(recv_desc) = self.pop_receive_buffer() {
-                    return Ok(sf::Buffer::from_mut(recv_desc.get_address(), recv_desc.get_size()));
+                    return Ok((recv_desc.get_address(), recv_desc.get_size()));
                 }
             }
         }
@@ -848,7 +1060,7 @@ impl CommandContext {
         }
         else {
             let handle: sf::MoveHandle = self.out_params.pop_handle()?;
-            object_info = ObjectInfo::from_handle(handle.handle);
+            object_info = ObjectInfo::from_handle(handle.into_raw());
         }
         Ok(object_info)
     }
@@ -862,4 +1074,12 @@ pub mod cmif;
 
 pub mod tipc;
 
-pub mod sf;
\ No newline at end of file
+pub mod sf;
+
+pub mod transport;
+
+pub mod decode;
+
+// Always built for tests, which run the fuzz entry points over a corpus
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
\ No newline at end of file
@@ -2,29 +2,183 @@ use crate::result::*;
 use crate::results;
 use crate::svc;
 use crate::wait;
+use crate::sync;
+use crate::thread;
+use crate::arm;
 use crate::ipc::sf::IObject;
 use crate::ipc::sf::hipc::IHipcManager;
-use crate::ipc::sf::hipc::IMitmQueryServer;
+use crate::ipc::sf::hipc::IMitmQueryServerServer;
 use crate::service;
 use crate::service::sm;
 use crate::service::sm::IUserInterface;
 use crate::mem;
+use crate::util;
 use super::*;
 use alloc::vec::Vec;
+use arrayvec::ArrayVec;
+use core::cell::UnsafeCell;
 
-// TODO: tipc support, implement remaining control commands
+// TODO: implement remaining control commands
 
 const MAX_COUNT: usize = wait::MAX_OBJECT_COUNT as usize;
 
+const IPC_BUF_BACKUP_SIZE: usize = 0x100;
+
+// Sessions created while processing a single request (accepted ones, out objects, clones...)
+pub const MAX_NEW_SESSION_COUNT: usize = 4;
+
+// Distinct commands whose requests are counted in session stats (see SessionStats)
+pub const MAX_STATS_REQUEST_ID_COUNT: usize = 0x40;
+
+// Requests which can be deferred at the same time by default (see ServerManager)
+pub const DEFAULT_MAX_DEFERRED_REQUEST_COUNT: usize = 4;
+
+pub type NewSessions = util::FixedVec<ServerHolder, MAX_NEW_SESSION_COUNT>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ServerErrorKind {
+    // A command failed: the error was already sent to the client as the command's result
+    CommandFailed,
+    // A command handler panicked: ResultPanicked was already sent to the client as the command's result
+    // Note: this is only ever reported when panics can be caught (see CAN_CATCH_COMMAND_PANICS), otherwise a panicking command handler takes the whole process down
+    CommandPanicked,
+    // The client sent something we can't process (malformed messages, bad descriptor counts, unknown domain objects...) or receiving/replying to its request failed
+    ProtocolViolation,
+    // Anything on our side (accepting sessions, talking to sm...)
+    Internal
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ErrorPolicy {
+    // Keep the session, replying with the error if the request wasn't replied to yet
+    Reply,
+    // Close the session which caused the error, every other session is left untouched
+    CloseSession,
+    // Stop processing, making loop_process return the error
+    Abort
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ServerError {
+    pub kind: ServerErrorKind,
+    pub rc: ResultCode,
+    pub handle: svc::Handle,
+    // Whether the client can still be replied to: not the case when receiving its request or replying to it is what failed
+    pub can_reply: bool
+}
+
+impl ServerError {
+    pub const fn new(kind: ServerErrorKind, rc: ResultCode, handle: svc::Handle) -> Self {
+        Self { kind, rc, handle, can_reply: true }
+    }
+}
+
+type ServerResult<T> = core::result::Result<T, ServerError>;
+
+#[inline(always)]
+fn protocol_violation(handle: svc::Handle) -> impl Fn(ResultCode) -> ServerError {
+    move |rc| ServerError::new(ServerErrorKind::ProtocolViolation, rc, handle)
+}
+
+#[inline(always)]
+fn transfer_failed(handle: svc::Handle) -> impl Fn(ResultCode) -> ServerError {
+    move |rc| ServerError { can_reply: false, ..ServerError::new(ServerErrorKind::ProtocolViolation, rc, handle) }
+}
+
+#[inline(always)]
+fn internal_error(handle: svc::Handle) -> impl Fn(ResultCode) -> ServerError {
+    move |rc| ServerError::new(ServerErrorKind::Internal, rc, handle)
+}
+
+// Called with every error (and the policy about to be applied), mostly meant for logging
+pub type ServerErrorCallback = fn(&ServerError, ErrorPolicy);
+
+// Malformed requests are never a reason to stop processing (unless a protocol violation policy says so)
+// Panics in command handlers only get the same treatment (as CommandPanicked) where they can be caught, see CAN_CATCH_COMMAND_PANICS
+
+#[derive(Copy, Clone)]
+pub struct ServerErrorPolicies {
+    pub command_failed: ErrorPolicy,
+    // The object which panicked might be left in an inconsistent state, thus its session gets closed by default
+    pub command_panicked: ErrorPolicy,
+    pub protocol_violation: ErrorPolicy,
+    pub internal: ErrorPolicy,
+    pub callback: Option<ServerErrorCallback>
+}
+
+impl ServerErrorPolicies {
+    pub const fn new() -> Self {
+        Self { command_failed: ErrorPolicy::Reply, command_panicked: ErrorPolicy::CloseSession, protocol_violation: ErrorPolicy::CloseSession, internal: ErrorPolicy::Abort, callback: None }
+    }
+
+    pub const fn get_policy(&self, kind: ServerErrorKind) -> ErrorPolicy {
+        match kind {
+            ServerErrorKind::CommandFailed => self.command_failed,
+            ServerErrorKind::CommandPanicked => self.command_panicked,
+            ServerErrorKind::ProtocolViolation => self.protocol_violation,
+            ServerErrorKind::Internal => self.internal
+        }
+    }
+
+    // Returns whether the holder's session has to be closed, or the error itself if processing must stop
+    fn handle_error(&self, server_holder: &ServerHolder, error: ServerError) -> Result<bool> {
+        // Replying again to a client we just failed to transfer a message with would fail the same way, thus its session gets closed instead
+        let policy = match self.get_policy(error.kind) {
+            ErrorPolicy::Reply if !error.can_reply => ErrorPolicy::CloseSession,
+            policy => policy
+        };
+        if let Some(callback) = self.callback {
+            (callback)(&error, policy);
+        }
+
+        // Servers (ports) have no session of their own to reply to or close
+        let is_session = server_holder.handle_type == WaitHandleType::Session;
+        match policy {
+            ErrorPolicy::Reply => match is_session && (error.kind == ServerErrorKind::ProtocolViolation) {
+                // If we can't even reply, there's nothing else to do with the session
+                true => Ok(reply_with_error(server_holder, error.rc).is_err()),
+                false => Ok(false)
+            },
+            ErrorPolicy::CloseSession => Ok(is_session),
+            ErrorPolicy::Abort => Err(error.rc)
+        }
+    }
+}
+
+// Catching panics needs unwinding, which is only available with std (host builds like tests or fuzzing, see the std feature)
+// Otherwise (thus always on the console) panics end in the panic handler like in any other no_std code, taking the whole process down
+pub const CAN_CATCH_COMMAND_PANICS: bool = cfg!(all(any(test, feature = "std"), panic = "unwind"));
+
+#[cfg(all(any(test, feature = "std"), panic = "unwind"))]
+fn call_command_catching_panics<F: FnOnce() -> Result<()>>(f: F) -> Option<Result<()>> {
+    std::panic::catch_unwind(core::panic::AssertUnwindSafe(f)).ok()
+}
+
+#[cfg(not(all(any(test, feature = "std"), panic = "unwind")))]
+#[inline(always)]
+fn call_command_catching_panics<F: FnOnce() -> Result<()>>(f: F) -> Option<Result<()>> {
+    Some(f())
+}
+
+fn reply_with_error(server_holder: &ServerHolder, rc: ResultCode) -> Result<()> {
+    // The request couldn't be parsed, so the response is as plain as it gets
+    let mut ctx = CommandContext::new_server(server_holder.info, core::ptr::null_mut(), 0);
+    write_request_command_response_on_msg_buffer(&mut ctx, rc, cmif::CommandType::Request);
+    match transport::reply_and_receive(&[], server_holder.info.handle, 0) {
+        Err(rc) if !results::os::ResultTimeout::matches(rc) => Err(rc),
+        _ => Ok(())
+    }
+}
+
 pub struct ServerContext<'a> {
     pub ctx: &'a mut CommandContext,
     pub raw_data_walker: DataWalker,
-    pub domain_table: mem::Shared<DomainTable>,
-    pub new_sessions: &'a mut Vec<ServerHolder>
+    pub domain_table: mem::Shared<dyn IDomainTable>,
+    pub new_sessions: &'a mut NewSessions
 }
 
 impl<'a> ServerContext<'a> {
-    pub const fn new(ctx: &'a mut CommandContext, raw_data_walker: DataWalker, domain_table: mem::Shared<DomainTable>, new_sessions: &'a mut Vec<ServerHolder>) -> Self {
+    pub const fn new(ctx: &'a mut CommandContext, raw_data_walker: DataWalker, domain_table: mem::Shared<dyn IDomainTable>, new_sessions: &'a mut NewSessions) -> Self {
         Self { ctx, raw_data_walker, domain_table, new_sessions }
     }
 }
@@ -37,7 +191,7 @@ pub trait CommandParameter<O> {
 
 impl<T: Copy> CommandParameter<T> for T {
     default fn after_request_read(ctx: &mut ServerContext) -> Result<Self> {
-        Ok(ctx.raw_data_walker.advance_get())
+        ctx.raw_data_walker.try_advance_get()
     }
 
     default fn before_response_write(_raw: &Self, ctx: &mut ServerContext) -> Result<()> {
@@ -51,17 +205,26 @@ impl<T: Copy> CommandParameter<T> for T {
     }
 }
 
-impl<const A: BufferAttribute, const S: usize> CommandParameter<sf::Buffer<A, S>> for sf::Buffer<A, S> {
+impl<'a, const A: BufferAttribute, const S: usize> CommandParameter<sf::InBuffer<'a, A, S>> for sf::InBuffer<'a, A, S> {
     fn after_request_read(ctx: &mut ServerContext) -> Result<Self> {
-        let buf = ctx.ctx.pop_buffer(&mut ctx.raw_data_walker)?;
+        let (buf, buf_size) = ctx.ctx.pop_buffer::<A, S>(&mut ctx.raw_data_walker)?;
+        Ok(unsafe { sf::InBuffer::from_const(buf, buf_size) })
+    }
 
-        if A.contains(BufferAttribute::Out()) && A.contains(BufferAttribute::Pointer()) {
-            // For Out(Fixed)Pointer buffers, we need to send them back as InPointer
-            // Note: since buffers can't be out params in this command param system, we need to send them back this way
-            ctx.ctx.add_buffer(sf::InPointerBuffer::from_other(&buf))?;
-        }
+    fn before_response_write(_buffer: &Self, _ctx: &mut ServerContext) -> Result<()> {
+        Err(results::hipc::ResultUnsupportedOperation::make())
+    }
 
-        Ok(buf)
+    fn after_response_write(_buffer: &Self, _ctx: &mut ServerContext) -> Result<()> {
+        Err(results::hipc::ResultUnsupportedOperation::make())
+    }
+}
+
+impl<'a, const A: BufferAttribute, const S: usize> CommandParameter<sf::OutBuffer<'a, A, S>> for sf::OutBuffer<'a, A, S> {
+    fn after_request_read(ctx: &mut ServerContext) -> Result<Self> {
+        // Note: out pointer buffers get sent back by the context itself
+        let (buf, buf_size) = ctx.ctx.pop_buffer::<A, S>(&mut ctx.raw_data_walker)?;
+        Ok(unsafe { sf::OutBuffer::from_mut(buf, buf_size) })
     }
 
     fn before_response_write(_buffer: &Self, _ctx: &mut ServerContext) -> Result<()> {
@@ -74,13 +237,12 @@ impl<const A: BufferAttribute, const S: usize> CommandParameter<sf::Buffer<A, S>
 }
 
 impl<const M: HandleMode> CommandParameter<sf::Handle<M>> for sf::Handle<M> {
-    fn after_request_read(_ctx: &mut ServerContext) -> Result<Self> {
-        // TODO: pop copy/move
-        Err(results::hipc::ResultUnsupportedOperation::make())
+    fn after_request_read(ctx: &mut ServerContext) -> Result<Self> {
+        ctx.ctx.in_params.pop_handle()
     }
 
     fn before_response_write(handle: &Self, ctx: &mut ServerContext) -> Result<()> {
-        ctx.ctx.out_params.push_handle(handle.clone())
+        ctx.ctx.out_params.push_handle(handle)
     }
 
     fn after_response_write(_handle: &Self, _ctx: &mut ServerContext) -> Result<()> {
@@ -92,7 +254,10 @@ impl CommandParameter<sf::ProcessId> for sf::ProcessId {
     fn after_request_read(ctx: &mut ServerContext) -> Result<Self> {
         if ctx.ctx.in_params.send_process_id {
             // TODO: is this really how process ID works? (is the in raw u64 just placeholder data, is it always present...?)
-            let _ = ctx.raw_data_walker.advance_get::<u64>();
+            if ctx.ctx.object_info.uses_cmif_protocol() {
+                // TIPC doesn't send this placeholder space for process IDs
+                ctx.raw_data_walker.try_advance_get::<u64>()?;
+            }
             Ok(sf::ProcessId::from(ctx.ctx.in_params.process_id)) 
         }
         else {
@@ -109,9 +274,56 @@ impl CommandParameter<sf::ProcessId> for sf::ProcessId {
     }
 }
 
+// Objects received outside of domains are plain session handles, which can't be matched against our own sessions
+// They are wrapped in this object, whose session can be taken to create a typed client object (see service::IClientObject)
+
+pub struct InSessionObject {
+    session: sf::Session
+}
+
+impl InSessionObject {
+    pub const fn new(session: sf::Session) -> Self {
+        Self { session }
+    }
+
+    pub fn take_session(&mut self) -> sf::Session {
+        core::mem::replace(&mut self.session, sf::Session::new())
+    }
+}
+
+impl sf::IObject for InSessionObject {
+    fn get_session(&mut self) -> &mut sf::Session {
+        &mut self.session
+    }
+
+    fn get_command_table(&self) -> sf::CommandMetadataTable {
+        vec! []
+    }
+}
+
+// Incoming objects are only known as IObjects, this checks that they are of the expected type before using them as such
+// Domain objects are our own server objects, while sessions received outside of domains are always InSessionObjects
+
+pub fn get_incoming_object<T: sf::IObject + 'static>(object: &mem::Shared<dyn sf::IObject>) -> Result<mem::Shared<T>> {
+    result_return_unless!(object.is_valid(), results::lib::ipc::ResultInvalidObjectType);
+    result_return_unless!(object.get().get_type_id() == core::any::TypeId::of::<T>(), results::lib::ipc::ResultInvalidObjectType);
+    Ok(object.to::<T>())
+}
+
 impl CommandParameter<mem::Shared<dyn sf::IObject>> for mem::Shared<dyn sf::IObject> {
-    fn after_request_read(_ctx: &mut ServerContext) -> Result<Self> {
-        Err(results::hipc::ResultUnsupportedOperation::make())
+    fn after_request_read(ctx: &mut ServerContext) -> Result<Self> {
+        if ctx.ctx.object_info.is_domain() {
+            // The object must be one of the objects of the domain this request was sent to
+            let domain_object_id = ctx.ctx.in_params.pop_domain_object()?;
+            result_return_unless!(ctx.domain_table.is_valid(), results::lib::ipc::ResultDomainNotFound);
+            ctx.domain_table.get().find_domain(domain_object_id)
+        }
+        else {
+            let handle: sf::MoveHandle = ctx.ctx.in_params.pop_handle()?;
+            let mut session = sf::Session::from_handle(handle.into_raw());
+            session.object_info.protocol = ctx.ctx.object_info.protocol;
+            Ok(mem::Shared::new(InSessionObject::new(session)))
+        }
     }
 
     fn before_response_write(session: &Self, ctx: &mut ServerContext) -> Result<()> {
@@ -119,15 +331,17 @@ impl CommandParameter<mem::Shared<dyn sf::IObject>> for mem::Shared<dyn sf::IObj
             let domain_object_id = ctx.domain_table.get().allocate_id()?;
             ctx.ctx.out_params.push_domain_object(domain_object_id)?;
             session.get().set_info(ObjectInfo::new());
-            ctx.domain_table.get().domains.push(ServerHolder::new_domain_session(0, domain_object_id, session.clone()));
-            Ok(())
+            ctx.domain_table.get().add_domain_object(domain_object_id, session.clone())
         }
         else {
-            let (server_handle, client_handle) = svc::create_session(false, 0)?;
-            ctx.ctx.out_params.push_handle(sf::MoveHandle::from(client_handle))?;
+            result_return_if!(ctx.new_sessions.is_full(), results::lib::ipc::ResultNewSessionsFull);
+
+            let (server_handle, client_handle) = transport::create_session()?;
+            ctx.ctx.out_params.push_handle(&unsafe { sf::MoveHandle::from_raw(client_handle, true) })?;
             session.get().set_info(ObjectInfo::new());
-            ctx.new_sessions.push(ServerHolder::new_session(server_handle, session.clone()));
-            Ok(())
+            let mut new_session = ServerHolder::new_session(server_handle, session.clone());
+            new_session.info.protocol = ctx.ctx.object_info.protocol;
+            ctx.new_sessions.push(new_session)
         }
     }
 
@@ -136,24 +350,208 @@ impl CommandParameter<mem::Shared<dyn sf::IObject>> for mem::Shared<dyn sf::IObj
     }
 }
 
+#[inline(always)]
+fn write_request_command_response_on_msg_buffer(ctx: &mut CommandContext, result: ResultCode, command_type: cmif::CommandType) {
+    match ctx.object_info.protocol {
+        CommandProtocol::Cmif => cmif::server::write_request_command_response_on_msg_buffer(ctx, result, command_type),
+        CommandProtocol::Tipc => tipc::server::write_request_command_response_on_msg_buffer(ctx, result, tipc::CommandType::Request as u32)
+    };
+}
+
+#[inline(always)]
+fn write_close_command_response_on_msg_buffer(ctx: &mut CommandContext) {
+    match ctx.object_info.protocol {
+        CommandProtocol::Cmif => cmif::server::write_close_command_response_on_msg_buffer(ctx),
+        CommandProtocol::Tipc => tipc::server::write_close_command_response_on_msg_buffer(ctx)
+    };
+}
+
+// Atmosphere's kernel replaces tagged process IDs with the ID of the process which sent the original request
+const MITM_FORWARD_PROCESS_ID_TAG: u64 = 0xFFFE_0000_0000_0000;
+
+fn prepare_forward_request_on_msg_buffer(ipc_buf_backup: &[u8], pointer_buffer: *mut u8, pointer_buffer_size: usize) {
+    unsafe {
+        let ipc_buf = get_msg_buffer();
+        core::ptr::copy(ipc_buf_backup.as_ptr(), ipc_buf, ipc_buf_backup.len());
+
+        let command_header = ipc_buf as *mut CommandHeader;
+        let mut buf = command_header.offset(1) as *mut u8;
+        if (*command_header).get_has_special_header() {
+            let special_header = buf as *mut CommandSpecialHeader;
+            buf = special_header.offset(1) as *mut u8;
+            if (*special_header).get_send_process_id() {
+                let process_id = buf as *mut u64;
+                *process_id = MITM_FORWARD_PROCESS_ID_TAG | (*process_id & 0x0000_FFFF_FFFF_FFFF);
+                buf = process_id.offset(1) as *mut u8;
+            }
+            buf = buf.add(core::mem::size_of::<svc::Handle>() * ((*special_header).get_copy_handle_count() + (*special_header).get_move_handle_count()) as usize);
+        }
+
+        // The client's receive statics are only valid in its own address space, so make the original service write any pointer buffers to ours (which we will send back to the client)
+        if (*command_header).get_receive_static_count() > 0 {
+            buf = buf.add(core::mem::size_of::<SendStaticDescriptor>() * (*command_header).get_send_static_count() as usize);
+            buf = buf.add(core::mem::size_of::<BufferDescriptor>() * ((*command_header).get_send_buffer_count() + (*command_header).get_receive_buffer_count() + (*command_header).get_exchange_buffer_count()) as usize);
+            buf = buf.add(core::mem::size_of::<u32>() * (*command_header).get_data_word_count() as usize);
+
+            let header = *command_header;
+            *command_header = CommandHeader::new(header.get_command_type(), header.get_send_static_count(), header.get_send_buffer_count(), header.get_receive_buffer_count(), header.get_exchange_buffer_count(), header.get_data_word_count(), 0xFF, header.get_has_special_header());
+            *(buf as *mut ReceiveStaticDescriptor) = ReceiveStaticDescriptor::new(pointer_buffer, pointer_buffer_size);
+        }
+    }
+}
+
+// Pointer buffers sent with a deferred request point to the location they were received on, which is reused by other requests meanwhile
+fn relocate_send_statics_on_msg_buffer(old_pointer_buffer: *const u8, new_pointer_buffer: *mut u8, pointer_buffer_size: usize) {
+    unsafe {
+        let ipc_buf = get_msg_buffer();
+        let command_header = ipc_buf as *mut CommandHeader;
+        let mut buf = command_header.offset(1) as *mut u8;
+        if (*command_header).get_has_special_header() {
+            let special_header = buf as *mut CommandSpecialHeader;
+            buf = special_header.offset(1) as *mut u8;
+            if (*special_header).get_send_process_id() {
+                buf = buf.add(core::mem::size_of::<u64>());
+            }
+            buf = buf.add(core::mem::size_of::<svc::Handle>() * ((*special_header).get_copy_handle_count() + (*special_header).get_move_handle_count()) as usize);
+        }
+
+        let send_statics = buf as *mut SendStaticDescriptor;
+        for i in 0..(*command_header).get_send_static_count() as usize {
+            let send_static = *send_statics.add(i);
+            let offset = (send_static.get_address() as usize).wrapping_sub(old_pointer_buffer as usize);
+            if offset < pointer_buffer_size {
+                *send_statics.add(i) = SendStaticDescriptor::new(new_pointer_buffer.add(offset), send_static.get_size(), send_static.get_index());
+            }
+        }
+    }
+}
+
+fn read_forward_response_copy_handles_from_msg_buffer(copy_handles: &mut ArrayVec<[svc::Handle; MAX_COUNT]>) {
+    unsafe {
+        let ipc_buf = get_msg_buffer();
+        let command_header = ipc_buf as *mut CommandHeader;
+        if (*command_header).get_has_special_header() {
+            let special_header = command_header.offset(1) as *mut CommandSpecialHeader;
+            let mut buf = special_header.offset(1) as *mut u8;
+            if (*special_header).get_send_process_id() {
+                buf = buf.add(core::mem::size_of::<u64>());
+            }
+            // The copy handle count is 4 bits long, thus they always fit
+            for copy_handle in core::slice::from_raw_parts(buf as *const svc::Handle, (*special_header).get_copy_handle_count() as usize) {
+                copy_handles.push(*copy_handle);
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SessionInfo {
+    pub handle: svc::Handle,
+    pub protocol: CommandProtocol,
+    pub domain_object_id: cmif::DomainObjectId,
+    // Regular sessions don't tell us which process is on the other side, mitm ones do (process and program IDs)
+    pub mitm_process_info: Option<sm::MitmProcessInfo>
+}
+
+// Lifecycle hooks are called for every session of the object (thus separately for clones of a session)
+
 pub trait IServerObject: sf::IObject {
     fn new() -> Self where Self: Sized;
+
+    fn on_session_opened(&mut self, _info: &SessionInfo) {}
+    fn on_session_closed(&mut self, _info: &SessionInfo) {}
+    fn on_converted_to_domain(&mut self, _info: &SessionInfo) {}
 }
 
 pub trait IMitmServerObject: sf::IObject {
-    fn new(info: sm::MitmProcessInfo) -> Self where Self: Sized;
+    fn new(info: sm::MitmProcessInfo, forward_session: mem::Shared<sf::Session>) -> Self where Self: Sized;
+
+    fn on_session_opened(&mut self, _info: &SessionInfo) {}
+    fn on_session_closed(&mut self, _info: &SessionInfo) {}
+    fn on_converted_to_domain(&mut self, _info: &SessionInfo) {}
 }
 
 fn create_server_object_impl<S: IServerObject + 'static>() -> mem::Shared<dyn sf::IObject> {
     mem::Shared::new(S::new())
 }
 
-fn create_mitm_server_object_impl<S: IMitmServerObject + 'static>(info: sm::MitmProcessInfo) -> mem::Shared<dyn sf::IObject> {
-    mem::Shared::new(S::new(info))
+fn create_mitm_server_object_impl<S: IMitmServerObject + 'static>(info: sm::MitmProcessInfo, forward_session: mem::Shared<sf::Session>) -> mem::Shared<dyn sf::IObject> {
+    mem::Shared::new(S::new(info, forward_session))
 }
 
 pub type NewServerFn = fn() -> mem::Shared<dyn sf::IObject>;
-pub type NewMitmServerFn = fn(sm::MitmProcessInfo) -> mem::Shared<dyn sf::IObject>;
+pub type NewMitmServerFn = fn(sm::MitmProcessInfo, mem::Shared<sf::Session>) -> mem::Shared<dyn sf::IObject>;
+
+pub type SessionHookFn = fn(&mem::Shared<dyn sf::IObject>, &SessionInfo);
+
+fn empty_session_hook(_object: &mem::Shared<dyn sf::IObject>, _info: &SessionInfo) {}
+
+// Holders only know their objects as IObjects, thus the hooks of the actual object type are kept along with them
+#[derive(Copy, Clone)]
+pub struct SessionHooks {
+    pub on_opened: SessionHookFn,
+    pub on_closed: SessionHookFn,
+    pub on_converted_to_domain: SessionHookFn
+}
+
+impl SessionHooks {
+    pub const fn empty() -> Self {
+        Self { on_opened: empty_session_hook, on_closed: empty_session_hook, on_converted_to_domain: empty_session_hook }
+    }
+
+    pub fn for_server<S: IServerObject + 'static>() -> Self {
+        Self {
+            on_opened: |object, info| IServerObject::on_session_opened(object.to::<S>().get(), info),
+            on_closed: |object, info| IServerObject::on_session_closed(object.to::<S>().get(), info),
+            on_converted_to_domain: |object, info| IServerObject::on_converted_to_domain(object.to::<S>().get(), info)
+        }
+    }
+
+    pub fn for_mitm_server<S: IMitmServerObject + 'static>() -> Self {
+        Self {
+            on_opened: |object, info| IMitmServerObject::on_session_opened(object.to::<S>().get(), info),
+            on_closed: |object, info| IMitmServerObject::on_session_closed(object.to::<S>().get(), info),
+            on_converted_to_domain: |object, info| IMitmServerObject::on_converted_to_domain(object.to::<S>().get(), info)
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SessionStats<const C: usize = MAX_STATS_REQUEST_ID_COUNT> {
+    // Request counts by command ID, for the first C distinct commands
+    pub request_counts: util::FixedVec<(u32, u64), C>,
+    // Requests of commands which didn't fit in the counts above
+    pub untracked_request_count: u64,
+    pub error_count: u64,
+    // Time spent processing the session's requests, in system ticks (see arm::get_system_tick_frequency)
+    pub processing_ticks: u64
+}
+
+impl<const C: usize> SessionStats<C> {
+    pub fn new() -> Self {
+        Self { request_counts: util::FixedVec::new(), untracked_request_count: 0, error_count: 0, processing_ticks: 0 }
+    }
+
+    fn record_request(&mut self, rq_id: u32) {
+        match self.request_counts.iter_mut().find(|(request_id, _)| *request_id == rq_id) {
+            Some((_, count)) => *count += 1,
+            None => {
+                if self.request_counts.push((rq_id, 1)).is_err() {
+                    self.untracked_request_count += 1;
+                }
+            }
+        };
+    }
+
+    // Note: requests of untracked commands aren't counted here
+    pub fn get_request_count(&self, rq_id: u32) -> u64 {
+        self.request_counts.iter().find(|(request_id, _)| *request_id == rq_id).map_or(0, |(_, count)| *count)
+    }
+
+    pub fn get_total_request_count(&self) -> u64 {
+        self.request_counts.iter().map(|(_, count)| *count).sum::<u64>() + self.untracked_request_count
+    }
+}
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(u8)]
@@ -162,50 +560,115 @@ pub enum WaitHandleType {
     Session
 }
 
-pub struct DomainTable {
-    pub table: Vec<cmif::DomainObjectId>,
-    pub domains: Vec<ServerHolder>,
+pub struct DomainObject {
+    pub id: cmif::DomainObjectId,
+    pub object: mem::Shared<dyn sf::IObject>,
+    pub command_table: mem::Shared<sf::CommandMetadataTable>
 }
 
-impl DomainTable {
+// Domain tables are shared between sessions and command contexts, which don't know about the manager's capacity (see ServerManager), thus they're accessed through this trait
+
+pub trait IDomainTable {
+    fn get_max_count(&self) -> usize;
+    fn allocate_id(&mut self) -> Result<cmif::DomainObjectId>;
+    fn allocate_specific_id(&mut self, specific_domain_object_id: cmif::DomainObjectId) -> Result<cmif::DomainObjectId>;
+    fn add_domain_object(&mut self, id: cmif::DomainObjectId, object: mem::Shared<dyn sf::IObject>) -> Result<()>;
+    fn find_domain_object(&self, id: cmif::DomainObjectId) -> Result<&DomainObject>;
+    fn find_domain(&mut self, id: cmif::DomainObjectId) -> Result<mem::Shared<dyn sf::IObject>>;
+    fn deallocate_domain(&mut self, domain_object_id: cmif::DomainObjectId);
+}
+
+pub struct DomainTable<const D: usize> {
+    pub table: util::FixedVec<cmif::DomainObjectId, D>,
+    pub domains: util::FixedVec<DomainObject, D>
+}
+
+impl<const D: usize> DomainTable<D> {
     pub fn new() -> Self {
-        Self { table: Vec::new(), domains: Vec::new() }
+        Self { table: util::FixedVec::new(), domains: util::FixedVec::new() }
     }
 
-    pub fn allocate_id(&mut self) -> Result<cmif::DomainObjectId> {
+    // Placeholder for sessions which aren't domains
+    pub fn empty_shared() -> mem::Shared<dyn IDomainTable> {
+        mem::Shared::<Self>::empty()
+    }
+}
+
+impl<const D: usize> IDomainTable for DomainTable<D> {
+    fn get_max_count(&self) -> usize {
+        D
+    }
+
+    fn allocate_id(&mut self) -> Result<cmif::DomainObjectId> {
+        result_return_if!(self.table.is_full(), results::lib::ipc::ResultDomainTableFull);
+
+        // There's a free ID among the first (allocated count + 1) ones
         let mut current_id: cmif::DomainObjectId = 1;
         loop {
-            // Note: fix potential infinite loops here?
             if !self.table.contains(&current_id) {
-                self.table.push(current_id);
+                self.table.push(current_id)?;
                 return Ok(current_id);
             }
             current_id += 1;
         }
     }
 
-    pub fn allocate_specific_id(&mut self, specific_domain_object_id: cmif::DomainObjectId) -> Result<cmif::DomainObjectId> {
+    fn allocate_specific_id(&mut self, specific_domain_object_id: cmif::DomainObjectId) -> Result<cmif::DomainObjectId> {
+        result_return_if!(self.table.is_full(), results::lib::ipc::ResultDomainTableFull);
+
         if !self.table.contains(&specific_domain_object_id) {
-            self.table.push(specific_domain_object_id);
+            self.table.push(specific_domain_object_id)?;
             return Ok(specific_domain_object_id);
         }
         
         Err(results::lib::ipc::ResultObjectIdAlreadyAllocated::make())
     }
 
-    pub fn find_domain(&mut self, id: cmif::DomainObjectId) -> Result<mem::Shared<dyn sf::IObject>> {
-        for holder in &self.domains {
-            if holder.info.domain_object_id == id {
-                return Ok(holder.server.clone());
-            }
+    fn add_domain_object(&mut self, id: cmif::DomainObjectId, object: mem::Shared<dyn sf::IObject>) -> Result<()> {
+        result_return_if!(self.domains.is_full(), results::lib::ipc::ResultDomainTableFull);
+
+        let command_table = mem::Shared::new(object.get().get_command_table());
+        self.domains.push(DomainObject { id, object, command_table })
+    }
+
+    fn find_domain_object(&self, id: cmif::DomainObjectId) -> Result<&DomainObject> {
+        match self.domains.iter().find(|domain_object| domain_object.id == id) {
+            Some(domain_object) => Ok(domain_object),
+            None => Err(results::lib::ipc::ResultDomainNotFound::make())
         }
+    }
 
-        Err(results::lib::ipc::ResultDomainNotFound::make())
+    fn find_domain(&mut self, id: cmif::DomainObjectId) -> Result<mem::Shared<dyn sf::IObject>> {
+        self.find_domain_object(id).map(|domain_object| domain_object.object.clone())
     }
     
-    pub fn deallocate_domain(&mut self, domain_object_id: cmif::DomainObjectId) {
+    fn deallocate_domain(&mut self, domain_object_id: cmif::DomainObjectId) {
         self.table.retain(|&id| id != domain_object_id);
-        self.domains.retain(|holder| holder.info.domain_object_id != domain_object_id);
+        self.domains.retain(|domain_object| domain_object.id != domain_object_id);
+    }
+}
+
+// A request whose command got deferred, which will be processed again (with the same message and pointer buffer contents, kept in one of the manager's deferred buffers) once the waiter fires
+// The object which deferred the command owns the waited handle, thus it's kept here so that the handle stays valid for as long as the request
+
+pub struct DeferredRequest {
+    object: mem::Shared<dyn sf::IObject>,
+    waiter_handle: svc::Handle,
+    waiter_type: wait::WaiterType,
+    pub pointer_buffer_base: *const u8
+}
+
+impl DeferredRequest {
+    pub fn new(object: mem::Shared<dyn sf::IObject>, waiter: wait::Waiter<'_>, pointer_buffer_base: *const u8) -> Self {
+        Self { object, waiter_handle: waiter.get_handle().as_raw(), waiter_type: waiter.get_type(), pointer_buffer_base }
+    }
+
+    pub fn get_object(&self) -> &mem::Shared<dyn sf::IObject> {
+        &self.object
+    }
+
+    pub fn get_waiter(&self) -> wait::Waiter<'_> {
+        wait::Waiter::from(unsafe { svc::BorrowedHandle::borrow_raw(self.waiter_handle) }, self.waiter_type)
     }
 }
 
@@ -215,49 +678,161 @@ pub struct ServerHolder {
     pub new_server_fn: Option<NewServerFn>,
     pub new_mitm_server_fn: Option<NewMitmServerFn>,
     pub handle_type: WaitHandleType,
-    pub mitm_forward_info: ObjectInfo,
+    pub mitm_forward_session: mem::Shared<sf::Session>,
     pub is_mitm_service: bool,
     pub service_name: sm::ServiceName,
-    pub domain_table: mem::Shared<DomainTable>
+    pub named_port_name: Option<&'static str>,
+    pub domain_table: mem::Shared<dyn IDomainTable>,
+    pub deferred_request: Option<DeferredRequest>,
+    pub command_table: mem::Shared<sf::CommandMetadataTable>,
+    pub hooks: SessionHooks,
+    pub mitm_process_info: Option<sm::MitmProcessInfo>,
+    // Only present if the manager has session stats enabled (see ServerManager::set_session_stats_enabled)
+    pub stats: Option<SessionStats>
 }
 
 impl ServerHolder {
-    pub fn new_server_session<S: IServerObject + 'static>(handle: svc::Handle) -> Self {
-        Self { server: mem::Shared::new(S::new()), info: ObjectInfo::from_handle(handle), new_server_fn: None, new_mitm_server_fn: None, handle_type: WaitHandleType::Session, mitm_forward_info: ObjectInfo::new(), is_mitm_service: false, service_name: sm::ServiceName::empty(), domain_table: mem::Shared::empty() } 
+    // Every holder starts as a plain session/server, the constructors below only change what's specific to them
+    fn new_base(server: mem::Shared<dyn sf::IObject>, command_table: mem::Shared<sf::CommandMetadataTable>, info: ObjectInfo, handle_type: WaitHandleType) -> Self {
+        Self { server, info, new_server_fn: None, new_mitm_server_fn: None, handle_type, mitm_forward_session: mem::Shared::empty(), is_mitm_service: false, service_name: sm::ServiceName::empty(), named_port_name: None, domain_table: DomainTable::<0>::empty_shared(), deferred_request: None, command_table, hooks: SessionHooks::empty(), mitm_process_info: None, stats: None }
+    }
+
+    pub fn new_server_session<S: IServerObject + 'static>(handle: svc::Handle, protocol: CommandProtocol) -> Self {
+        let server: mem::Shared<dyn sf::IObject> = mem::Shared::new(S::new());
+        let command_table = Self::make_command_table(&server);
+        let mut server_holder = Self::new_base(server, command_table, ObjectInfo::from_handle_with_protocol(handle, protocol), WaitHandleType::Session);
+        server_holder.hooks = SessionHooks::for_server::<S>();
+        Self::notify_opened(server_holder)
     }
 
     pub fn new_session(handle: svc::Handle, object: mem::Shared<dyn sf::IObject>) -> Self {
-        Self { server: object, info: ObjectInfo::from_handle(handle), new_server_fn: None, new_mitm_server_fn: None, handle_type: WaitHandleType::Session, mitm_forward_info: ObjectInfo::new(), is_mitm_service: false, service_name: sm::ServiceName::empty(), domain_table: mem::Shared::empty() } 
+        let command_table = Self::make_command_table(&object);
+        Self::new_base(object, command_table, ObjectInfo::from_handle(handle), WaitHandleType::Session)
     }
 
     pub fn new_domain_session(handle: svc::Handle, domain_object_id: cmif::DomainObjectId, object: mem::Shared<dyn sf::IObject>) -> Self {
-        Self { server: object, info: ObjectInfo::from_domain_object_id(handle, domain_object_id), new_server_fn: None, new_mitm_server_fn: None, handle_type: WaitHandleType::Session, mitm_forward_info: ObjectInfo::new(), is_mitm_service: false, service_name: sm::ServiceName::empty(), domain_table: mem::Shared::empty() } 
+        let command_table = Self::make_command_table(&object);
+        Self::new_base(object, command_table, ObjectInfo::from_domain_object_id(handle, domain_object_id), WaitHandleType::Session)
     }
     
-    pub fn new_server<S: IServerObject + 'static>(handle: svc::Handle, service_name: sm::ServiceName) -> Self {
-        Self { server: mem::Shared::<S>::empty(), info: ObjectInfo::from_handle(handle), new_server_fn: Some(create_server_object_impl::<S>), new_mitm_server_fn: None, handle_type: WaitHandleType::Server, mitm_forward_info: ObjectInfo::new(), is_mitm_service: false, service_name, domain_table: mem::Shared::empty() } 
+    pub fn new_server<S: IServerObject + 'static>(handle: svc::Handle, service_name: sm::ServiceName, protocol: CommandProtocol) -> Self {
+        let mut server_holder = Self::new_base(mem::Shared::<S>::empty(), mem::Shared::empty(), ObjectInfo::from_handle_with_protocol(handle, protocol), WaitHandleType::Server);
+        server_holder.new_server_fn = Some(create_server_object_impl::<S>);
+        server_holder.service_name = service_name;
+        server_holder.hooks = SessionHooks::for_server::<S>();
+        server_holder
     }
 
     pub fn new_mitm_server<S: IMitmServerObject + 'static>(handle: svc::Handle, service_name: sm::ServiceName) -> Self {
-        Self { server: mem::Shared::<S>::empty(), info: ObjectInfo::from_handle(handle), new_server_fn: None, new_mitm_server_fn: Some(create_mitm_server_object_impl::<S>), handle_type: WaitHandleType::Server, mitm_forward_info: ObjectInfo::new(), is_mitm_service: true, service_name, domain_table: mem::Shared::empty() } 
+        let mut server_holder = Self::new_base(mem::Shared::<S>::empty(), mem::Shared::empty(), ObjectInfo::from_handle(handle), WaitHandleType::Server);
+        server_holder.new_mitm_server_fn = Some(create_mitm_server_object_impl::<S>);
+        server_holder.is_mitm_service = true;
+        server_holder.service_name = service_name;
+        server_holder.hooks = SessionHooks::for_mitm_server::<S>();
+        server_holder
+    }
+
+    // Sessions accepted by a server keep its creation functions and hooks
+    fn new_accepted_session(&self, server: mem::Shared<dyn sf::IObject>, info: ObjectInfo) -> Self {
+        let command_table = Self::make_command_table(&server);
+        let mut server_holder = Self::new_base(server, command_table, info, WaitHandleType::Session);
+        server_holder.new_server_fn = self.new_server_fn;
+        server_holder.new_mitm_server_fn = self.new_mitm_server_fn;
+        server_holder.is_mitm_service = self.is_mitm_service;
+        server_holder.hooks = self.hooks;
+        server_holder
     }
 
     pub fn make_new_session(&self, handle: svc::Handle) -> Result<Self> {
         let new_fn = self.get_new_server_fn()?;
-        Ok(Self { server: (new_fn)(), info: ObjectInfo::from_handle(handle), new_server_fn: self.new_server_fn, new_mitm_server_fn: self.new_mitm_server_fn, handle_type: WaitHandleType::Session, mitm_forward_info: ObjectInfo::new(), is_mitm_service: self.is_mitm_service, service_name: sm::ServiceName::empty(), domain_table: mem::Shared::empty() })
+        let server = (new_fn)();
+        Ok(Self::notify_opened(self.new_accepted_session(server, ObjectInfo::from_handle_with_protocol(handle, self.info.protocol))))
     }
 
     pub fn make_new_mitm_session(&self, handle: svc::Handle, forward_handle: svc::Handle, info: sm::MitmProcessInfo) -> Result<Self> {
         let new_mitm_fn = self.get_new_mitm_server_fn()?;
-        Ok(Self { server: (new_mitm_fn)(info), info: ObjectInfo::from_handle(handle), new_server_fn: self.new_server_fn, new_mitm_server_fn: self.new_mitm_server_fn, handle_type: WaitHandleType::Session, mitm_forward_info: ObjectInfo::from_handle(forward_handle), is_mitm_service: self.is_mitm_service, service_name: sm::ServiceName::empty(), domain_table: mem::Shared::empty() })
+        // The forward session is shared with the mitm object, and it gets closed once both are gone
+        let forward_session = mem::Shared::new(sf::Session::from_handle(forward_handle));
+        let server = (new_mitm_fn)(info, forward_session.clone());
+        let mut server_holder = self.new_accepted_session(server, ObjectInfo::from_handle(handle));
+        server_holder.mitm_forward_session = forward_session;
+        server_holder.mitm_process_info = Some(info);
+        Ok(Self::notify_opened(server_holder))
+    }
+
+    pub fn make_copied_domain_session(&self, handle: svc::Handle, forward_handle: svc::Handle, object: mem::Shared<dyn sf::IObject>) -> Self {
+        // The copied object becomes a standalone (non-domain) session
+        let command_table = Self::make_command_table(&object);
+        let mut server_holder = Self::new_base(object, command_table, ObjectInfo::from_handle(handle), WaitHandleType::Session);
+        server_holder.mitm_forward_session = Self::make_forward_session(ObjectInfo::from_handle(forward_handle));
+        server_holder.is_mitm_service = forward_handle != 0;
+        server_holder.mitm_process_info = self.mitm_process_info;
+        server_holder
     }
 
     pub fn clone_self(&self, handle: svc::Handle, forward_handle: svc::Handle) -> Result<Self> {
         let mut object_info = self.info;
         object_info.handle = handle;
-        let mut mitm_fwd_info = self.mitm_forward_info;
+        let mut mitm_fwd_info = self.get_forward_info();
         mitm_fwd_info.handle = forward_handle;
-        Ok(Self { server: self.server.clone(), info: object_info, new_server_fn: self.new_server_fn, new_mitm_server_fn: self.new_mitm_server_fn, handle_type: WaitHandleType::Session, mitm_forward_info: mitm_fwd_info, is_mitm_service: forward_handle != 0, service_name: sm::ServiceName::empty(), domain_table: self.domain_table.clone() })
+
+        let mut server_holder = Self::new_base(self.server.clone(), self.command_table.clone(), object_info, WaitHandleType::Session);
+        server_holder.new_server_fn = self.new_server_fn;
+        server_holder.new_mitm_server_fn = self.new_mitm_server_fn;
+        server_holder.mitm_forward_session = Self::make_forward_session(mitm_fwd_info);
+        server_holder.is_mitm_service = forward_handle != 0;
+        server_holder.domain_table = self.domain_table.clone();
+        server_holder.hooks = self.hooks;
+        server_holder.mitm_process_info = self.mitm_process_info;
+        Ok(Self::notify_opened(server_holder))
+    }
+
+    fn make_command_table(object: &mem::Shared<dyn sf::IObject>) -> mem::Shared<sf::CommandMetadataTable> {
+        // Tables are built once per object, so that handling requests doesn't allocate them again and again
+        match object.is_valid() {
+            true => mem::Shared::new(object.get().get_command_table()),
+            false => mem::Shared::empty()
+        }
+    }
+
+    fn notify_opened(holder: Self) -> Self {
+        (holder.hooks.on_opened)(&holder.server, &holder.get_session_info());
+        holder
+    }
+
+    pub fn get_session_info(&self) -> SessionInfo {
+        SessionInfo { handle: self.info.handle, protocol: self.info.protocol, domain_object_id: self.info.domain_object_id, mitm_process_info: self.mitm_process_info }
+    }
+
+    fn make_forward_session(forward_info: ObjectInfo) -> mem::Shared<sf::Session> {
+        match forward_info.is_valid() {
+            true => mem::Shared::new(sf::Session::from(forward_info)),
+            false => mem::Shared::empty()
+        }
+    }
+
+    pub fn get_forward_info(&self) -> ObjectInfo {
+        match self.mitm_forward_session.is_valid() {
+            true => self.mitm_forward_session.get().object_info,
+            false => ObjectInfo::new()
+        }
+    }
+
+    // The request's buffers must have been copied to a deferred buffer already (see DeferredBuffer::store)
+    pub fn defer_request(&mut self, deferred_request: DeferredRequest) {
+        self.deferred_request = Some(deferred_request);
+    }
+
+    pub fn get_wait_handle(&self) -> svc::Handle {
+        match self.deferred_request.as_ref() {
+            Some(deferred_request) => deferred_request.waiter_handle,
+            None => self.info.handle
+        }
+    }
+
+    // While a request is deferred the session is still waited on too, since the client might close it meanwhile
+    pub fn is_waiting_on(&self, handle: svc::Handle) -> bool {
+        (handle == self.get_wait_handle()) || (self.deferred_request.is_some() && (handle == self.info.handle))
     }
 
     pub fn get_new_server_fn(&self) -> Result<NewServerFn> {
@@ -274,27 +849,34 @@ impl ServerHolder {
         }
     }
 
-    pub fn convert_to_domain(&mut self) -> Result<cmif::DomainObjectId> {
+    pub fn convert_to_domain<const D: usize>(&mut self) -> Result<cmif::DomainObjectId> {
         // Check that we're not already a domain
         result_return_if!(self.info.is_domain(), 0xBADE);
+        // TIPC has no domain support
+        result_return_if!(self.info.uses_tipc_protocol(), results::lib::ResultNotSupported);
 
         // Since we're a base domain object now, create a domain table
-        self.domain_table = mem::Shared::new(DomainTable::new());
+        self.domain_table = mem::Shared::new(DomainTable::<D>::new());
 
         let domain_object_id = match self.is_mitm_service {
             true => {
-                let forward_object_id = self.mitm_forward_info.convert_current_object_to_domain()?;
-                self.mitm_forward_info.domain_object_id = forward_object_id;
+                self.mitm_forward_session.get().convert_to_domain()?;
+                let forward_object_id = self.mitm_forward_session.get().object_info.domain_object_id;
                 self.domain_table.get().allocate_specific_id(forward_object_id)?
             },
             false => self.domain_table.get().allocate_id()?
         };
 
         self.info.domain_object_id = domain_object_id;
+        (self.hooks.on_converted_to_domain)(&self.server, &self.get_session_info());
         Ok(domain_object_id)
     }
 
     pub fn close(&mut self) -> Result<()> {
+        if (self.handle_type == WaitHandleType::Session) && self.server.is_valid() {
+            (self.hooks.on_closed)(&self.server, &self.get_session_info());
+        }
+
         if !self.service_name.is_empty() {
             let sm = service::new_named_port_object::<sm::UserInterface>()?;
             match self.is_mitm_service {
@@ -304,90 +886,110 @@ impl ServerHolder {
             sm.get().detach_client(sf::ProcessId::new())?;
         }
 
-        // Don't close our session like a normal one (like the forward session below) as we allocated the object IDs ourselves, the only thing we do have to close is the handle
+        if let Some(port_name) = self.named_port_name {
+            svc::unmanage_named_port(port_name.as_ptr())?;
+        }
+
+        // Don't close our session like a normal one (like the forward session, which closes itself once dropped) as we allocated the object IDs ourselves, the only thing we do have to close is the handle
         if self.info.owns_handle {
-            svc::close_handle(self.info.handle)?;
+            transport::close_handle(self.info.handle)?;
         }
-        sf::Session::from(self.mitm_forward_info).close();
         Ok(())
     }
 }
 
 impl Drop for ServerHolder {
     fn drop(&mut self) {
-        self.close().unwrap();
+        // Holders are dropped when their session gets closed, which must not take the whole server down if something fails
+        let _ = self.close();
     }
 }
 
-pub struct HipcManager<'a> {
+// D is the maximum object count of the domains created through it (see ServerManager)
+
+pub struct HipcManager<'a, const D: usize> {
     session: sf::Session,
     server_holder: &'a mut ServerHolder,
     pointer_buf_size: usize,
-    pub cloned_object_server_handle: svc::Handle,
-    pub cloned_object_forward_handle: svc::Handle
+    pub new_sessions: &'a mut NewSessions
 }
 
-impl<'a> HipcManager<'a> {
-    pub fn new(server_holder: &'a mut ServerHolder, pointer_buf_size: usize) -> Self {
-        Self { session: sf::Session::new(), server_holder, pointer_buf_size, cloned_object_server_handle: 0, cloned_object_forward_handle: 0 }
+impl<'a, const D: usize> HipcManager<'a, D> {
+    pub fn new(server_holder: &'a mut ServerHolder, pointer_buf_size: usize, new_sessions: &'a mut NewSessions) -> Self {
+        Self { session: sf::Session::new(), server_holder, pointer_buf_size, new_sessions }
     }
 
-    pub fn has_cloned_object(&self) -> bool {
-        self.cloned_object_server_handle != 0
-    }
+    fn clone_current_object_impl(&mut self, tag: Option<u32>) -> Result<sf::MoveHandle> {
+        result_return_if!(self.new_sessions.is_full(), results::lib::ipc::ResultNewSessionsFull);
+
+        let mut forward_handle: svc::Handle = 0;
+        if self.server_holder.is_mitm_service {
+            // The tag is meaningless to us, but the original service might care about it
+            let fwd_handle = match tag {
+                Some(tag) => self.server_holder.get_forward_info().clone_current_object_ex(tag)?,
+                None => self.server_holder.get_forward_info().clone_current_object()?
+            };
+            forward_handle = fwd_handle.into_raw();
+        }
 
-    pub fn clone_object(&self) -> Result<ServerHolder> {
-        self.server_holder.clone_self(self.cloned_object_server_handle, self.cloned_object_forward_handle)
+        let (server_handle, client_handle) = transport::create_session()?;
+
+        // Domain holders get cloned with the same domain table, so both sessions keep referring to the same domain
+        self.new_sessions.push(self.server_holder.clone_self(server_handle, forward_handle)?)?;
+        unsafe {
+            Ok(sf::Handle::from_raw(client_handle, true))
+        }
     }
 }
 
-impl<'a> IHipcManager for HipcManager<'a> {
+impl<'a, const D: usize> IHipcManager for HipcManager<'a, D> {
     fn convert_current_object_to_domain(&mut self) -> Result<cmif::DomainObjectId> {
-        self.server_holder.convert_to_domain()
+        self.server_holder.convert_to_domain::<D>()
     }
 
-    fn copy_from_current_domain(&mut self, _domain_object_id: cmif::DomainObjectId) -> Result<sf::MoveHandle> {
-        // TODO
-        Err(results::lib::ResultNotImplemented::make())
-    }
+    fn copy_from_current_domain(&mut self, domain_object_id: cmif::DomainObjectId) -> Result<sf::MoveHandle> {
+        result_return_unless!(self.server_holder.info.is_domain() && self.server_holder.domain_table.is_valid(), results::lib::ipc::ResultTargetNotDomain);
+        result_return_if!(self.new_sessions.is_full(), results::lib::ipc::ResultNewSessionsFull);
+
+        let object = match domain_object_id == self.server_holder.info.domain_object_id {
+            true => self.server_holder.server.clone(),
+            false => self.server_holder.domain_table.get().find_domain(domain_object_id)?
+        };
 
-    fn clone_current_object(&mut self) -> Result<sf::MoveHandle> {
-        let (server_handle, client_handle) = svc::create_session(false, 0)?;
-        
         let mut forward_handle: svc::Handle = 0;
         if self.server_holder.is_mitm_service {
-            let fwd_handle = self.server_holder.mitm_forward_info.clone_current_object()?;
-            forward_handle = fwd_handle.handle;
+            // Mitm domain object IDs match the ones of the forward domain (see ServerHolder::convert_to_domain)
+            let fwd_handle = self.server_holder.get_forward_info().copy_from_current_domain(domain_object_id)?;
+            forward_handle = fwd_handle.into_raw();
         }
 
-        self.cloned_object_server_handle = server_handle;
-        self.cloned_object_forward_handle = forward_handle;
-        Ok(sf::Handle::from(client_handle))
+        let (server_handle, client_handle) = transport::create_session()?;
+        self.new_sessions.push(self.server_holder.make_copied_domain_session(server_handle, forward_handle, object))?;
+        unsafe {
+            Ok(sf::Handle::from_raw(client_handle, true))
+        }
+    }
+
+    fn clone_current_object(&mut self) -> Result<sf::MoveHandle> {
+        self.clone_current_object_impl(None)
     }
 
     fn query_pointer_buffer_size(&mut self) -> Result<u16> {
         Ok(self.pointer_buf_size as u16)
     }
 
-    fn clone_current_object_ex(&mut self, _tag: u32) -> Result<sf::MoveHandle> {
-        // The tag value is unused anyways :P
-        self.clone_current_object()
+    fn clone_current_object_ex(&mut self, tag: u32) -> Result<sf::MoveHandle> {
+        self.clone_current_object_impl(Some(tag))
     }
 }
 
-impl<'a> sf::IObject for HipcManager<'a> {
+impl<'a, const D: usize> sf::IObject for HipcManager<'a, D> {
     fn get_session(&mut self) -> &mut sf::Session {
         &mut self.session
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(convert_current_object_to_domain: 0),
-            ipc_cmif_interface_make_command_meta!(copy_from_current_domain: 1),
-            ipc_cmif_interface_make_command_meta!(clone_current_object: 2),
-            ipc_cmif_interface_make_command_meta!(query_pointer_buffer_size: 3),
-            ipc_cmif_interface_make_command_meta!(clone_current_object_ex: 4)
-        ]
+        Self::get_interface_command_table()
     }
 }
 
@@ -396,7 +998,7 @@ pub struct MitmQueryServer<S: IMitmService> {
     phantom: core::marker::PhantomData<S>
 }
 
-impl<S: IMitmService> IMitmQueryServer for MitmQueryServer<S> {
+impl<S: IMitmService> IMitmQueryServerServer for MitmQueryServer<S> {
     fn should_mitm(&mut self, info: sm::MitmProcessInfo) -> Result<bool> {
         Ok(S::should_mitm(info))
     }
@@ -408,9 +1010,7 @@ impl<S: IMitmService> sf::IObject for MitmQueryServer<S> {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(should_mitm: 65000)
-        ]
+        Self::get_interface_command_table()
     }
 }
 
@@ -423,6 +1023,10 @@ impl<S: IMitmService> IServerObject for MitmQueryServer<S> {
 pub trait IService: IServerObject {
     fn get_name() -> &'static str;
     fn get_max_sesssions() -> i32;
+
+    fn get_protocol() -> CommandProtocol {
+        CommandProtocol::Cmif
+    }
 }
 
 pub trait IMitmService: IMitmServerObject {
@@ -433,109 +1037,393 @@ pub trait IMitmService: IMitmServerObject {
 pub trait INamedPort: IServerObject {
     fn get_port_name() -> &'static str;
     fn get_max_sesssions() -> i32;
+
+    fn get_protocol() -> CommandProtocol {
+        CommandProtocol::Cmif
+    }
 }
 
-// TODO: use const generics to reduce memory usage, like libstratosphere does?
+// Message and pointer buffer copies of a deferred request
 
-pub struct ServerManager<const P: usize> {
-    server_holders: Vec<ServerHolder>,
-    wait_handles: [svc::Handle; MAX_COUNT],
+struct DeferredBuffer<const P: usize> {
+    msg_buffer: [u8; IPC_BUF_BACKUP_SIZE],
     pointer_buffer: [u8; P]
 }
 
-impl<const P: usize> ServerManager<P> {
+impl<const P: usize> DeferredBuffer<P> {
+    const fn new() -> Self {
+        Self { msg_buffer: [0; IPC_BUF_BACKUP_SIZE], pointer_buffer: [0; P] }
+    }
+
+    fn store(&mut self, ipc_buf_backup: &[u8; IPC_BUF_BACKUP_SIZE], pointer_buffer: *const u8) {
+        self.msg_buffer = *ipc_buf_backup;
+        // Requests deferred again are already using this buffer
+        if pointer_buffer != self.pointer_buffer.as_ptr() {
+            unsafe { core::ptr::copy_nonoverlapping(pointer_buffer, self.pointer_buffer.as_mut_ptr(), P) };
+        }
+    }
+}
+
+// Every limit is set at compile time: P is the pointer buffer size, H the maximum holder count (servers and sessions), D the maximum object count of each domain and R the maximum count of requests deferred at the same time
+// Holders are stored in fixed slots and never move while they're in the manager, and nothing gets allocated while processing requests (other than the objects created by commands themselves)
+
+// State shared by every holder, only accessed with the worker lock held while worker threads are running (see ServerManager::loop_process_multithreaded)
+
+struct ServerManagerState<const H: usize, const R: usize> {
+    // Holders being processed by worker threads
+    busy_holders: [bool; H],
+    // Index of the holder using each deferred buffer (see ServerManager::reserve_deferred_buffer)
+    deferred_buffer_owners: [Option<usize>; R],
+    // Group of each holder, holders which might share objects are in the same group (see ServerManager::get_new_session_group)
+    holder_groups: [usize; H],
+    next_group: usize,
+    error_policies: ServerErrorPolicies,
+    stats_enabled: bool
+}
+
+// Holder slots, deferred buffers and the shared state are cells: while worker threads are running, every thread only has a shared reference to the manager
+// A busy holder's slot (and the deferred buffer reserved for it) is only accessed by the worker processing it, everything else only with the worker lock held
+// Otherwise (single-threaded processing or no processing at all) the manager is only accessed by its owner, thus anything can be accessed
+
+pub struct ServerManager<const P: usize, const H: usize, const D: usize, const R: usize = DEFAULT_MAX_DEFERRED_REQUEST_COUNT> {
+    server_holders: [UnsafeCell<Option<ServerHolder>>; H],
+    pointer_buffer: [u8; P],
+    deferred_buffers: [UnsafeCell<DeferredBuffer<P>>; R],
+    state: UnsafeCell<ServerManagerState<H, R>>
+}
+
+// Holders waiting to be processed by a single worker thread
+
+struct ServerWorkerQueue<const H: usize> {
+    // Holder indexes along with the handle each one got signaled on
+    pending_holders: util::FixedVec<(usize, svc::Handle), H>,
+    request_event: wait::SystemEvent
+}
+
+// Shared between the thread waiting on the holders and the worker threads processing them (see ServerManager::loop_process_multithreaded), only accessed with its lock held
+// Holders are marked as busy while they're being processed, thus every holder is only accessed by one thread at a time
+// Objects shared between sessions (mem::Shared isn't thread-safe) are kept safe by always processing holders of the same group in the same worker
+
+struct ServerWorkerState<const P: usize, const H: usize, const D: usize, const R: usize> {
+    lock: sync::Mutex,
+    manager: *const ServerManager<P, H, D, R>,
+    queues: Vec<ServerWorkerQueue<H>>,
+    processed_event: wait::SystemEvent,
+    error: Option<ResultCode>,
+    should_exit: bool
+}
+
+// Requests to sm made while processing holders (acknowledging mitm sessions) might come from different worker threads, thus they're serialized
+
+static mut G_SM_LOCK: sync::Mutex = sync::Mutex::new(false);
+
+impl<const P: usize, const H: usize, const D: usize, const R: usize> ServerManager<P, H, D, R> {
+    // Every holder, the sessions of deferred requests (also waited on, see ServerHolder::is_waiting_on) and the event used by loop_process_multithreaded must fit in a single wait
+    const HOLDER_COUNT_CHECK: () = assert!(H + R < MAX_COUNT, "ServerManager supports up to 63 holders and deferred requests");
+
     pub fn new() -> Result<Self> {
-        Ok(Self { server_holders: Vec::new(), wait_handles: [0; MAX_COUNT], pointer_buffer: [0; P] })
+        let () = Self::HOLDER_COUNT_CHECK;
+        let state = ServerManagerState { busy_holders: [false; H], deferred_buffer_owners: [None; R], holder_groups: [0; H], next_group: 0, error_policies: ServerErrorPolicies::new(), stats_enabled: false };
+        Ok(Self { server_holders: [(); H].map(|_| UnsafeCell::new(None)), pointer_buffer: [0; P], deferred_buffers: [(); R].map(|_| UnsafeCell::new(DeferredBuffer::new())), state: UnsafeCell::new(state) })
+    }
+
+    // Safety: the slot must be accessible by the calling thread (see ServerManager), and the reference must not outlive that
+    #[allow(clippy::mut_from_ref)]
+    #[inline(always)]
+    unsafe fn holder_slot(&self, index: usize) -> &mut Option<ServerHolder> {
+        &mut *self.server_holders[index].get()
+    }
+
+    // Safety: same as holder_slot, for the deferred buffer at the given slot
+    #[allow(clippy::mut_from_ref)]
+    #[inline(always)]
+    unsafe fn deferred_buffer(&self, slot: usize) -> &mut DeferredBuffer<P> {
+        &mut *self.deferred_buffers[slot].get()
+    }
+
+    // Safety: the worker lock must be held while worker threads are running, and the reference must not outlive that
+    #[allow(clippy::mut_from_ref)]
+    #[inline(always)]
+    unsafe fn state(&self) -> &mut ServerManagerState<H, R> {
+        &mut *self.state.get()
+    }
+
+    pub fn set_error_policies(&mut self, error_policies: ServerErrorPolicies) {
+        self.state.get_mut().error_policies = error_policies;
+    }
+
+    pub fn get_error_policies(&self) -> ServerErrorPolicies {
+        unsafe { self.state().error_policies }
+    }
+
+    pub fn set_error_policy(&mut self, kind: ServerErrorKind, policy: ErrorPolicy) {
+        let error_policies = &mut self.state.get_mut().error_policies;
+        match kind {
+            ServerErrorKind::CommandFailed => error_policies.command_failed = policy,
+            ServerErrorKind::CommandPanicked => error_policies.command_panicked = policy,
+            ServerErrorKind::ProtocolViolation => error_policies.protocol_violation = policy,
+            ServerErrorKind::Internal => error_policies.internal = policy
+        };
+    }
+
+    pub fn set_error_callback(&mut self, callback: ServerErrorCallback) {
+        self.state.get_mut().error_policies.callback = Some(callback);
+    }
+
+    pub fn reset_error_callback(&mut self) {
+        self.state.get_mut().error_policies.callback = None;
+    }
+
+    // Stats are kept from the moment they get enabled, disabling them drops the existing ones
+    pub fn set_session_stats_enabled(&mut self, enabled: bool) {
+        self.state.get_mut().stats_enabled = enabled;
+        for server_holder in self.server_holders.iter_mut().filter_map(|server_holder| server_holder.get_mut().as_mut()).filter(|server_holder| server_holder.handle_type == WaitHandleType::Session) {
+            server_holder.stats = match enabled {
+                true => server_holder.stats.take().or(Some(SessionStats::new())),
+                false => None
+            };
+        }
+    }
+
+    pub fn is_session_stats_enabled(&self) -> bool {
+        unsafe { self.state().stats_enabled }
+    }
+
+    // Every holder is listed by these: they can't be called while worker threads are running (loop_process_multithreaded borrows the manager mutably), thus no holder is busy meanwhile
+
+    fn all_server_holders(&self) -> impl Iterator<Item = &ServerHolder> {
+        self.server_holders.iter().filter_map(|server_holder| unsafe { (*server_holder.get()).as_ref() })
+    }
+
+    pub fn get_sessions(&self) -> Vec<SessionInfo> {
+        self.all_server_holders().filter(|server_holder| server_holder.handle_type == WaitHandleType::Session).map(ServerHolder::get_session_info).collect()
+    }
+
+    pub fn get_session_stats(&self, handle: svc::Handle) -> Option<SessionStats> {
+        self.all_server_holders().find(|server_holder| (server_holder.handle_type == WaitHandleType::Session) && (server_holder.info.handle == handle)).and_then(|server_holder| server_holder.stats.clone())
+    }
+
+    pub fn get_all_session_stats(&self) -> Vec<(SessionInfo, SessionStats)> {
+        self.all_server_holders().filter_map(|server_holder| server_holder.stats.as_ref().map(|stats| (server_holder.get_session_info(), stats.clone()))).collect()
+    }
+
+    pub fn get_server_holder_count(&self) -> usize {
+        self.server_holders.iter().filter(|server_holder| unsafe { (*server_holder.get()).is_some() }).count()
+    }
+
+    fn allocate_group(&self) -> usize {
+        let state = unsafe { self.state() };
+        let group = state.next_group;
+        state.next_group = state.next_group.wrapping_add(1);
+        group
+    }
+
+    // Sessions accepted by servers get a group of their own, while the ones created by sessions (clones, out objects...) might share objects with them, thus they're kept in the same group
+    fn get_new_session_group(&self, index: usize) -> usize {
+        match unsafe { self.holder_slot(index) }.as_ref().map(|server_holder| server_holder.handle_type) {
+            Some(WaitHandleType::Session) => unsafe { self.state().holder_groups[index] },
+            _ => self.allocate_group()
+        }
+    }
+
+    fn add_server_holder(&mut self, server_holder: ServerHolder) -> Result<()> {
+        let group = self.allocate_group();
+        self.add_server_holder_in_group(server_holder, group)
+    }
+
+    // Holders which don't fit are dropped, thus their sessions get closed
+    // Free slots aren't used by anyone, thus they're filled like the shared state (with the worker lock held while worker threads are running)
+    fn add_server_holder_in_group(&self, mut server_holder: ServerHolder, group: usize) -> Result<()> {
+        let index = match self.server_holders.iter().position(|server_holder| unsafe { (*server_holder.get()).is_none() }) {
+            Some(index) => index,
+            None => return Err(results::lib::ipc::ResultServerHoldersFull::make())
+        };
+
+        let state = unsafe { self.state() };
+        if state.stats_enabled && (server_holder.handle_type == WaitHandleType::Session) && server_holder.stats.is_none() {
+            server_holder.stats = Some(SessionStats::new());
+        }
+        unsafe { *self.holder_slot(index) = Some(server_holder) };
+        state.busy_holders[index] = false;
+        state.holder_groups[index] = group;
+        Ok(())
+    }
+
+    // Adds the sessions created while processing the holder at the given index
+    fn add_server_holders(&self, server_holders: &mut NewSessions, index: usize) -> Result<()> {
+        while let Some(server_holder) = server_holders.remove(0) {
+            let group = self.get_new_session_group(index);
+            self.add_server_holder_in_group(server_holder, group)?;
+        }
+        Ok(())
+    }
+
+    // Closes every server and session being handled, like dropping the manager would
+    pub fn close_all(&mut self) {
+        for server_holder in self.server_holders.iter_mut() {
+            *server_holder.get_mut() = None;
+        }
+        self.state.get_mut().deferred_buffer_owners = [None; R];
+    }
+
+    // Holders keep their deferred buffer as long as they have a deferred request, other ones get a free buffer (if any) while they're being processed
+    fn reserve_deferred_buffer(&self, index: usize) -> Option<usize> {
+        let state = unsafe { self.state() };
+        let slot = match state.deferred_buffer_owners.iter().position(|owner| *owner == Some(index)) {
+            Some(slot) => Some(slot),
+            None => state.deferred_buffer_owners.iter().position(Option::is_none)
+        };
+
+        if let Some(slot) = slot {
+            state.deferred_buffer_owners[slot] = Some(index);
+        }
+        slot
+    }
+
+    fn release_deferred_buffer(&self, index: usize) {
+        let has_deferred_request = unsafe { self.holder_slot(index) }.as_ref().map_or(false, |server_holder| server_holder.deferred_request.is_some());
+        if !has_deferred_request {
+            for owner in unsafe { self.state() }.deferred_buffer_owners.iter_mut().filter(|owner| **owner == Some(index)) {
+                *owner = None;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn is_waiting_on(&self, index: usize, handle: svc::Handle) -> bool {
+        !unsafe { self.state() }.busy_holders[index] && unsafe { self.holder_slot(index) }.as_ref().map_or(false, |server_holder| server_holder.is_waiting_on(handle))
     }
     
+    // Returns the count of handles to wait on (every idle holder's ones, after the extra ones)
     #[inline(always)]
-    fn prepare_wait_handles(&mut self) -> &[svc::Handle] {
+    fn prepare_wait_handles(&self, wait_handles: &mut [svc::Handle; MAX_COUNT], extra_handles: &[svc::Handle]) -> usize {
         let mut handles_index: usize = 0;
-        for server_holder in &mut self.server_holders {
-            let server_info = server_holder.info;
-            if server_info.handle != 0 {
-                self.wait_handles[handles_index] = server_info.handle;
-                handles_index += 1;
+        for extra_handle in extra_handles {
+            wait_handles[handles_index] = *extra_handle;
+            handles_index += 1;
+        }
+
+        // There's room for every holder (see HOLDER_COUNT_CHECK)
+        for index in 0..H {
+            if let (Some(server_holder), false) = (unsafe { self.holder_slot(index) }.as_ref(), unsafe { self.state() }.busy_holders[index]) {
+                let wait_handle = server_holder.get_wait_handle();
+                if wait_handle != 0 {
+                    wait_handles[handles_index] = wait_handle;
+                    handles_index += 1;
+                }
+                // Only holders with a deferred request (at most one per deferred buffer) wait on their session too
+                if server_holder.deferred_request.is_some() {
+                    wait_handles[handles_index] = server_holder.info.handle;
+                    handles_index += 1;
+                }
             }
         }
 
-        unsafe { core::slice::from_raw_parts(self.wait_handles.as_ptr(), handles_index) }
+        handles_index
     }
 
     #[inline(always)]
-    fn handle_request_command(&mut self, ctx: &mut CommandContext, rq_id: u32, command_type: cmif::CommandType, domain_command_type: cmif::DomainCommandType, ipc_buf_backup: &[u8], domain_table: mem::Shared<DomainTable>) -> Result<()> {
+    fn handle_request_command(server_holder: &ServerHolder, pointer_buffer: *mut u8, ctx: &mut CommandContext, rq_id: u32, command_type: cmif::CommandType, domain_command_type: cmif::DomainCommandType, ipc_buf_backup: &[u8], domain_table: mem::Shared<dyn IDomainTable>, forwarded_copy_handles: &mut ArrayVec<[svc::Handle; MAX_COUNT]>, new_sessions: &mut NewSessions, command_error: &mut Option<(ServerErrorKind, ResultCode)>) -> Result<Option<DeferredRequest>> {
         let is_domain = ctx.object_info.is_domain();
-        let domain_table_clone = domain_table.clone();
-        let mut do_handle_request = || -> Result<()> {
-            let mut new_sessions: Vec<ServerHolder> = Vec::new();
-            for server_holder in &mut self.server_holders {
-                let server_info = server_holder.info;
-                if server_info.handle == ctx.object_info.handle {
-                    let send_to_forward_handle = || -> Result<()> {
-                        let ipc_buf = get_msg_buffer();
-                        unsafe {
-                            core::ptr::copy(ipc_buf_backup.as_ptr(), ipc_buf, ipc_buf_backup.len());
-                        }
-                        // Let the original service take care of the command for us.
-                        svc::send_sync_request(server_holder.mitm_forward_info.handle)
-                    };
-                    
-                    let target_server = match is_domain {
-                        true => match ctx.object_info.owns_handle {
-                            true => server_holder.server.clone(),
-                            false => domain_table_clone.get().find_domain(ctx.object_info.domain_object_id)?
+        let mut send_to_forward_session = || -> Result<()> {
+            // Let the original service take care of the command for us.
+            prepare_forward_request_on_msg_buffer(ipc_buf_backup, pointer_buffer, P);
+            transport::send_sync_request(server_holder.get_forward_info().handle)?;
+            read_forward_response_copy_handles_from_msg_buffer(forwarded_copy_handles);
+            Ok(())
+        };
+        // Every error sent back as a command result is reported once the reply is done
+        let mut write_error_response = |ctx: &mut CommandContext, rc: ResultCode| {
+            write_request_command_response_on_msg_buffer(ctx, rc, command_type);
+            *command_error = Some((ServerErrorKind::CommandFailed, rc));
+        };
+        let mut command_panicked = false;
+
+        let mut do_handle_request = || -> Result<Option<DeferredRequest>> {
+            let (target_server, command_table) = match is_domain {
+                true => match ctx.object_info.owns_handle {
+                    true => (server_holder.server.clone(), server_holder.command_table.clone()),
+                    false => match domain_table.get().find_domain_object(ctx.object_info.domain_object_id) {
+                        Ok(domain_object) => (domain_object.object.clone(), domain_object.command_table.clone()),
+                        // Objects we don't know about in mitm domains were created by the original service itself
+                        Err(_) if server_holder.is_mitm_service => {
+                            if let Err(rc) = send_to_forward_session() {
+                                write_error_response(ctx, rc);
+                            }
+                            return Ok(None);
                         },
-                        false => server_holder.server.clone()
+                        Err(rc) => return Err(rc)
+                    }
+                },
+                false => (server_holder.server.clone(), server_holder.command_table.clone())
+            };
+            // Nothing done on success here, as if the command succeeds it will automatically respond by itself.
+            let mut command_found = false;
+            let mut deferred_request: Option<DeferredRequest> = None;
+            for command in command_table.get().iter() {
+                if command.matches(ctx.object_info.protocol, rq_id) {
+                    command_found = true;
+                    let mut server_ctx = ServerContext::new(ctx, DataWalker::empty(), domain_table.clone(), new_sessions);
+                    let command_rc = match call_command_catching_panics(|| target_server.get().call_self_command(command.command_fn, &mut server_ctx)) {
+                        Some(rc) => rc,
+                        None => {
+                            command_panicked = true;
+                            Err(results::lib::ResultPanicked::make())
+                        }
                     };
-                    // Nothing done on success here, as if the command succeeds it will automatically respond by itself.
-                    let mut command_found = false;
-                    for command in target_server.get().get_command_table() {
-                        if command.matches(ctx.object_info.protocol, rq_id) {
-                            command_found = true;
-                            let mut server_ctx = ServerContext::new(ctx, DataWalker::empty(), domain_table_clone.clone(), &mut new_sessions);
-                            if let Err(rc) = target_server.get().call_self_command(command.command_fn, &mut server_ctx) {
-                                if server_holder.is_mitm_service && results::sm::mitm::ResultShouldForwardToSession::matches(rc) {
-                                    if let Err(rc) = send_to_forward_handle() {
-                                        cmif::server::write_request_command_response_on_msg_buffer(ctx, rc, command_type);
-                                    }
-                                }
-                                else {
-                                    cmif::server::write_request_command_response_on_msg_buffer(ctx, rc, command_type);
-                                }
-                            }
+                    if let Err(rc) = command_rc {
+                        if results::sm::mitm::ResultShouldForwardToSession::matches(rc) {
+                            // Commands which aren't implemented by the object (see the interface macros) end up here too
+                            command_found = false;
                         }
-                    }
-                    if !command_found {
-                        if server_holder.is_mitm_service {
-                            if let Err(rc) = send_to_forward_handle() {
-                                cmif::server::write_request_command_response_on_msg_buffer(ctx, rc, command_type);
-                            }
+                        else if results::cmif::ResultRequestDeferredByUser::matches(rc) {
+                            // Don't respond yet, the request will be processed again once the waiter fires
+                            match target_server.get().get_deferral_waiter() {
+                                Some(waiter) => deferred_request = Some(DeferredRequest::new(target_server.clone(), waiter, pointer_buffer)),
+                                None => write_error_response(ctx, rc)
+                            };
                         }
                         else {
-                            cmif::server::write_request_command_response_on_msg_buffer(ctx, results::cmif::ResultInvalidCommandRequestId::make(), command_type);
+                            write_error_response(ctx, rc);
                         }
                     }
-                    break;
+                }
+            }
+            if !command_found {
+                if server_holder.is_mitm_service {
+                    if let Err(rc) = send_to_forward_session() {
+                        write_error_response(ctx, rc);
+                    }
+                }
+                else {
+                    write_error_response(ctx, results::cmif::ResultInvalidCommandRequestId::make());
                 }
             }
 
-            self.server_holders.append(&mut new_sessions);
-
-            Ok(())
+            Ok(deferred_request)
         };
 
+        let mut deferred_request: Option<DeferredRequest> = None;
         match domain_command_type {
             cmif::DomainCommandType::Invalid => {
                 // Invalid command type might mean that the session isn't a domain :P
                 match is_domain {
-                    false => do_handle_request()?,
+                    false => deferred_request = do_handle_request()?,
                     true => return Err(results::lib::ipc::ResultInvalidDomainCommandType::make())
                 };
             },
-            cmif::DomainCommandType::SendMessage => do_handle_request()?,
+            cmif::DomainCommandType::SendMessage => deferred_request = do_handle_request()?,
             cmif::DomainCommandType::Close => {
                 if !ctx.object_info.owns_handle {
-                    domain_table.get().deallocate_domain(ctx.object_info.domain_object_id);
+                    match server_holder.is_mitm_service && domain_table.get().find_domain(ctx.object_info.domain_object_id).is_err() {
+                        // Same as above, the original service has to close the objects it created
+                        true => {
+                            if let Err(rc) = send_to_forward_session() {
+                                write_error_response(ctx, rc);
+                            }
+                        },
+                        false => domain_table.get().deallocate_domain(ctx.object_info.domain_object_id)
+                    };
                 }
                 else {
                     // TODO: Abort? Error?
@@ -543,145 +1431,110 @@ impl<const P: usize> ServerManager<P> {
             }
         }
 
-        Ok(())
+        if command_panicked {
+            *command_error = Some((ServerErrorKind::CommandPanicked, results::lib::ResultPanicked::make()));
+        }
+        Ok(deferred_request)
     }
 
     #[inline(always)]
-    fn handle_control_command(&mut self, ctx: &mut CommandContext, rq_id: u32, command_type: cmif::CommandType) -> Result<()> {
-        for server_holder in &mut self.server_holders {
-            let server_info = server_holder.info;
-            if server_info.handle == ctx.object_info.handle {
-                let mut hipc_manager = HipcManager::new(server_holder, P);
-                // Nothing done on success here, as if the command succeeds it will automatically respond by itself.
-                let mut command_found = false;
-                for command in hipc_manager.get_command_table() {
-                    if command.matches(CommandProtocol::Cmif, rq_id) {
-                        command_found = true;
-                        let mut unused_new_sessions: Vec<ServerHolder> = Vec::new();
-                        let unused_domain_table = mem::Shared::empty();
-                        let mut server_ctx = ServerContext::new(ctx, DataWalker::empty(), unused_domain_table, &mut unused_new_sessions);
-                        if let Err(rc) = hipc_manager.call_self_command(command.command_fn, &mut server_ctx) {
-                            cmif::server::write_control_command_response_on_msg_buffer(ctx, rc, command_type);
-                        }
-                    }
-                }
-                if !command_found {
-                    cmif::server::write_control_command_response_on_msg_buffer(ctx, results::cmif::ResultInvalidCommandRequestId::make(), command_type);
-                }
-
-                if hipc_manager.has_cloned_object() {
-                    let cloned_holder = hipc_manager.clone_object()?;
-                    self.server_holders.push(cloned_holder);
+    fn handle_control_command(server_holder: &mut ServerHolder, ctx: &mut CommandContext, rq_id: u32, command_type: cmif::CommandType, new_sessions: &mut NewSessions) -> Result<()> {
+        let mut hipc_manager = HipcManager::<D>::new(server_holder, P, new_sessions);
+        // Nothing done on success here, as if the command succeeds it will automatically respond by itself.
+        let mut command_found = false;
+        for command in hipc_manager.get_command_table() {
+            if command.matches(CommandProtocol::Cmif, rq_id) {
+                command_found = true;
+                let mut unused_new_sessions = NewSessions::new();
+                let unused_domain_table = DomainTable::<0>::empty_shared();
+                let mut server_ctx = ServerContext::new(ctx, DataWalker::empty(), unused_domain_table, &mut unused_new_sessions);
+                if let Err(rc) = hipc_manager.call_self_command(command.command_fn, &mut server_ctx) {
+                    cmif::server::write_control_command_response_on_msg_buffer(ctx, rc, command_type);
                 }
-                break;
             }
         }
+        if !command_found {
+            cmif::server::write_control_command_response_on_msg_buffer(ctx, results::cmif::ResultInvalidCommandRequestId::make(), command_type);
+        }
 
         Ok(())
     }
 
-    fn process_signaled_handle(&mut self, handle: svc::Handle) -> Result<()> {
-        let mut server_found = false;
-        let mut index: usize = 0;
+    // Handles the request currently in the message buffer, returning whether the holder's session got closed
+    // The request can only be deferred if there's a deferred buffer to keep it in
+    fn handle_received_request(server_holder: &mut ServerHolder, pointer_buffer: *mut u8, deferred_buffer: Option<&mut DeferredBuffer<P>>, new_sessions: &mut NewSessions) -> ServerResult<bool> {
+        let server_info = server_holder.info;
+        let handle = server_info.handle;
         let mut should_close_session = false;
-        let mut new_sessions: Vec<ServerHolder> = Vec::new();
 
-        let mut ctx = CommandContext::empty();
-        let mut command_type = cmif::CommandType::Invalid;
         let mut domain_cmd_type = cmif::DomainCommandType::Invalid;
         let mut rq_id: u32 = 0;
-        let mut ipc_buf_backup: [u8; 0x100] = [0; 0x100];
-        let mut domain_table: mem::Shared<DomainTable> = mem::Shared::empty();
-
-        for server_holder in &mut self.server_holders {
-            let server_info = server_holder.info;
-            if server_info.handle == handle {
-                server_found = true;
-                match server_holder.handle_type {
-                    WaitHandleType::Session => {
-                        if P > 0 {
-                            // Send our pointer buffer as a C descriptor for kernel - why are Pointer buffers so fucking weird?
-                            let mut tmp_ctx = CommandContext::new_client(server_info);
-                            tmp_ctx.add_receive_static(ReceiveStaticDescriptor::new(self.pointer_buffer.as_ptr(), P))?;
-                            cmif::client::write_command_on_msg_buffer(&mut tmp_ctx, cmif::CommandType::Invalid, 0);
-                        }
+        let mut ipc_buf_backup: [u8; IPC_BUF_BACKUP_SIZE] = [0; IPC_BUF_BACKUP_SIZE];
+        let mut domain_table = DomainTable::<0>::empty_shared();
 
-                        match svc::reply_and_receive(&handle, 1, 0, -1) {
-                            Err(rc) => {
-                                if results::os::ResultSessionClosed::matches(rc) {
-                                    should_close_session = true;
-                                    break;
-                                }
-                                else {
-                                    return Err(rc);
+        unsafe { core::ptr::copy(get_msg_buffer(), ipc_buf_backup.as_mut_ptr(), ipc_buf_backup.len()) };
+
+        let mut ctx = CommandContext::new_server(server_info, pointer_buffer, P);
+        let command_type = match server_info.uses_tipc_protocol() {
+            true => {
+                // TIPC requests/closes are dispatched like their CMIF counterparts (TIPC has no control commands or domains)
+                match tipc::server::read_command_from_msg_buffer(&mut ctx).map_err(protocol_violation(handle))? {
+                    tipc_command_type if tipc_command_type == tipc::CommandType::CloseSession as u32 => {
+                        should_close_session = true;
+                        cmif::CommandType::Close
+                    },
+                    _ => {
+                        rq_id = tipc::server::read_request_command_from_msg_buffer(&mut ctx).map_err(protocol_violation(handle))?;
+                        cmif::CommandType::Request
+                    }
+                }
+            },
+            false => {
+                let command_type = cmif::server::read_command_from_msg_buffer(&mut ctx).map_err(protocol_violation(handle))?;
+                match command_type {
+                    cmif::CommandType::Request | cmif::CommandType::RequestWithContext => {
+                        match cmif::server::read_request_command_from_msg_buffer(&mut ctx) {
+                            Ok((request_id, domain_command_type, domain_object_id)) => {
+                                let mut base_info = server_info;
+                                if server_info.is_domain() {
+                                    // This is a domain request
+                                    base_info.domain_object_id = domain_object_id;
+                                    base_info.owns_handle = server_info.domain_object_id == domain_object_id;
                                 }
+                                ctx.object_info = base_info;
+                                domain_cmd_type = domain_command_type;
+                                rq_id = request_id;
+                                domain_table = server_holder.domain_table.clone();
                             },
-                            _ => {}
+                            Err(rc) => return Err(protocol_violation(handle)(rc))
                         };
-
-                        unsafe { core::ptr::copy(get_msg_buffer(), ipc_buf_backup.as_mut_ptr(), ipc_buf_backup.len()) };
-
-                        ctx = CommandContext::new_server(server_info, self.pointer_buffer.as_mut_ptr());
-                        command_type = cmif::server::read_command_from_msg_buffer(&mut ctx);
-                        match command_type {
-                            cmif::CommandType::Request | cmif::CommandType::RequestWithContext => {
-                                match cmif::server::read_request_command_from_msg_buffer(&mut ctx) {
-                                    Ok((request_id, domain_command_type, domain_object_id)) => {
-                                        let mut base_info = server_info;
-                                        if server_info.is_domain() {
-                                            // This is a domain request
-                                            base_info.domain_object_id = domain_object_id;
-                                            base_info.owns_handle = server_info.domain_object_id == domain_object_id;
-                                        }
-                                        ctx.object_info = base_info;
-                                        domain_cmd_type = domain_command_type;
-                                        rq_id = request_id;
-                                        domain_table = server_holder.domain_table.clone();
-                                    },
-                                    Err(rc) => return Err(rc)
-                                };
-                            },
-                            cmif::CommandType::Control | cmif::CommandType::ControlWithContext => {
-                                match cmif::server::read_control_command_from_msg_buffer(&mut ctx) {
-                                    Ok(control_rq_id) => {
-                                        rq_id = control_rq_id as u32;
-                                    },
-                                    Err(rc) => return Err(rc),
-                                };
-                            },
-                            cmif::CommandType::Close => {
-                                should_close_session = true;
+                    },
+                    cmif::CommandType::Control | cmif::CommandType::ControlWithContext => {
+                        match cmif::server::read_control_command_from_msg_buffer(&mut ctx) {
+                            Ok(control_rq_id) => {
+                                rq_id = control_rq_id;
                             },
-                            _ => return Err(results::lib::ipc::ResultInvalidCommandType::make())
-                        }
+                            Err(rc) => return Err(protocol_violation(handle)(rc)),
+                        };
                     },
-                    WaitHandleType::Server => {
-                        let new_handle = svc::accept_session(handle)?;
-
-                        if server_holder.is_mitm_service {
-                            let sm = service::new_named_port_object::<sm::UserInterface>()?;
-                            let (info, session_handle) = sm.get().atmosphere_acknowledge_mitm_session(server_holder.service_name)?;
-                            new_sessions.push(server_holder.make_new_mitm_session(new_handle, session_handle.handle, info)?);
-                            sm.get().detach_client(sf::ProcessId::new())?;
-                        }
-                        else {
-                            new_sessions.push(server_holder.make_new_session(new_handle)?);
-                        }
-                    }
+                    cmif::CommandType::Close => {
+                        should_close_session = true;
+                    },
+                    _ => return Err(protocol_violation(handle)(results::lib::ipc::ResultInvalidCommandType::make()))
                 };
-                break;
+                command_type
             }
-            index += 1;
-        }
+        };
 
-        let reply_impl = || -> Result<()> {
-            match svc::reply_and_receive(&handle, 0, handle, 0) {
+        // Replying fails when the client's message was not what the kernel expected (like bad receive buffers), thus that's on the client too
+        let reply_impl = || -> ServerResult<()> {
+            match transport::reply_and_receive(&[], handle, 0) {
                 Err(rc) => {
                     if results::os::ResultTimeout::matches(rc) || results::os::ResultSessionClosed::matches(rc) {
                         Ok(())
                     }
                     else {
-                        Err(rc)
+                        Err(transfer_failed(handle)(rc))
                     }
                 },
                 _ => Ok(())
@@ -690,45 +1543,204 @@ impl<const P: usize> ServerManager<P> {
 
         match command_type {
             cmif::CommandType::Request | cmif::CommandType::RequestWithContext => {
-                self.handle_request_command(&mut ctx, rq_id, command_type, domain_cmd_type, &ipc_buf_backup, domain_table)?;
-                reply_impl()?;
+                let mut forwarded_copy_handles: ArrayVec<[svc::Handle; MAX_COUNT]> = ArrayVec::new();
+                let mut command_error: Option<(ServerErrorKind, ResultCode)> = None;
+                match Self::handle_request_command(server_holder, pointer_buffer, &mut ctx, rq_id, command_type, domain_cmd_type, &ipc_buf_backup, domain_table, &mut forwarded_copy_handles, new_sessions, &mut command_error).map_err(protocol_violation(handle))? {
+                    Some(deferred_request) => match deferred_buffer {
+                        Some(deferred_buffer) => {
+                            // No reply for now, the request will be processed again once its waiter fires (unless the client closes the session meanwhile)
+                            deferred_buffer.store(&ipc_buf_backup, pointer_buffer);
+                            server_holder.defer_request(deferred_request);
+                        },
+                        None => {
+                            // Every deferred buffer is in use, thus the request fails instead
+                            let rc = results::lib::ipc::ResultDeferredBuffersFull::make();
+                            write_request_command_response_on_msg_buffer(&mut ctx, rc, command_type);
+                            reply_impl()?;
+                            return Err(ServerError::new(ServerErrorKind::CommandFailed, rc, handle));
+                        }
+                    },
+                    None => {
+                        reply_impl()?;
+
+                        // Only counted once replied, so that deferred requests (processed multiple times) are counted once
+                        if let Some(stats) = server_holder.stats.as_mut() {
+                            stats.record_request(rq_id);
+                        }
+
+                        // The kernel copied these to the client, our copies aren't needed anymore
+                        for copy_handle in forwarded_copy_handles {
+                            transport::close_handle(copy_handle).map_err(internal_error(handle))?;
+                        }
+
+                        if let Some((kind, rc)) = command_error {
+                            return Err(ServerError::new(kind, rc, handle));
+                        }
+                    }
+                };
             },
             cmif::CommandType::Control | cmif::CommandType::ControlWithContext => {
-                self.handle_control_command(&mut ctx, rq_id, command_type)?;
+                Self::handle_control_command(server_holder, &mut ctx, rq_id, command_type, new_sessions).map_err(protocol_violation(handle))?;
                 reply_impl()?;
             },
             cmif::CommandType::Close => {
-                cmif::server::write_close_command_response_on_msg_buffer(&mut ctx);
+                write_close_command_response_on_msg_buffer(&mut ctx);
                 reply_impl()?;
             }
-            _ => {
-                // Do nothing, since it might not be set at all without having failed (for instance, if a new session was accepted)
-                // If this actually failed and it reached this point server_found will be false, which is handled below
-            }
+            _ => {}
         };
 
-        if should_close_session {
-            self.server_holders.remove(index);
+        Ok(should_close_session)
+    }
+
+    fn handle_received_request_with_stats(server_holder: &mut ServerHolder, pointer_buffer: *mut u8, deferred_buffer: Option<&mut DeferredBuffer<P>>, new_sessions: &mut NewSessions) -> ServerResult<bool> {
+        if server_holder.stats.is_none() {
+            return Self::handle_received_request(server_holder, pointer_buffer, deferred_buffer, new_sessions);
+        }
+
+        let start_tick = arm::get_system_tick();
+        let rc = Self::handle_received_request(server_holder, pointer_buffer, deferred_buffer, new_sessions);
+        let elapsed_ticks = arm::get_system_tick() - start_tick;
+
+        if let Some(stats) = server_holder.stats.as_mut() {
+            stats.processing_ticks += elapsed_ticks;
+            if rc.is_err() {
+                stats.error_count += 1;
+            }
+        }
+        rc
+    }
+
+    // Accepts a new session, receives and handles a request or processes a deferred request again for the holder (signaled on the given handle), returning whether the holder's session got closed
+    // Note: this only accesses the given holder (and the deferred buffer reserved for it), so that holders can be processed by different threads
+    fn process_server_holder(server_holder: &mut ServerHolder, signaled_handle: svc::Handle, pointer_buffer: &mut [u8; P], deferred_buffer: Option<&mut DeferredBuffer<P>>, new_sessions: &mut NewSessions) -> ServerResult<bool> {
+        let server_info = server_holder.info;
+        let handle = server_info.handle;
+
+        match server_holder.handle_type {
+            WaitHandleType::Session => {
+                if let Some(deferred_request) = server_holder.deferred_request.take() {
+                    // The client can't send anything else while waiting for the reply, thus a signaled session means that it got closed
+                    if signaled_handle == handle {
+                        return Ok(true);
+                    }
+
+                    // Note: a failed clear just means that the waiter wasn't signaled anymore
+                    let _ = deferred_request.get_waiter().clear();
+
+                    // Holders with a deferred request always get the buffer their request was stored in (see ServerManager::reserve_deferred_buffer)
+                    let deferred_buffer = deferred_buffer.ok_or_else(|| internal_error(handle)(results::lib::ipc::ResultDeferredBuffersFull::make()))?;
+                    unsafe { core::ptr::copy(deferred_buffer.msg_buffer.as_ptr(), get_msg_buffer(), IPC_BUF_BACKUP_SIZE) };
+                    let deferred_pointer_buffer = deferred_buffer.pointer_buffer.as_mut_ptr();
+                    relocate_send_statics_on_msg_buffer(deferred_request.pointer_buffer_base, deferred_pointer_buffer, P);
+                    return Self::handle_received_request_with_stats(server_holder, deferred_pointer_buffer, Some(deferred_buffer), new_sessions);
+                }
+
+                if (P > 0) && server_info.uses_cmif_protocol() {
+                    // Send our pointer buffer as a C descriptor for kernel - why are Pointer buffers so fucking weird?
+                    let mut tmp_ctx = CommandContext::new_client(server_info);
+                    tmp_ctx.add_receive_static(ReceiveStaticDescriptor::new(pointer_buffer.as_ptr(), P)).map_err(internal_error(handle))?;
+                    cmif::client::write_command_on_msg_buffer(&mut tmp_ctx, cmif::CommandType::Invalid, 0);
+                }
+
+                match transport::reply_and_receive(&[handle], 0, -1) {
+                    Err(rc) => {
+                        if results::os::ResultSessionClosed::matches(rc) {
+                            return Ok(true);
+                        }
+                        else {
+                            // The kernel couldn't receive the client's message into our buffers
+                            return Err(protocol_violation(handle)(rc));
+                        }
+                    },
+                    _ => {}
+                };
+
+                Self::handle_received_request_with_stats(server_holder, pointer_buffer.as_mut_ptr(), deferred_buffer, new_sessions)
+            },
+            WaitHandleType::Server => {
+                let mut accept_impl = || -> Result<()> {
+                    // Accepted sessions are owned by their holders, like the port itself
+                    let new_handle = svc::accept_session(unsafe { svc::BorrowedHandle::borrow_raw(handle) })?.into_raw();
+
+                    if server_holder.is_mitm_service {
+                        let _sm_lock = unsafe { sync::ScopedLock::new(&mut G_SM_LOCK) };
+                        let sm = service::new_named_port_object::<sm::UserInterface>()?;
+                        let (info, session_handle) = sm.get().atmosphere_acknowledge_mitm_session(server_holder.service_name)?;
+                        new_sessions.push(server_holder.make_new_mitm_session(new_handle, session_handle.into_raw(), info)?)?;
+                        sm.get().detach_client(sf::ProcessId::new())?;
+                    }
+                    else {
+                        new_sessions.push(server_holder.make_new_session(new_handle)?)?;
+                    }
+                    Ok(())
+                };
+
+                accept_impl().map_err(internal_error(handle))?;
+                Ok(false)
+            }
+        }
+    }
+
+    // Policies are applied with the holder in place, since replying to the client (or closing its session) needs it
+    fn resolve_holder_result(error_policies: &ServerErrorPolicies, server_holder: Option<&ServerHolder>, rc: ServerResult<bool>) -> Result<bool> {
+        match (rc, server_holder) {
+            (Ok(should_close_session), _) => Ok(should_close_session),
+            (Err(error), Some(server_holder)) => error_policies.handle_error(server_holder, error),
+            (Err(error), None) => Err(error.rc)
+        }
+    }
+
+    fn process_holder_at(&mut self, index: usize, signaled_handle: svc::Handle) -> Result<()> {
+        if self.server_holders[index].get_mut().is_none() {
+            return Ok(());
         }
 
-        self.server_holders.append(&mut new_sessions);
+        let mut new_sessions = NewSessions::new();
+        let deferred_buffer = match self.reserve_deferred_buffer(index) {
+            Some(slot) => Some(self.deferred_buffers[slot].get_mut()),
+            None => None
+        };
+        let (handle, process_rc) = match self.server_holders[index].get_mut().as_mut() {
+            Some(server_holder) => (server_holder.info.handle, Self::process_server_holder(server_holder, signaled_handle, &mut self.pointer_buffer, deferred_buffer, &mut new_sessions)),
+            None => return Ok(())
+        };
 
-        match server_found {
-            true => Ok(()),
-            false => Err(results::lib::ipc::ResultSignaledServerNotFound::make())
+        // Sessions created before any error are kept anyway
+        let add_rc = self.add_server_holders(&mut new_sessions, index).map_err(internal_error(handle));
+        let error_policies = self.get_error_policies();
+        let rc = Self::resolve_holder_result(&error_policies, self.server_holders[index].get_mut().as_ref(), process_rc.and_then(|should_close_session| add_rc.map(|_| should_close_session)));
+        if let Ok(true) = rc {
+            *self.server_holders[index].get_mut() = None;
         }
+        self.release_deferred_buffer(index);
+        rc.map(|_| ())
+    }
+
+    pub(crate) fn process_signaled_handle(&mut self, handle: svc::Handle) -> Result<()> {
+        // Multiple holders might be waiting on the same handle (when their requests got deferred with the same waiter)
+        let mut found = false;
+        for index in 0..H {
+            if self.is_waiting_on(index, handle) {
+                found = true;
+                self.process_holder_at(index, handle)?;
+            }
+        }
+
+        result_return_unless!(found, results::lib::ipc::ResultSignaledServerNotFound);
+        Ok(())
     }
     
-    pub fn register_server<S: IServerObject + 'static>(&mut self, handle: svc::Handle, service_name: sm::ServiceName) {
-        self.server_holders.push(ServerHolder::new_server::<S>(handle, service_name));
+    pub fn register_server<S: IServerObject + 'static>(&mut self, handle: svc::Handle, service_name: sm::ServiceName, protocol: CommandProtocol) -> Result<()> {
+        self.add_server_holder(ServerHolder::new_server::<S>(handle, service_name, protocol))
     }
 
-    pub fn register_mitm_server<S: IMitmServerObject + 'static>(&mut self, handle: svc::Handle, service_name: sm::ServiceName) {
-        self.server_holders.push(ServerHolder::new_mitm_server::<S>(handle, service_name));
+    pub fn register_mitm_server<S: IMitmServerObject + 'static>(&mut self, handle: svc::Handle, service_name: sm::ServiceName) -> Result<()> {
+        self.add_server_holder(ServerHolder::new_mitm_server::<S>(handle, service_name))
     }
     
-    pub fn register_session<S: IServerObject + 'static>(&mut self, handle: svc::Handle) {
-        self.server_holders.push(ServerHolder::new_server_session::<S>(handle));
+    pub fn register_session<S: IServerObject + 'static>(&mut self, handle: svc::Handle, protocol: CommandProtocol) -> Result<()> {
+        self.add_server_holder(ServerHolder::new_server_session::<S>(handle, protocol))
     }
     
     pub fn register_service_server<S: IService + 'static>(&mut self) -> Result<()> {
@@ -736,7 +1748,7 @@ impl<const P: usize> ServerManager<P> {
         
         let sm = service::new_named_port_object::<sm::UserInterface>()?;
         let service_handle = sm.get().register_service(service_name, false, S::get_max_sesssions())?;
-        self.register_server::<S>(service_handle.handle, service_name);
+        self.register_server::<S>(service_handle.into_raw(), service_name, S::get_protocol())?;
         sm.get().detach_client(sf::ProcessId::new())?;
         Ok(())
     }
@@ -747,8 +1759,8 @@ impl<const P: usize> ServerManager<P> {
         let sm = service::new_named_port_object::<sm::UserInterface>()?;
         let (mitm_handle, query_handle) = sm.get().atmosphere_install_mitm(service_name)?;
 
-        self.register_mitm_server::<S>(mitm_handle.handle, service_name);
-        self.register_session::<MitmQueryServer<S>>(query_handle.handle);
+        self.register_mitm_server::<S>(mitm_handle.into_raw(), service_name)?;
+        self.register_session::<MitmQueryServer<S>>(query_handle.into_raw(), CommandProtocol::Cmif)?;
 
         sm.get().atmosphere_clear_future_mitm(service_name)?;
         sm.get().detach_client(sf::ProcessId::new())?;
@@ -758,15 +1770,18 @@ impl<const P: usize> ServerManager<P> {
     pub fn register_named_port_server<S: INamedPort + 'static>(&mut self) -> Result<()> {
         let port_handle = svc::manage_named_port(S::get_port_name().as_ptr(), S::get_max_sesssions())?;
 
-        self.register_server::<S>(port_handle, sm::ServiceName::empty());
-        Ok(())
+        // The port gets unregistered once the server is closed, like services do
+        let mut server_holder = ServerHolder::new_server::<S>(port_handle.into_raw(), sm::ServiceName::empty(), S::get_protocol());
+        server_holder.named_port_name = Some(S::get_port_name());
+        self.add_server_holder(server_holder)
     }
 
     pub fn process(&mut self) -> Result<()> {
-        let handles = self.prepare_wait_handles();
-        let index = wait::wait_handles(handles, -1)?;
+        let mut wait_handles: [svc::Handle; MAX_COUNT] = [0; MAX_COUNT];
+        let handle_count = self.prepare_wait_handles(&mut wait_handles, &[]);
+        let index = wait::wait_handles(&wait_handles[..handle_count], -1)?;
 
-        let signaled_handle = self.wait_handles[index];
+        let signaled_handle = wait_handles[index];
         self.process_signaled_handle(signaled_handle)?;
 
         Ok(())
@@ -776,7 +1791,7 @@ impl<const P: usize> ServerManager<P> {
         loop {
             match self.process() {
                 Err(rc) => {
-                    // TODO: handle results properly here
+                    // Errors of single sessions are already handled according to the error policies, whatever gets here stops processing
                     if results::os::ResultOperationCanceled::matches(rc) {
                         break;
                     }
@@ -788,4 +1803,173 @@ impl<const P: usize> ServerManager<P> {
 
         Ok(())
     }
+    fn process_worker_thread(args: &(*mut ServerWorkerState<P, H, D, R>, usize)) {
+        let (state, worker_index) = *args;
+        unsafe {
+            // Like every other thread, workers only have a shared reference to the manager (see ServerManager)
+            let manager = &*(*state).manager;
+            let mut pointer_buffer: [u8; P] = [0; P];
+
+            loop {
+                (*state).lock.lock();
+                if (*state).should_exit {
+                    (*state).lock.unlock();
+                    break;
+                }
+                let queue = &mut (&mut (*state).queues)[worker_index];
+                let (index, signaled_handle) = match queue.pending_holders.remove(0) {
+                    Some(pending_holder) => pending_holder,
+                    None => {
+                        // Reset the event while locked, so that we can't miss any holder pushed meanwhile
+                        let _ = svc::reset_signal(queue.request_event.client_handle.as_borrowed());
+                        let request_event_handle = queue.request_event.client_handle.as_raw();
+                        (*state).lock.unlock();
+                        let _ = wait::wait_handles(&[request_event_handle], -1);
+                        continue;
+                    }
+                };
+                let deferred_buffer_slot = manager.reserve_deferred_buffer(index);
+                let error_policies = manager.get_error_policies();
+                (*state).lock.unlock();
+
+                // The holder is busy, thus nobody else accesses its slot or its deferred buffer (and holders don't move while new ones are added to other slots)
+                // References to the slot are never kept across locking, since the shared state functions might access it too
+                let mut new_sessions = NewSessions::new();
+                let deferred_buffer = deferred_buffer_slot.map(|slot| manager.deferred_buffer(slot));
+                let (handle, process_rc) = match manager.holder_slot(index).as_mut() {
+                    Some(server_holder) => (server_holder.info.handle, Self::process_server_holder(server_holder, signaled_handle, &mut pointer_buffer, deferred_buffer, &mut new_sessions)),
+                    None => (0, Ok(false))
+                };
+
+                (*state).lock.lock();
+                let add_rc = manager.add_server_holders(&mut new_sessions, index).map_err(internal_error(handle));
+                (*state).lock.unlock();
+                let rc = Self::resolve_holder_result(&error_policies, manager.holder_slot(index).as_ref(), process_rc.and_then(|should_close_session| add_rc.map(|_| should_close_session)));
+
+                (*state).lock.lock();
+                let should_close_session = match rc {
+                    Ok(should_close_session) => should_close_session,
+                    Err(rc) => {
+                        if (*state).error.is_none() {
+                            (*state).error = Some(rc);
+                        }
+                        false
+                    }
+                };
+                let closed_server_holder = match should_close_session {
+                    true => manager.holder_slot(index).take(),
+                    false => None
+                };
+                manager.release_deferred_buffer(index);
+                manager.state().busy_holders[index] = false;
+                let _ = (*state).processed_event.signal();
+                (*state).lock.unlock();
+
+                // Closing might involve IPC (like unregistering services), thus it's done without holding the lock
+                drop(closed_server_holder);
+            }
+        }
+    }
+
+    fn dispatch_to_workers(&self, state: *mut ServerWorkerState<P, H, D, R>) -> Result<()> {
+        unsafe {
+            let processed_event_handle = (*state).processed_event.client_handle.as_raw();
+            let mut wait_handles: [svc::Handle; MAX_COUNT] = [0; MAX_COUNT];
+
+            loop {
+                // Workers add the sessions they create meanwhile, and holders they finished with get back to the wait set
+                (*state).lock.lock();
+                let worker_error = (*state).error.take();
+                let handle_count = self.prepare_wait_handles(&mut wait_handles, &[processed_event_handle]);
+                (*state).lock.unlock();
+
+                if let Some(rc) = worker_error {
+                    return Err(rc);
+                }
+
+                let index = wait::wait_handles(&wait_handles[..handle_count], -1)?;
+
+                let signaled_handle = wait_handles[index];
+                if signaled_handle == processed_event_handle {
+                    svc::reset_signal((*state).processed_event.client_handle.as_borrowed())?;
+                    continue;
+                }
+
+                // Multiple holders might be waiting on the same handle (see process_signaled_handle)
+                (*state).lock.lock();
+                let worker_count = (*state).queues.len();
+                let mut rc = Ok(());
+                for index in 0..H {
+                    if self.is_waiting_on(index, signaled_handle) {
+                        self.state().busy_holders[index] = true;
+                        // Holders of the same group always go to the same worker, which processes them one after another
+                        let queue = &mut (&mut (*state).queues)[self.state().holder_groups[index] % worker_count];
+                        // Every holder fits, and busy ones aren't pushed twice
+                        let _ = queue.pending_holders.push((index, signaled_handle));
+                        rc = rc.and(queue.request_event.signal());
+                    }
+                }
+                (*state).lock.unlock();
+                rc?;
+            }
+        }
+    }
+
+    // Same as loop_process, but only waiting happens on this thread: signaled holders are processed by the worker threads
+    // Note: sessions which might share objects (see ServerManager::get_new_session_group) are always processed by the same worker, thus they never run in parallel
+    pub fn loop_process_multithreaded(&mut self, worker_count: usize, worker_stack_size: usize) -> Result<()> {
+        result_return_if!(worker_count == 0, results::lib::ipc::ResultInvalidWorkerCount);
+
+        // Only shared references to the manager are used until every worker is done (see ServerManager)
+        let manager: &Self = self;
+        let mut state = ServerWorkerState {
+            lock: sync::Mutex::new(false),
+            manager,
+            queues: Vec::with_capacity(worker_count),
+            processed_event: wait::SystemEvent::new()?,
+            error: None,
+            should_exit: false
+        };
+        for _ in 0..worker_count {
+            state.queues.push(ServerWorkerQueue { pending_holders: util::FixedVec::new(), request_event: wait::SystemEvent::new()? });
+        }
+        let state_ref: *mut ServerWorkerState<P, H, D, R> = &mut state;
+
+        let mut workers: Vec<thread::Thread> = Vec::with_capacity(worker_count);
+        for worker_index in 0..worker_count {
+            workers.push(thread::Thread::new(Self::process_worker_thread, &(state_ref, worker_index), "ServerWorker", worker_stack_size)?);
+        }
+
+        // Note: threads must not be moved after they're initialized, thus they're initialized once all of them are in place
+        let mut rc = Ok(());
+        for worker in workers.iter_mut() {
+            rc = worker.initialize(thread::PRIORITY_AUTO, -2).and_then(|_| worker.start());
+            if rc.is_err() {
+                break;
+            }
+        }
+        if rc.is_ok() {
+            rc = manager.dispatch_to_workers(state_ref);
+        }
+
+        unsafe {
+            (*state_ref).lock.lock();
+            (*state_ref).should_exit = true;
+            for queue in (*state_ref).queues.iter() {
+                let _ = queue.request_event.signal();
+            }
+            (*state_ref).lock.unlock();
+        }
+
+        // Dropping the workers waits for them to finish
+        drop(workers);
+
+        // Holders which were still pending get waited on again
+        self.state.get_mut().busy_holders = [false; H];
+
+        match rc {
+            Err(rc) if results::os::ResultOperationCanceled::matches(rc) => Ok(()),
+            _ => rc
+        }
+    }
 }
\ No newline at end of file
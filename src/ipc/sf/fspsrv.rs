@@ -2,6 +2,8 @@ use crate::result::*;
 use crate::ipc::sf;
 use crate::mem;
 use crate::util;
+use crate::version;
+use crate::service;
 
 bit_enum! {
     FileOpenMode (u32) {
@@ -61,30 +63,95 @@ pub struct DirectoryEntry {
 }
 const_assert!(core::mem::size_of::<DirectoryEntry>() == 0x310);
 
-pub trait IFile {
-    ipc_cmif_interface_define_command!(read: (option: FileReadOption, offset: usize, size: usize, buf: sf::OutNonSecureMapAliasBuffer) => (read_size: usize));
-    ipc_cmif_interface_define_command!(write: (option: FileWriteOption, offset: usize, size: usize, buf: sf::InNonSecureMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(get_size: () => (size: usize));
+// Times are POSIX timestamps (in seconds)
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct FileTimeStampRaw {
+    pub create: i64,
+    pub modify: i64,
+    pub access: i64,
+    pub is_local_time: bool,
+    pub pad: [u8; 7]
 }
+const_assert!(core::mem::size_of::<FileTimeStampRaw>() == 0x20);
 
-pub trait IDirectory {
-    ipc_cmif_interface_define_command!(read: (out_entries: sf::OutMapAliasBuffer) => (read_count: u64));
-    ipc_cmif_interface_define_command!(get_entry_count: () => (count: u64));
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum OperationId {
+    Clear = 0,
+    ClearSignature = 1,
+    InvalidateCache = 2,
+    QueryRange = 3
 }
 
-pub trait IFileSystem {
-    ipc_cmif_interface_define_command!(create_file: (attribute: FileAttribute, size: usize, path_buf: sf::InPointerBuffer) => ());
-    ipc_cmif_interface_define_command!(delete_file: (path_buf: sf::InPointerBuffer) => ());
-    ipc_cmif_interface_define_command!(create_directory: (path_buf: sf::InPointerBuffer) => ());
-    ipc_cmif_interface_define_command!(delete_directory: (path_buf: sf::InPointerBuffer) => ());
-    ipc_cmif_interface_define_command!(delete_directory_recursively: (path_buf: sf::InPointerBuffer) => ());
-    ipc_cmif_interface_define_command!(get_entry_type: (path_buf: sf::InPointerBuffer) => (entry_type: DirectoryEntryType));
-    ipc_cmif_interface_define_command!(open_file: (mode: FileOpenMode, path_buf: sf::InPointerBuffer) => (file: mem::Shared<dyn sf::IObject>));
-    ipc_cmif_interface_define_command!(open_directory: (mode: DirectoryOpenMode, path_buf: sf::InPointerBuffer) => (dir: mem::Shared<dyn sf::IObject>));
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct RangeInfo {
+    pub aes_ctr_key_type: i32,
+    pub speed_emulation_type: i32,
+    pub reserved: [u8; 0x38]
+}
+const_assert!(core::mem::size_of::<RangeInfo>() == 0x40);
+
+impl RangeInfo {
+    pub const fn new() -> Self {
+        Self { aes_ctr_key_type: 0, speed_emulation_type: 0, reserved: [0; 0x38] }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum QueryId {
+    SetConcatenationFileAttribute = 0,
+    UpdateMac = 1,
+    IsSignedSystemPartitionOnSdCardValid = 2,
+    QueryUnpreparedFileInformation = 3
 }
 
-pub trait IFileSystemProxy {
-    ipc_cmif_interface_define_command!(set_current_process: (process_id: sf::ProcessId) => ());
-    ipc_cmif_interface_define_command!(open_sd_card_filesystem: () => (sd_filesystem: mem::Shared<dyn sf::IObject>));
-    ipc_cmif_interface_define_command!(output_access_log_to_sd_card: (access_log: sf::InMapAliasBuffer) => ());
+ipc_sf_define_interface_trait! {
+    trait IFile {
+        read [0, version::VersionInterval::all()]: (option: FileReadOption, offset: usize, size: usize, buf: sf::OutNonSecureMapAliasBuffer) => (read_size: usize);
+        write [1, version::VersionInterval::all()]: (option: FileWriteOption, offset: usize, size: usize, buf: sf::InNonSecureMapAliasBuffer) => ();
+        flush [2, version::VersionInterval::all()]: () => ();
+        set_size [3, version::VersionInterval::all()]: (size: usize) => ();
+        get_size [4, version::VersionInterval::all()]: () => (size: usize);
+        operate_range [5, version::VersionInterval::from(version::Version::new(4, 0, 0))]: (operation_id: OperationId, offset: usize, size: usize) => (range_info: RangeInfo);
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait IDirectory {
+        read [0, version::VersionInterval::all()]: (out_entries: sf::OutMapAliasBuffer) => (read_count: u64);
+        get_entry_count [1, version::VersionInterval::all()]: () => (count: u64);
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait IFileSystem {
+        create_file [0, version::VersionInterval::all()]: (attribute: FileAttribute, size: usize, path_buf: sf::InPointerBuffer) => ();
+        delete_file [1, version::VersionInterval::all()]: (path_buf: sf::InPointerBuffer) => ();
+        create_directory [2, version::VersionInterval::all()]: (path_buf: sf::InPointerBuffer) => ();
+        delete_directory [3, version::VersionInterval::all()]: (path_buf: sf::InPointerBuffer) => ();
+        delete_directory_recursively [4, version::VersionInterval::all()]: (path_buf: sf::InPointerBuffer) => ();
+        rename_file [5, version::VersionInterval::all()]: (old_path_buf: sf::InPointerBuffer, new_path_buf: sf::InPointerBuffer) => ();
+        rename_directory [6, version::VersionInterval::all()]: (old_path_buf: sf::InPointerBuffer, new_path_buf: sf::InPointerBuffer) => ();
+        get_entry_type [7, version::VersionInterval::all()]: (path_buf: sf::InPointerBuffer) => (entry_type: DirectoryEntryType);
+        open_file [8, version::VersionInterval::all()]: (mode: FileOpenMode, path_buf: sf::InPointerBuffer) => (file: mem::Shared<dyn sf::IObject> as service::fspsrv::File);
+        open_directory [9, version::VersionInterval::all()]: (mode: DirectoryOpenMode, path_buf: sf::InPointerBuffer) => (dir: mem::Shared<dyn sf::IObject> as service::fspsrv::Directory);
+        commit [10, version::VersionInterval::all()]: () => ();
+        get_free_space_size [11, version::VersionInterval::all()]: (path_buf: sf::InPointerBuffer) => (size: usize);
+        get_total_space_size [12, version::VersionInterval::all()]: (path_buf: sf::InPointerBuffer) => (size: usize);
+        clean_directory_recursively [13, version::VersionInterval::from(version::Version::new(3, 0, 0))]: (path_buf: sf::InPointerBuffer) => ();
+        get_file_time_stamp_raw [14, version::VersionInterval::from(version::Version::new(3, 0, 0))]: (path_buf: sf::InPointerBuffer) => (time_stamp: FileTimeStampRaw);
+        query_entry [15, version::VersionInterval::from(version::Version::new(4, 0, 0))]: (query_id: QueryId, out_buf: sf::OutNonSecureMapAliasBuffer, in_buf: sf::InNonSecureMapAliasBuffer, path_buf: sf::InPointerBuffer) => ();
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait IFileSystemProxy {
+        set_current_process [1, version::VersionInterval::all()]: (process_id: sf::ProcessId) => ();
+        open_sd_card_filesystem [18, version::VersionInterval::all()]: () => (sd_filesystem: mem::Shared<dyn sf::IObject> as service::fspsrv::FileSystem);
+        output_access_log_to_sd_card [1006, version::VersionInterval::all()]: (access_log: sf::InMapAliasBuffer) => ();
+    }
 }
\ No newline at end of file
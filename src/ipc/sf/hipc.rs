@@ -1,17 +1,22 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::ipc::sf::sm;
+use crate::version;
 
 // Interfaces related to core serverside IPC (for control requests and MitM support)
 
-pub trait IHipcManager {
-    ipc_control_interface_define_command!(convert_current_object_to_domain: () => (domain_object_id: u32));
-    ipc_control_interface_define_command!(copy_from_current_domain: (domain_object_id: u32) => (handle: sf::MoveHandle));
-    ipc_control_interface_define_command!(clone_current_object: () => (cloned_handle: sf::MoveHandle));
-    ipc_control_interface_define_command!(query_pointer_buffer_size: () => (pointer_buffer_size: u16));
-    ipc_control_interface_define_command!(clone_current_object_ex: (tag: u32) => (cloned_handle: sf::MoveHandle));
+ipc_sf_define_interface_trait! {
+    trait IHipcManager [control] {
+        convert_current_object_to_domain [0, version::VersionInterval::all()]: () => (domain_object_id: u32);
+        copy_from_current_domain [1, version::VersionInterval::all()]: (domain_object_id: u32) => (handle: sf::MoveHandle);
+        clone_current_object [2, version::VersionInterval::all()]: () => (cloned_handle: sf::MoveHandle);
+        query_pointer_buffer_size [3, version::VersionInterval::all()]: () => (pointer_buffer_size: u16);
+        clone_current_object_ex [4, version::VersionInterval::all()]: (tag: u32) => (cloned_handle: sf::MoveHandle);
+    }
 }
 
-pub trait IMitmQueryServer {
-    ipc_cmif_interface_define_command!(should_mitm: (info: sm::MitmProcessInfo) => (should_mitm: bool));
+ipc_sf_define_interface_trait! {
+    trait IMitmQueryServer {
+        should_mitm [65000, version::VersionInterval::all()]: (info: sm::MitmProcessInfo) => (should_mitm: bool);
+    }
 }
\ No newline at end of file
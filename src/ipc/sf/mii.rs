@@ -2,6 +2,8 @@ use crate::result::*;
 use crate::ipc::sf;
 use crate::mem;
 use crate::util;
+use crate::version;
+use crate::service;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(u32)]
@@ -125,14 +127,18 @@ pub struct StoreData {
 }
 const_assert!(core::mem::size_of::<StoreData>() == 0x44);
 
-pub trait IDatabaseService {
-    ipc_cmif_interface_define_command!(is_updated: (flag: SourceFlag) => (updated: bool));
-    ipc_cmif_interface_define_command!(is_full: () => (full: bool));
-    ipc_cmif_interface_define_command!(get_count: (flag: SourceFlag) => (count: u32));
-    ipc_cmif_interface_define_command!(get_1: (flag: SourceFlag, out_char_infos: sf::OutMapAliasBuffer) => (count: u32));
-    ipc_cmif_interface_define_command!(build_random: (age: Age, gender: Gender, face_color: FaceColor) => (char_info: CharInfo));
+ipc_sf_define_interface_trait! {
+    trait IDatabaseService {
+        is_updated [0, version::VersionInterval::all()]: (flag: SourceFlag) => (updated: bool);
+        is_full [1, version::VersionInterval::all()]: () => (full: bool);
+        get_count [2, version::VersionInterval::all()]: (flag: SourceFlag) => (count: u32);
+        get_1 [4, version::VersionInterval::all()]: (flag: SourceFlag, out_char_infos: sf::OutMapAliasBuffer) => (count: u32);
+        build_random [6, version::VersionInterval::all()]: (age: Age, gender: Gender, face_color: FaceColor) => (char_info: CharInfo);
+    }
 }
 
-pub trait IStaticService {
-    ipc_cmif_interface_define_command!(get_database_service: (key_code: SpecialKeyCode) => (database_service: mem::Shared<dyn sf::IObject>));
+ipc_sf_define_interface_trait! {
+    trait IStaticService {
+        get_database_service [0, version::VersionInterval::all()]: (key_code: SpecialKeyCode) => (database_service: mem::Shared<dyn sf::IObject> as service::mii::DatabaseService);
+    }
 }
\ No newline at end of file
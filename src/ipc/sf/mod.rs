@@ -1,113 +1,313 @@
 use super::*;
 use crate::svc;
 use crate::version;
+use crate::wait;
 use core::mem;
+use core::any;
+use core::marker::PhantomData;
+use core::cell::Cell;
 use alloc::vec::Vec;
 use alloc::string::String;
 
-#[derive(Clone)]
-pub struct Buffer<const A: BufferAttribute, const S: usize> {
-    pub buf: *const u8,
-    pub size: usize
+// Buffer contents are reinterpreted as these types, thus any bit pattern must be a valid value of them (no bools, enums, references...)
+// Safety: only implement this for plain data types, like #[repr(C)] structs made of other PlainData types
+
+pub unsafe trait PlainData: Copy {}
+
+macro_rules! impl_plain_data {
+    ($($t:ty),*) => {
+        $( unsafe impl PlainData for $t {} )*
+    };
 }
 
-impl<const A: BufferAttribute, const S: usize> Buffer<A, S> {
-    pub const fn new() -> Self {
-        Self { buf: ptr::null_mut(), size: 0 }
+impl_plain_data!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: PlainData, const N: usize> PlainData for [T; N] {}
+
+// Buffers borrow the memory they describe for as long as the request using them is alive
+// In buffers can only be read, while out buffers (which can be written) can't be copied, thus there's never more than one way to write their memory
+// Note: the raw constructors (from_const/from_mut) are only meant for memory whose lifetime can't be expressed otherwise, and are therefore unsafe
+
+fn check_buffer_layout<T>(buf: *const u8, size: usize, count: usize) -> Result<()> {
+    // Buffer contents come from the client: never trust their size or address
+    result_return_unless!(size >= (count * mem::size_of::<T>()), results::lib::ipc::ResultBufferTooSmall);
+    result_return_unless!((buf as usize % mem::align_of::<T>()) == 0, results::lib::ipc::ResultInvalidBufferAlignment);
+    Ok(())
+}
+
+fn get_buffer_count<T>(size: usize) -> usize {
+    match mem::size_of::<T>() {
+        0 => 0,
+        type_size => size / type_size
     }
+}
 
-    pub const fn from_other<const B: BufferAttribute, const Z: usize>(other: &Buffer<B, Z>) -> Self {
-        Self { buf: other.buf, size: other.size }
+fn get_buffer_as<'a, T: PlainData>(buf: *const u8, size: usize) -> Result<&'a T> {
+    check_buffer_layout::<T>(buf, size, 1)?;
+    unsafe {
+        Ok(&*(buf as *const T))
     }
-    
-    pub const fn from_const<T>(buf: *const T, size: usize) -> Self {
-        Self { buf: buf as *const u8, size }
+}
+
+fn get_buffer_slice<'a, T: PlainData>(buf: *const u8, size: usize) -> Result<&'a [T]> {
+    let count = get_buffer_count::<T>(size);
+    if count == 0 {
+        return Ok(&[]);
+    }
+
+    check_buffer_layout::<T>(buf, size, count)?;
+    unsafe {
+        Ok(core::slice::from_raw_parts(buf as *const T, count))
     }
+}
 
-    pub const fn from_mut<T>(buf: *mut T, size: usize) -> Self {
-        Self { buf: buf as *const u8, size }
+fn get_buffer_string(buf: *const u8, size: usize) -> String {
+    let mut string = String::with_capacity(size);
+    if let Ok(chars) = get_buffer_slice::<u8>(buf, size) {
+        for cur_char in chars {
+            if *cur_char == 0 {
+                break;
+            }
+            string.push(*cur_char as char);
+        }
     }
+    string
+}
+
+#[derive(Copy, Clone)]
+pub struct InBuffer<'a, const A: BufferAttribute, const S: usize> {
+    buf: *const u8,
+    size: usize,
+    phantom: PhantomData<&'a [u8]>
+}
 
-    pub const fn from_var<T>(var: &T) -> Self {
-        Self::from_const(var as *const T, mem::size_of::<T>())
+impl<'a, const A: BufferAttribute, const S: usize> InBuffer<'a, A, S> {
+    pub const fn new() -> Self {
+        Self { buf: ptr::null(), size: 0, phantom: PhantomData }
     }
 
-    pub const fn from_array<T>(arr: &[T]) -> Self {
-        Self::from_const(arr.as_ptr(), arr.len() * mem::size_of::<T>())
+    pub const unsafe fn from_const<T>(buf: *const T, size: usize) -> Self {
+        Self { buf: buf as *const u8, size, phantom: PhantomData }
     }
 
-    pub const fn get_as<T>(&self) -> &T {
+    pub const fn from_var<T>(var: &'a T) -> Self {
         unsafe {
-            &*(self.buf as *const T)
+            Self::from_const(var as *const T, mem::size_of::<T>())
         }
     }
 
-    pub fn get_mut_as<T>(&self) -> &mut T {
+    pub const fn from_array<T>(arr: &'a [T]) -> Self {
         unsafe {
-            &mut *(self.buf as *mut T)
+            Self::from_const(arr.as_ptr(), arr.len() * mem::size_of::<T>())
         }
     }
 
-    pub fn set_as<T>(&mut self, t: T) {
+    pub const fn get_address(&self) -> *const u8 {
+        self.buf
+    }
+
+    pub const fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_as<T: PlainData>(&self) -> Result<&'a T> {
+        get_buffer_as(self.buf, self.size)
+    }
+
+    pub fn get_slice<T: PlainData>(&self) -> Result<&'a [T]> {
+        get_buffer_slice(self.buf, self.size)
+    }
+
+    pub fn get_string(&self) -> String {
+        get_buffer_string(self.buf, self.size)
+    }
+}
+
+pub struct OutBuffer<'a, const A: BufferAttribute, const S: usize> {
+    buf: *mut u8,
+    size: usize,
+    phantom: PhantomData<&'a mut [u8]>
+}
+
+impl<'a, const A: BufferAttribute, const S: usize> OutBuffer<'a, A, S> {
+    pub const fn new() -> Self {
+        Self { buf: ptr::null_mut(), size: 0, phantom: PhantomData }
+    }
+
+    pub const unsafe fn from_mut<T>(buf: *mut T, size: usize) -> Self {
+        Self { buf: buf as *mut u8, size, phantom: PhantomData }
+    }
+
+    pub fn from_mut_var<T>(var: &'a mut T) -> Self {
         unsafe {
-            *(self.buf as *mut T) = t;
+            Self::from_mut(var as *mut T, mem::size_of::<T>())
         }
     }
 
-    pub fn get_slice<T>(&self) -> &[T] {
+    pub fn from_mut_array<T>(arr: &'a mut [T]) -> Self {
         unsafe {
-            core::slice::from_raw_parts(self.buf as *const T, self.size / mem::size_of::<T>())
+            Self::from_mut(arr.as_mut_ptr(), arr.len() * mem::size_of::<T>())
         }
     }
 
-    pub fn get_mut_slice<T>(&self) -> &mut [T] {
+    pub const fn get_address(&self) -> *const u8 {
+        self.buf
+    }
+
+    pub const fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_as<T: PlainData>(&self) -> Result<&T> {
+        get_buffer_as(self.buf, self.size)
+    }
+
+    pub fn get_mut_as<T: PlainData>(&mut self) -> Result<&mut T> {
+        check_buffer_layout::<T>(self.buf, self.size, 1)?;
         unsafe {
-            core::slice::from_raw_parts_mut(self.buf as *mut T, self.size / mem::size_of::<T>())
+            Ok(&mut *(self.buf as *mut T))
         }
     }
 
-    pub fn get_string(&self) -> String {
+    pub fn set_as<T: PlainData>(&mut self, t: T) -> Result<()> {
+        *self.get_mut_as()? = t;
+        Ok(())
+    }
+
+    pub fn get_slice<T: PlainData>(&self) -> Result<&[T]> {
+        get_buffer_slice(self.buf, self.size)
+    }
+
+    pub fn get_mut_slice<T: PlainData>(&mut self) -> Result<&mut [T]> {
+        let count = get_buffer_count::<T>(self.size);
+        if count == 0 {
+            return Ok(&mut []);
+        }
+
+        check_buffer_layout::<T>(self.buf, self.size, count)?;
         unsafe {
-            let mut string = String::with_capacity(self.size);
-            for i in 0..self.size {
-                let cur_char = *self.buf.add(i) as char;
-                if cur_char == '\0' {
-                    break;
-                }
-                string.push(cur_char);
-            }
-            string
+            Ok(core::slice::from_raw_parts_mut(self.buf as *mut T, count))
         }
     }
 
+    pub fn get_string(&self) -> String {
+        get_buffer_string(self.buf, self.size)
+    }
+
     pub fn set_string(&mut self, string: String) {
-        unsafe {
-            // First memset to zero so that it will be a valid nul-terminated string
-            core::ptr::write_bytes(self.buf as *mut u8, 0, self.size);
-            core::ptr::copy(string.as_ptr(), self.buf as *mut u8, core::cmp::min(self.size - 1, string.len()));
+        if let Ok(chars) = self.get_mut_slice::<u8>() {
+            if let Some((_, str_chars)) = chars.split_last_mut() {
+                // First memset to zero so that it will be a valid nul-terminated string
+                str_chars.fill(0);
+                let copy_len = core::cmp::min(str_chars.len(), string.len());
+                str_chars[..copy_len].copy_from_slice(&string.as_bytes()[..copy_len]);
+            }
         }
     }
 }
 
-pub type InMapAliasBuffer = Buffer<{bit_group!{ BufferAttribute [In, MapAlias] }}, 0>;
-pub type OutMapAliasBuffer = Buffer<{bit_group!{ BufferAttribute [Out, MapAlias] }}, 0>;
-pub type InNonSecureMapAliasBuffer = Buffer<{bit_group!{ BufferAttribute [In, MapAlias, MapTransferAllowsNonSecure] }}, 0>;
-pub type OutNonSecureMapAliasBuffer = Buffer<{bit_group!{ BufferAttribute [Out, MapAlias, MapTransferAllowsNonSecure] }}, 0>;
-pub type InAutoSelectBuffer = Buffer<{bit_group!{ BufferAttribute [In, AutoSelect] }}, 0>;
-pub type OutAutoSelectBuffer = Buffer<{bit_group!{ BufferAttribute [Out, AutoSelect] }}, 0>;
-pub type InPointerBuffer = Buffer<{bit_group!{ BufferAttribute [In, Pointer] }}, 0>;
-pub type OutPointerBuffer = Buffer<{bit_group!{ BufferAttribute [Out, Pointer] }}, 0>;
-pub type InFixedPointerBuffer<T> = Buffer<{bit_group!{ BufferAttribute [In, Pointer, FixedSize] }}, {mem::size_of::<T>()}>;
-pub type OutFixedPointerBuffer<T> = Buffer<{bit_group!{ BufferAttribute [Out, Pointer, FixedSize] }}, {mem::size_of::<T>()}>;
+// The buffer attributes/size are resolved in lifetime-free kind types, keeping the borrowed lifetime out of the const arguments (generic_const_exprs can't evaluate consts depending on late-bound lifetimes)
+
+pub struct InBufferKind<const A: BufferAttribute, const S: usize>;
+pub struct OutBufferKind<const A: BufferAttribute, const S: usize>;
+
+pub trait BufferOf {
+    type Buffer<'a>;
+}
+
+impl<const A: BufferAttribute, const S: usize> BufferOf for InBufferKind<A, S> {
+    type Buffer<'a> = InBuffer<'a, A, S>;
+}
+
+impl<const A: BufferAttribute, const S: usize> BufferOf for OutBufferKind<A, S> {
+    type Buffer<'a> = OutBuffer<'a, A, S>;
+}
+
+pub type InMapAliasBufferKind = InBufferKind<{bit_group!{ BufferAttribute [In, MapAlias] }}, 0>;
+pub type OutMapAliasBufferKind = OutBufferKind<{bit_group!{ BufferAttribute [Out, MapAlias] }}, 0>;
+pub type InNonSecureMapAliasBufferKind = InBufferKind<{bit_group!{ BufferAttribute [In, MapAlias, MapTransferAllowsNonSecure] }}, 0>;
+pub type OutNonSecureMapAliasBufferKind = OutBufferKind<{bit_group!{ BufferAttribute [Out, MapAlias, MapTransferAllowsNonSecure] }}, 0>;
+pub type InAutoSelectBufferKind = InBufferKind<{bit_group!{ BufferAttribute [In, AutoSelect] }}, 0>;
+pub type OutAutoSelectBufferKind = OutBufferKind<{bit_group!{ BufferAttribute [Out, AutoSelect] }}, 0>;
+pub type InPointerBufferKind = InBufferKind<{bit_group!{ BufferAttribute [In, Pointer] }}, 0>;
+pub type OutPointerBufferKind = OutBufferKind<{bit_group!{ BufferAttribute [Out, Pointer] }}, 0>;
+pub type InFixedPointerBufferKind<T> = InBufferKind<{bit_group!{ BufferAttribute [In, Pointer, FixedSize] }}, {mem::size_of::<T>()}>;
+pub type OutFixedPointerBufferKind<T> = OutBufferKind<{bit_group!{ BufferAttribute [Out, Pointer, FixedSize] }}, {mem::size_of::<T>()}>;
+
+pub type InMapAliasBuffer<'a> = <InMapAliasBufferKind as BufferOf>::Buffer<'a>;
+pub type OutMapAliasBuffer<'a> = <OutMapAliasBufferKind as BufferOf>::Buffer<'a>;
+pub type InNonSecureMapAliasBuffer<'a> = <InNonSecureMapAliasBufferKind as BufferOf>::Buffer<'a>;
+pub type OutNonSecureMapAliasBuffer<'a> = <OutNonSecureMapAliasBufferKind as BufferOf>::Buffer<'a>;
+pub type InAutoSelectBuffer<'a> = <InAutoSelectBufferKind as BufferOf>::Buffer<'a>;
+pub type OutAutoSelectBuffer<'a> = <OutAutoSelectBufferKind as BufferOf>::Buffer<'a>;
+pub type InPointerBuffer<'a> = <InPointerBufferKind as BufferOf>::Buffer<'a>;
+pub type OutPointerBuffer<'a> = <OutPointerBufferKind as BufferOf>::Buffer<'a>;
+pub type InFixedPointerBuffer<'a, T> = <InFixedPointerBufferKind<T> as BufferOf>::Buffer<'a>;
+pub type OutFixedPointerBuffer<'a, T> = <OutFixedPointerBufferKind<T> as BufferOf>::Buffer<'a>;
+
+// Received handles (client output or server input) are ours, thus closed when dropped unless taken out
+// Handles we send are either borrowed (copy handles) or given away once written to the message (move handles)
 
-#[derive(Clone)]
 pub struct Handle<const M: HandleMode> {
-    pub handle: svc::Handle
+    handle: svc::Handle,
+    owned: Cell<bool>
 }
 
 impl<const M: HandleMode> Handle<M> {
-    pub const fn from(handle: svc::Handle) -> Self {
-        Self { handle }
+    pub const unsafe fn from_raw(handle: svc::Handle, owned: bool) -> Self {
+        Self { handle, owned: Cell::new(owned) }
+    }
+
+    pub fn from_owned(handle: svc::OwnedHandle) -> Self {
+        unsafe {
+            Self::from_raw(handle.into_raw(), true)
+        }
+    }
+
+    pub const fn from_borrowed(handle: svc::BorrowedHandle<'_>) -> Self {
+        unsafe {
+            Self::from_raw(handle.as_raw(), false)
+        }
+    }
+
+    pub const fn as_raw(&self) -> svc::Handle {
+        self.handle
+    }
+
+    pub const fn as_borrowed(&self) -> svc::BorrowedHandle<'_> {
+        unsafe {
+            svc::BorrowedHandle::borrow_raw(self.handle)
+        }
+    }
+
+    pub fn is_owned(&self) -> bool {
+        self.owned.get()
+    }
+
+    pub fn into_owned(self) -> Result<svc::OwnedHandle> {
+        result_return_unless!(self.is_owned(), results::lib::ipc::ResultHandleNotOwned);
+        unsafe {
+            Ok(svc::OwnedHandle::from_raw(self.release()))
+        }
+    }
+
+    // For handles whose ownership is tracked elsewhere (like IPC sessions)
+    pub fn into_raw(self) -> svc::Handle {
+        self.release()
+    }
+
+    pub(crate) fn release(&self) -> svc::Handle {
+        self.owned.set(false);
+        self.handle
+    }
+}
+
+impl<const M: HandleMode> Drop for Handle<M> {
+    fn drop(&mut self) {
+        if self.is_owned() && (self.handle != svc::INVALID_HANDLE) {
+            // Note: this might be an IPC session handle, thus it's closed through the transport
+            let _ = transport::close_handle(self.handle);
+        }
     }
 }
 
@@ -164,7 +364,7 @@ impl Session {
             if self.object_info.is_domain() {
                 let mut ctx = CommandContext::new_client(self.object_info);
                 cmif::client::write_request_command_on_msg_buffer(&mut ctx, None, cmif::DomainCommandType::Close);
-                let _ = svc::send_sync_request(self.object_info.handle);
+                let _ = transport::send_sync_request(self.object_info.handle);
             }
             else if self.object_info.owns_handle {
                 let mut ctx = CommandContext::new_client(self.object_info);
@@ -174,10 +374,10 @@ impl Session {
                     CommandProtocol::Tipc => tipc::client::write_close_command_on_msg_buffer(&mut ctx)
                 };
 
-                let _ = svc::send_sync_request(self.object_info.handle);
+                let _ = transport::send_sync_request(self.object_info.handle);
             }
             if self.object_info.owns_handle {
-                let _ = svc::close_handle(self.object_info.handle);
+                let _ = transport::close_handle(self.object_info.handle);
             }
             self.object_info = ObjectInfo::new();
         }
@@ -208,19 +408,12 @@ impl CommandMetadata {
         Self { protocol, rq_id, command_fn, min_ver, max_ver }
     }
 
+    pub fn new_with_interval(protocol: CommandProtocol, rq_id: u32, command_fn: CommandFn, ver_intv: version::VersionInterval) -> Self {
+        Self::new(protocol, rq_id, command_fn, ver_intv.min, ver_intv.max)
+    }
+
     pub fn validate_version(&self) -> bool {
-        let ver = version::get_version();
-        if let Some(min_v) = self.min_ver {
-            if ver < min_v {
-                return false;
-            }
-        }
-        if let Some(max_v) = self.max_ver {
-            if ver > max_v {
-                return false;
-            }
-        }
-        true
+        version::VersionInterval { min: self.min_ver, max: self.max_ver }.contains(version::get_version())
     }
 
     pub fn matches(&self, protocol: CommandProtocol, rq_id: u32) -> bool {
@@ -230,6 +423,7 @@ impl CommandMetadata {
 
 // This trait is analogous to N's IServiceObject type - the base for any kind of IPC interface
 // IClientObject (on service module) and IServerObject (on server module) are wrappers for some specific kind of objects
+// Only server objects handle commands (their table comes from their interfaces' server traits), client objects just have an empty table
 
 pub trait IObject {
     fn get_session(&mut self) -> &mut Session;
@@ -263,6 +457,16 @@ pub trait IObject {
         self.get_info().is_domain()
     }
 
+    // Server objects whose commands defer requests (returning ResultRequestDeferredByUser) provide the waiter which signals when to process them again
+    fn get_deferral_waiter(&mut self) -> Option<wait::Waiter<'_>> {
+        None
+    }
+
+    // Identifies the actual object type behind a dyn IObject (see server::get_incoming_object)
+    fn get_type_id(&self) -> any::TypeId where Self: 'static {
+        any::TypeId::of::<Self>()
+    }
+
     fn call_self_command(&mut self, command_fn: CommandFn, ctx: &mut server::ServerContext) -> Result<()> {
         let original_fn: CommandSpecificFn<Self> = unsafe { mem::transmute(command_fn) };
         (original_fn)(self, ctx)
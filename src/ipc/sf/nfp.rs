@@ -5,6 +5,7 @@ use crate::util;
 
 use crate::ipc::sf::applet;
 use crate::ipc::sf::mii;
+use crate::version;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(C)]
@@ -148,107 +149,119 @@ pub struct RegisterInfoPrivate {
 }
 const_assert!(core::mem::size_of::<RegisterInfoPrivate>() == 0x100);
 
-pub trait IUser {
-    ipc_cmif_interface_define_command!(initialize: (aruid: applet::AppletResourceUserId, process_id: sf::ProcessId, mcu_data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(finalize: () => ());
-    ipc_cmif_interface_define_command!(list_devices: (out_devices: sf::OutPointerBuffer) => (count: u32));
-    ipc_cmif_interface_define_command!(start_detection: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(stop_detection: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(mount: (device_handle: DeviceHandle, model_type: ModelType, mount_target: MountTarget) => ());
-    ipc_cmif_interface_define_command!(unmount: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(open_application_area: (device_handle: DeviceHandle, access_id: AccessId) => ());
-    ipc_cmif_interface_define_command!(get_application_area: (device_handle: DeviceHandle, out_data: sf::OutMapAliasBuffer) => (size: u32));
-    ipc_cmif_interface_define_command!(set_application_area: (device_handle: DeviceHandle, data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(flush: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(restore: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(create_application_area: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(get_tag_info: (device_handle: DeviceHandle, out_tag_info: sf::OutFixedPointerBuffer<TagInfo>) => ());
-    ipc_cmif_interface_define_command!(get_register_info: (device_handle: DeviceHandle, out_register_info: sf::OutFixedPointerBuffer<RegisterInfo>) => ());
-    ipc_cmif_interface_define_command!(get_common_info: (device_handle: DeviceHandle, out_common_info: sf::OutFixedPointerBuffer<CommonInfo>) => ());
-    ipc_cmif_interface_define_command!(get_model_info: (device_handle: DeviceHandle, out_model_info: sf::OutFixedPointerBuffer<ModelInfo>) => ());
-    ipc_cmif_interface_define_command!(attach_activate_event: (device_handle: DeviceHandle) => (activate_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(attach_deactivate_event: (device_handle: DeviceHandle) => (deactivate_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(get_state: () => (state: State));
-    ipc_cmif_interface_define_command!(get_device_state: (device_handle: DeviceHandle) => (device_state: DeviceState));
-    ipc_cmif_interface_define_command!(get_npad_id: (device_handle: DeviceHandle) => (npad_id: u32));
-    ipc_cmif_interface_define_command!(get_application_area_size: (device_handle: DeviceHandle) => (size: u32));
-    ipc_cmif_interface_define_command!(attach_availability_change_event: () => (availability_change_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(recreate_application_area: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ());
-}
-
-pub trait IUserManager {
-    ipc_cmif_interface_define_command!(create_user_interface: () => (user_interface: mem::Shared<dyn sf::IObject>));
-}
-
-pub trait ISystem {
-    ipc_cmif_interface_define_command!(initialize_system: (aruid: applet::AppletResourceUserId, process_id: sf::ProcessId, mcu_data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(finalize_system: () => ());
-    ipc_cmif_interface_define_command!(list_devices: (out_devices: sf::OutPointerBuffer) => (count: u32));
-    ipc_cmif_interface_define_command!(start_detection: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(stop_detection: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(mount: (device_handle: DeviceHandle, model_type: ModelType, mount_target: MountTarget) => ());
-    ipc_cmif_interface_define_command!(unmount: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(flush: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(restore: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(get_tag_info: (device_handle: DeviceHandle, out_tag_info: sf::OutFixedPointerBuffer<TagInfo>) => ());
-    ipc_cmif_interface_define_command!(get_register_info: (device_handle: DeviceHandle, out_register_info: sf::OutFixedPointerBuffer<RegisterInfo>) => ());
-    ipc_cmif_interface_define_command!(get_common_info: (device_handle: DeviceHandle, out_common_info: sf::OutFixedPointerBuffer<CommonInfo>) => ());
-    ipc_cmif_interface_define_command!(get_model_info: (device_handle: DeviceHandle, out_model_info: sf::OutFixedPointerBuffer<ModelInfo>) => ());
-    ipc_cmif_interface_define_command!(attach_activate_event: (device_handle: DeviceHandle) => (activate_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(attach_deactivate_event: (device_handle: DeviceHandle) => (deactivate_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(get_state: () => (state: State));
-    ipc_cmif_interface_define_command!(get_device_state: (device_handle: DeviceHandle) => (device_state: DeviceState));
-    ipc_cmif_interface_define_command!(get_npad_id: (device_handle: DeviceHandle) => (npad_id: u32));
-    ipc_cmif_interface_define_command!(attach_availability_change_event: () => (availability_change_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(format: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(get_admin_info: (device_handle: DeviceHandle, out_admin_info: sf::OutFixedPointerBuffer<AdminInfo>) => ());
-    ipc_cmif_interface_define_command!(get_register_info_private: (device_handle: DeviceHandle, out_register_info_private: sf::OutFixedPointerBuffer<RegisterInfoPrivate>) => ());
-    ipc_cmif_interface_define_command!(set_register_info_private: (device_handle: DeviceHandle, register_info_private: sf::InFixedPointerBuffer<RegisterInfoPrivate>) => ());
-    ipc_cmif_interface_define_command!(delete_register_info: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(delete_application_area: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(exists_application_area: (device_handle: DeviceHandle) => (exists: bool));
-}
-
-pub trait ISystemManager {
-    ipc_cmif_interface_define_command!(create_system_interface: () => (system_interface: mem::Shared<dyn sf::IObject>));
-}
-
-pub trait IDebug {
-    ipc_cmif_interface_define_command!(initialize_debug: (aruid: applet::AppletResourceUserId, process_id: sf::ProcessId, mcu_data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(finalize_debug: () => ());
-    ipc_cmif_interface_define_command!(list_devices: (out_devices: sf::OutPointerBuffer) => (count: u32));
-    ipc_cmif_interface_define_command!(start_detection: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(stop_detection: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(mount: (device_handle: DeviceHandle, model_type: ModelType, mount_target: MountTarget) => ());
-    ipc_cmif_interface_define_command!(unmount: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(open_application_area: (device_handle: DeviceHandle, access_id: AccessId) => ());
-    ipc_cmif_interface_define_command!(get_application_area: (device_handle: DeviceHandle, out_data: sf::OutMapAliasBuffer) => (size: u32));
-    ipc_cmif_interface_define_command!(set_application_area: (device_handle: DeviceHandle, data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(flush: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(restore: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(create_application_area: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(get_tag_info: (device_handle: DeviceHandle, out_tag_info: sf::OutFixedPointerBuffer<TagInfo>) => ());
-    ipc_cmif_interface_define_command!(get_register_info: (device_handle: DeviceHandle, out_register_info: sf::OutFixedPointerBuffer<RegisterInfo>) => ());
-    ipc_cmif_interface_define_command!(get_common_info: (device_handle: DeviceHandle, out_common_info: sf::OutFixedPointerBuffer<CommonInfo>) => ());
-    ipc_cmif_interface_define_command!(get_model_info: (device_handle: DeviceHandle, out_model_info: sf::OutFixedPointerBuffer<ModelInfo>) => ());
-    ipc_cmif_interface_define_command!(attach_activate_event: (device_handle: DeviceHandle) => (activate_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(attach_deactivate_event: (device_handle: DeviceHandle) => (deactivate_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(get_state: () => (state: State));
-    ipc_cmif_interface_define_command!(get_device_state: (device_handle: DeviceHandle) => (device_state: DeviceState));
-    ipc_cmif_interface_define_command!(get_npad_id: (device_handle: DeviceHandle) => (npad_id: u32));
-    ipc_cmif_interface_define_command!(get_application_area_size: (device_handle: DeviceHandle) => (size: u32));
-    ipc_cmif_interface_define_command!(attach_availability_change_event: () => (availability_change_event: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(recreate_application_area: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ());
-    ipc_cmif_interface_define_command!(format: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(get_admin_info: (device_handle: DeviceHandle, out_admin_info: sf::OutFixedPointerBuffer<AdminInfo>) => ());
-    ipc_cmif_interface_define_command!(get_register_info_private: (device_handle: DeviceHandle, out_register_info_private: sf::OutFixedPointerBuffer<RegisterInfoPrivate>) => ());
-    ipc_cmif_interface_define_command!(set_register_info_private: (device_handle: DeviceHandle, register_info_private: sf::InFixedPointerBuffer<RegisterInfoPrivate>) => ());
-    ipc_cmif_interface_define_command!(delete_register_info: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(delete_application_area: (device_handle: DeviceHandle) => ());
-    ipc_cmif_interface_define_command!(exists_application_area: (device_handle: DeviceHandle) => (exists: bool));
-    // TODO: remaining commands
-}
-
-pub trait IDebugManager {
-    ipc_cmif_interface_define_command!(create_debug_interface: () => (debug_interface: mem::Shared<dyn sf::IObject>));
+ipc_sf_define_interface_trait! {
+    trait IUser {
+        initialize [0, version::VersionInterval::all()]: (aruid: applet::AppletResourceUserId, process_id: sf::ProcessId, mcu_data: sf::InMapAliasBuffer) => ();
+        finalize [1, version::VersionInterval::all()]: () => ();
+        list_devices [2, version::VersionInterval::all()]: (out_devices: sf::OutPointerBuffer) => (count: u32);
+        start_detection [3, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        stop_detection [4, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        mount [5, version::VersionInterval::all()]: (device_handle: DeviceHandle, model_type: ModelType, mount_target: MountTarget) => ();
+        unmount [6, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        open_application_area [7, version::VersionInterval::all()]: (device_handle: DeviceHandle, access_id: AccessId) => ();
+        get_application_area [8, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_data: sf::OutMapAliasBuffer) => (size: u32);
+        set_application_area [9, version::VersionInterval::all()]: (device_handle: DeviceHandle, data: sf::InMapAliasBuffer) => ();
+        flush [10, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        restore [11, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        create_application_area [12, version::VersionInterval::all()]: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ();
+        get_tag_info [13, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_tag_info: sf::OutFixedPointerBuffer<TagInfo>) => ();
+        get_register_info [14, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_register_info: sf::OutFixedPointerBuffer<RegisterInfo>) => ();
+        get_common_info [15, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_common_info: sf::OutFixedPointerBuffer<CommonInfo>) => ();
+        get_model_info [16, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_model_info: sf::OutFixedPointerBuffer<ModelInfo>) => ();
+        attach_activate_event [17, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (activate_event: sf::CopyHandle);
+        attach_deactivate_event [18, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (deactivate_event: sf::CopyHandle);
+        get_state [19, version::VersionInterval::all()]: () => (state: State);
+        get_device_state [20, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (device_state: DeviceState);
+        get_npad_id [21, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (npad_id: u32);
+        get_application_area_size [22, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (size: u32);
+        attach_availability_change_event [23, version::VersionInterval::from(version::Version::new(3, 0, 0))]: () => (availability_change_event: sf::CopyHandle);
+        recreate_application_area [24, version::VersionInterval::from(version::Version::new(3, 0, 0))]: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ();
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait IUserManager {
+        create_user_interface [0, version::VersionInterval::all()]: () => (user_interface: mem::Shared<dyn sf::IObject>);
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait ISystem {
+        initialize_system [0, version::VersionInterval::all()]: (aruid: applet::AppletResourceUserId, process_id: sf::ProcessId, mcu_data: sf::InMapAliasBuffer) => ();
+        finalize_system [1, version::VersionInterval::all()]: () => ();
+        list_devices [2, version::VersionInterval::all()]: (out_devices: sf::OutPointerBuffer) => (count: u32);
+        start_detection [3, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        stop_detection [4, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        mount [5, version::VersionInterval::all()]: (device_handle: DeviceHandle, model_type: ModelType, mount_target: MountTarget) => ();
+        unmount [6, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        flush [10, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        restore [11, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        get_tag_info [13, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_tag_info: sf::OutFixedPointerBuffer<TagInfo>) => ();
+        get_register_info [14, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_register_info: sf::OutFixedPointerBuffer<RegisterInfo>) => ();
+        get_common_info [15, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_common_info: sf::OutFixedPointerBuffer<CommonInfo>) => ();
+        get_model_info [16, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_model_info: sf::OutFixedPointerBuffer<ModelInfo>) => ();
+        attach_activate_event [17, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (activate_event: sf::CopyHandle);
+        attach_deactivate_event [18, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (deactivate_event: sf::CopyHandle);
+        get_state [19, version::VersionInterval::all()]: () => (state: State);
+        get_device_state [20, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (device_state: DeviceState);
+        get_npad_id [21, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (npad_id: u32);
+        attach_availability_change_event [23, version::VersionInterval::from(version::Version::new(3, 0, 0))]: () => (availability_change_event: sf::CopyHandle);
+        format [100, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        get_admin_info [101, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_admin_info: sf::OutFixedPointerBuffer<AdminInfo>) => ();
+        get_register_info_private [102, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_register_info_private: sf::OutFixedPointerBuffer<RegisterInfoPrivate>) => ();
+        set_register_info_private [103, version::VersionInterval::all()]: (device_handle: DeviceHandle, register_info_private: sf::InFixedPointerBuffer<RegisterInfoPrivate>) => ();
+        delete_register_info [104, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        delete_application_area [105, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        exists_application_area [106, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (exists: bool);
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait ISystemManager {
+        create_system_interface [0, version::VersionInterval::all()]: () => (system_interface: mem::Shared<dyn sf::IObject>);
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait IDebug {
+        initialize_debug [0, version::VersionInterval::all()]: (aruid: applet::AppletResourceUserId, process_id: sf::ProcessId, mcu_data: sf::InMapAliasBuffer) => ();
+        finalize_debug [1, version::VersionInterval::all()]: () => ();
+        list_devices [2, version::VersionInterval::all()]: (out_devices: sf::OutPointerBuffer) => (count: u32);
+        start_detection [3, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        stop_detection [4, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        mount [5, version::VersionInterval::all()]: (device_handle: DeviceHandle, model_type: ModelType, mount_target: MountTarget) => ();
+        unmount [6, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        open_application_area [7, version::VersionInterval::all()]: (device_handle: DeviceHandle, access_id: AccessId) => ();
+        get_application_area [8, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_data: sf::OutMapAliasBuffer) => (size: u32);
+        set_application_area [9, version::VersionInterval::all()]: (device_handle: DeviceHandle, data: sf::InMapAliasBuffer) => ();
+        flush [10, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        restore [11, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        create_application_area [12, version::VersionInterval::all()]: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ();
+        get_tag_info [13, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_tag_info: sf::OutFixedPointerBuffer<TagInfo>) => ();
+        get_register_info [14, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_register_info: sf::OutFixedPointerBuffer<RegisterInfo>) => ();
+        get_common_info [15, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_common_info: sf::OutFixedPointerBuffer<CommonInfo>) => ();
+        get_model_info [16, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_model_info: sf::OutFixedPointerBuffer<ModelInfo>) => ();
+        attach_activate_event [17, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (activate_event: sf::CopyHandle);
+        attach_deactivate_event [18, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (deactivate_event: sf::CopyHandle);
+        get_state [19, version::VersionInterval::all()]: () => (state: State);
+        get_device_state [20, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (device_state: DeviceState);
+        get_npad_id [21, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (npad_id: u32);
+        get_application_area_size [22, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (size: u32);
+        attach_availability_change_event [23, version::VersionInterval::from(version::Version::new(3, 0, 0))]: () => (availability_change_event: sf::CopyHandle);
+        recreate_application_area [24, version::VersionInterval::from(version::Version::new(3, 0, 0))]: (device_handle: DeviceHandle, access_id: AccessId, data: sf::InMapAliasBuffer) => ();
+        format [100, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        get_admin_info [101, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_admin_info: sf::OutFixedPointerBuffer<AdminInfo>) => ();
+        get_register_info_private [102, version::VersionInterval::all()]: (device_handle: DeviceHandle, out_register_info_private: sf::OutFixedPointerBuffer<RegisterInfoPrivate>) => ();
+        set_register_info_private [103, version::VersionInterval::all()]: (device_handle: DeviceHandle, register_info_private: sf::InFixedPointerBuffer<RegisterInfoPrivate>) => ();
+        delete_register_info [104, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        delete_application_area [105, version::VersionInterval::all()]: (device_handle: DeviceHandle) => ();
+        exists_application_area [106, version::VersionInterval::all()]: (device_handle: DeviceHandle) => (exists: bool);
+        // TODO: remaining commands
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait IDebugManager {
+        create_debug_interface [0, version::VersionInterval::all()]: () => (debug_interface: mem::Shared<dyn sf::IObject>);
+    }
 }
\ No newline at end of file
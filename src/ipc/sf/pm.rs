@@ -1,10 +1,15 @@
 use crate::result::*;
+use crate::version;
 // use crate::ipc::sf;
 
-pub trait IInformationInterface {
-    ipc_cmif_interface_define_command!(get_program_id: (process_id: u64) => (program_id: u64));
+ipc_sf_define_interface_trait! {
+    trait IInformationInterface {
+        get_program_id [0, version::VersionInterval::all()]: (process_id: u64) => (program_id: u64);
+    }
 }
 
-pub trait IDebugMonitorInterface {
-    ipc_cmif_interface_define_command!(get_application_process_id: () => (process_id: u64));
+ipc_sf_define_interface_trait! {
+    trait IDebugMonitorInterface {
+        get_application_process_id [5, version::VersionInterval::all()]: () => (process_id: u64);
+    }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::mem;
+use crate::version;
+use crate::service;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(u32)]
@@ -19,14 +21,18 @@ pub enum State {
     ReadyShutdown
 }
 
-pub trait IPmModule {
-    ipc_cmif_interface_define_command!(initialize: (id: ModuleId, dependencies: sf::InMapAliasBuffer) => (event_handle: sf::CopyHandle));
-    ipc_cmif_interface_define_command!(get_request: () => (state: State, flags: u32));
-    ipc_cmif_interface_define_command!(acknowledge: () => ());
-    ipc_cmif_interface_define_command!(finalize: () => ());
-    ipc_cmif_interface_define_command!(acknowledge_ex: (state: State) => ());
+ipc_sf_define_interface_trait! {
+    trait IPmModule {
+        initialize [0, version::VersionInterval::all()]: (id: ModuleId, dependencies: sf::InMapAliasBuffer) => (event_handle: sf::CopyHandle);
+        get_request [1, version::VersionInterval::all()]: () => (state: State, flags: u32);
+        acknowledge [2, version::VersionInterval::all()]: () => ();
+        finalize [3, version::VersionInterval::all()]: () => ();
+        acknowledge_ex [4, version::VersionInterval::all()]: (state: State) => ();
+    }
 }
 
-pub trait IPmService {
-    ipc_cmif_interface_define_command!(get_pm_module: () => (pm_module: mem::Shared<dyn sf::IObject>));
+ipc_sf_define_interface_trait! {
+    trait IPmService {
+        get_pm_module [0, version::VersionInterval::all()]: () => (pm_module: mem::Shared<dyn sf::IObject> as service::psc::PmModule);
+    }
 }
\ No newline at end of file
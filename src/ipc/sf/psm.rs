@@ -1,6 +1,9 @@
 use crate::result::*;
+use crate::version;
 // use crate::ipc::sf;
 
-pub trait IPsmServer {
-    ipc_cmif_interface_define_command!(get_battery_charge_percentage: () => (charge: u32));
+ipc_sf_define_interface_trait! {
+    trait IPsmServer {
+        get_battery_charge_percentage [0, version::VersionInterval::all()]: () => (charge: u32);
+    }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::util;
+use crate::version;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(C)]
@@ -20,7 +21,74 @@ pub struct FirmwareVersion {
 }
 const_assert!(core::mem::size_of::<FirmwareVersion>() == 0x100);
 
-pub trait ISystemSettingsServer {
-    ipc_cmif_interface_define_command!(get_firmware_version: (out_version: sf::OutFixedPointerBuffer<FirmwareVersion>) => ());
-    ipc_cmif_interface_define_command!(get_firmware_version_2: (out_version: sf::OutFixedPointerBuffer<FirmwareVersion>) => ());
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum Language {
+    Japanese = 0,
+    AmericanEnglish = 1,
+    French = 2,
+    German = 3,
+    Italian = 4,
+    Spanish = 5,
+    Chinese = 6,
+    Korean = 7,
+    Dutch = 8,
+    Portuguese = 9,
+    Russian = 10,
+    Taiwanese = 11,
+    BritishEnglish = 12,
+    CanadianFrench = 13,
+    LatinAmericanSpanish = 14,
+    SimplifiedChinese = 15,
+    TraditionalChinese = 16,
+    BrazilianPortuguese = 17
+}
+
+// Language codes are just (NUL-padded) strings like "en-US" stored as a u64
+pub type LanguageCode = util::CString<8>;
+
+impl Language {
+    pub const ALL: [Language; 18] = [Self::Japanese, Self::AmericanEnglish, Self::French, Self::German, Self::Italian, Self::Spanish, Self::Chinese, Self::Korean, Self::Dutch, Self::Portuguese, Self::Russian, Self::Taiwanese, Self::BritishEnglish, Self::CanadianFrench, Self::LatinAmericanSpanish, Self::SimplifiedChinese, Self::TraditionalChinese, Self::BrazilianPortuguese];
+
+    pub const fn get_code_str(self) -> &'static str {
+        match self {
+            Self::Japanese => "ja",
+            Self::AmericanEnglish => "en-US",
+            Self::French => "fr",
+            Self::German => "de",
+            Self::Italian => "it",
+            Self::Spanish => "es",
+            Self::Chinese => "zh-CN",
+            Self::Korean => "ko",
+            Self::Dutch => "nl",
+            Self::Portuguese => "pt",
+            Self::Russian => "ru",
+            Self::Taiwanese => "zh-TW",
+            Self::BritishEnglish => "en-GB",
+            Self::CanadianFrench => "fr-CA",
+            Self::LatinAmericanSpanish => "es-419",
+            Self::SimplifiedChinese => "zh-Hans",
+            Self::TraditionalChinese => "zh-Hant",
+            Self::BrazilianPortuguese => "pt-BR"
+        }
+    }
+
+    pub fn get_code(self) -> Result<LanguageCode> {
+        LanguageCode::from_str(self.get_code_str())
+    }
+
+    pub fn from_code_str(code: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|language| language.get_code_str() == code)
+    }
+
+    pub fn from_code(code: &LanguageCode) -> Option<Self> {
+        Self::from_code_str(code.get_str().ok()?)
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait ISystemSettingsServer {
+        get_firmware_version [3, version::VersionInterval::all()]: (out_version: sf::OutFixedPointerBuffer<FirmwareVersion>) => ();
+        get_firmware_version_2 [4, version::VersionInterval::all()]: (out_version: sf::OutFixedPointerBuffer<FirmwareVersion>) => ();
+    }
 }
\ No newline at end of file
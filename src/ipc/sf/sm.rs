@@ -1,6 +1,7 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::input;
+use crate::version;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(C)]
@@ -38,19 +39,21 @@ pub struct MitmProcessInfo {
     pub override_flags: u64
 }
 
-pub trait IUserInterface {
-    ipc_cmif_tipc_interface_define_command!(register_client: (process_id: sf::ProcessId) => ());
-    ipc_cmif_tipc_interface_define_command!(get_service_handle: (name: ServiceName) => (service_handle: sf::MoveHandle));
-    ipc_cmif_tipc_interface_define_command!(register_service: (name: ServiceName, is_light: bool, max_sessions: i32) => (port_handle: sf::MoveHandle));
-    ipc_cmif_tipc_interface_define_command!(unregister_service: (name: ServiceName) => ());
-    ipc_cmif_tipc_interface_define_command!(detach_client: (process_id: sf::ProcessId) => ());
-    ipc_cmif_tipc_interface_define_command!(atmosphere_install_mitm: (name: ServiceName) => (port_handle: sf::MoveHandle, query_handle: sf::MoveHandle));
-    ipc_cmif_tipc_interface_define_command!(atmosphere_uninstall_mitm: (name: ServiceName) => ());
-    ipc_cmif_tipc_interface_define_command!(atmosphere_acknowledge_mitm_session: (name: ServiceName) => (info: MitmProcessInfo, session_handle: sf::MoveHandle));
-    ipc_cmif_tipc_interface_define_command!(atmosphere_has_mitm: (name: ServiceName) => (has: bool));
-    ipc_cmif_tipc_interface_define_command!(atmosphere_wait_mitm: (name: ServiceName) => ());
-    ipc_cmif_tipc_interface_define_command!(atmosphere_declare_future_mitm: (name: ServiceName) => ());
-    ipc_cmif_tipc_interface_define_command!(atmosphere_clear_future_mitm: (name: ServiceName) => ());
-    ipc_cmif_tipc_interface_define_command!(atmosphere_has_service: (name: ServiceName) => (has: bool));
-    ipc_cmif_tipc_interface_define_command!(atmosphere_wait_service: (name: ServiceName) => ());
+ipc_sf_define_interface_trait! {
+    trait IUserInterface [cmif_tipc] {
+        register_client [0, version::VersionInterval::all()]: (process_id: sf::ProcessId) => ();
+        get_service_handle [1, version::VersionInterval::all()]: (name: ServiceName) => (service_handle: sf::MoveHandle);
+        register_service [2, version::VersionInterval::all()]: (name: ServiceName, is_light: bool, max_sessions: i32) => (port_handle: sf::MoveHandle);
+        unregister_service [3, version::VersionInterval::all()]: (name: ServiceName) => ();
+        detach_client [4, version::VersionInterval::all()]: (process_id: sf::ProcessId) => ();
+        atmosphere_install_mitm [65000, version::VersionInterval::all()]: (name: ServiceName) => (port_handle: sf::MoveHandle, query_handle: sf::MoveHandle);
+        atmosphere_uninstall_mitm [65001, version::VersionInterval::all()]: (name: ServiceName) => ();
+        atmosphere_acknowledge_mitm_session [65003, version::VersionInterval::all()]: (name: ServiceName) => (info: MitmProcessInfo, session_handle: sf::MoveHandle);
+        atmosphere_has_mitm [65004, version::VersionInterval::all()]: (name: ServiceName) => (has: bool);
+        atmosphere_wait_mitm [65005, version::VersionInterval::all()]: (name: ServiceName) => ();
+        atmosphere_declare_future_mitm [65006, version::VersionInterval::all()]: (name: ServiceName) => ();
+        atmosphere_clear_future_mitm [65007, version::VersionInterval::all()]: (name: ServiceName) => ();
+        atmosphere_has_service [65100, version::VersionInterval::all()]: (name: ServiceName) => (has: bool);
+        atmosphere_wait_service [65101, version::VersionInterval::all()]: (name: ServiceName) => ();
+    }
 }
\ No newline at end of file
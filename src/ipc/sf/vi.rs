@@ -3,6 +3,8 @@ use crate::ipc::sf;
 use crate::mem;
 use crate::util;
 use crate::ipc::sf::applet;
+use crate::version;
+use crate::service;
 
 pub type DisplayName = util::CString<0x40>;
 
@@ -24,32 +26,52 @@ pub enum DisplayServiceMode {
     Privileged = 1
 }
 
-pub trait IManagerDisplayService {
-    ipc_cmif_interface_define_command!(create_managed_layer: (flags: LayerFlags, display_id: DisplayId, aruid: applet::AppletResourceUserId) => (id: LayerId));
-    ipc_cmif_interface_define_command!(destroy_managed_layer: (id: LayerId) => ());
+ipc_sf_define_interface_trait! {
+    trait IManagerDisplayService {
+        create_managed_layer [2010, version::VersionInterval::all()]: (flags: LayerFlags, display_id: DisplayId, aruid: applet::AppletResourceUserId) => (id: LayerId);
+        destroy_managed_layer [2011, version::VersionInterval::all()]: (id: LayerId) => ();
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait ISystemDisplayService {
+        get_z_order_count_min [1200, version::VersionInterval::all()]: (display_id: DisplayId) => (z: i64);
+        get_z_order_count_max [1202, version::VersionInterval::all()]: (display_id: DisplayId) => (z: i64);
+        set_layer_position [2201, version::VersionInterval::all()]: (x: f32, y: f32, id: LayerId) => ();
+        set_layer_size [2203, version::VersionInterval::all()]: (id: LayerId, width: u64, height: u64) => ();
+        set_layer_z [2205, version::VersionInterval::all()]: (id: LayerId, z: i64) => ();
+        set_layer_visibility [2207, version::VersionInterval::all()]: (visible: bool, id: LayerId) => ();
+    }
+}
+
+ipc_sf_define_interface_trait! {
+    trait IApplicationDisplayService {
+        get_relay_service [100, version::VersionInterval::all()]: () => (relay_service: mem::Shared<dyn sf::IObject> as service::dispdrv::HOSBinderDriver);
+        get_system_display_service [101, version::VersionInterval::all()]: () => (relay_service: mem::Shared<dyn sf::IObject> as service::vi::SystemDisplayService);
+        get_manager_display_service [102, version::VersionInterval::all()]: () => (relay_service: mem::Shared<dyn sf::IObject> as service::vi::ManagerDisplayService);
+        open_display [1010, version::VersionInterval::all()]: (name: DisplayName) => (id: DisplayId);
+        close_display [1020, version::VersionInterval::all()]: (id: DisplayId) => ();
+        open_layer [2020, version::VersionInterval::all()]: (name: DisplayName, id: LayerId, aruid: sf::ProcessId, out_native_window: sf::OutMapAliasBuffer) => (native_window_size: usize);
+        create_stray_layer [2030, version::VersionInterval::all()]: (flags: LayerFlags, display_id: DisplayId, out_native_window: sf::OutMapAliasBuffer) => (id: LayerId, native_window_size: usize);
+        destroy_stray_layer [2031, version::VersionInterval::all()]: (id: LayerId) => ();
+        get_display_vsync_event [5202, version::VersionInterval::all()]: (id: DisplayId) => (event_handle: sf::CopyHandle);
+    }
 }
 
-pub trait ISystemDisplayService {
-    ipc_cmif_interface_define_command!(get_z_order_count_min: (display_id: DisplayId) => (z: i64));
-    ipc_cmif_interface_define_command!(get_z_order_count_max: (display_id: DisplayId) => (z: i64));
-    ipc_cmif_interface_define_command!(set_layer_position: (x: f32, y: f32, id: LayerId) => ());
-    ipc_cmif_interface_define_command!(set_layer_size: (id: LayerId, width: u64, height: u64) => ());
-    ipc_cmif_interface_define_command!(set_layer_z: (id: LayerId, z: i64) => ());
-    ipc_cmif_interface_define_command!(set_layer_visibility: (visible: bool, id: LayerId) => ());
+ipc_sf_define_interface_trait! {
+    trait ISystemRootService {
+        get_display_service [1, version::VersionInterval::all()]: (mode: DisplayServiceMode) => (display_service: mem::Shared<dyn sf::IObject> as service::vi::ApplicationDisplayService);
+    }
 }
 
-pub trait IApplicationDisplayService {
-    ipc_cmif_interface_define_command!(get_relay_service: () => (relay_service: mem::Shared<dyn sf::IObject>));
-    ipc_cmif_interface_define_command!(get_system_display_service: () => (relay_service: mem::Shared<dyn sf::IObject>));
-    ipc_cmif_interface_define_command!(get_manager_display_service: () => (relay_service: mem::Shared<dyn sf::IObject>));
-    ipc_cmif_interface_define_command!(open_display: (name: DisplayName) => (id: DisplayId));
-    ipc_cmif_interface_define_command!(close_display: (id: DisplayId) => ());
-    ipc_cmif_interface_define_command!(open_layer: (name: DisplayName, id: LayerId, aruid: sf::ProcessId, out_native_window: sf::OutMapAliasBuffer) => (native_window_size: usize));
-    ipc_cmif_interface_define_command!(create_stray_layer: (flags: LayerFlags, display_id: DisplayId, out_native_window: sf::OutMapAliasBuffer) => (id: LayerId, native_window_size: usize));
-    ipc_cmif_interface_define_command!(destroy_stray_layer: (id: LayerId) => ());
-    ipc_cmif_interface_define_command!(get_display_vsync_event: (id: DisplayId) => (event_handle: sf::CopyHandle));
+ipc_sf_define_interface_trait! {
+    trait IManagerRootService {
+        get_display_service [2, version::VersionInterval::all()]: (mode: DisplayServiceMode) => (display_service: mem::Shared<dyn sf::IObject> as service::vi::ApplicationDisplayService);
+    }
 }
 
+// Each root service exposes the display service through a different command ID, this lets code (like gpu contexts) work with any of them
+
 pub trait IRootService {
-    ipc_cmif_interface_define_command!(get_display_service: (mode: DisplayServiceMode) => (display_service: mem::Shared<dyn sf::IObject>));
+    fn get_display_service(&mut self, mode: DisplayServiceMode) -> Result<mem::Shared<dyn sf::IObject>>;
 }
\ No newline at end of file
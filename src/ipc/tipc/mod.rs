@@ -4,7 +4,8 @@ use super::*;
 #[repr(u32)]
 pub enum CommandType {
     Invalid = 0,
-    CloseSession = 15
+    CloseSession = 15,
+    Request = 16 // Request IDs are sent added to this value
 }
 
 pub mod client;
@@ -1,46 +1,42 @@
 use crate::result::*;
+use crate::results;
 use super::*;
 use core::mem as cmem;
 
-#[inline(always)]
-pub fn read_command_from_msg_buffer(ctx: &mut CommandContext) -> u32 {
-    unsafe {
-        let mut ipc_buf = get_msg_buffer();
+// Same as the CMIF one, requests are parsed within the bounds of the given message
+pub fn read_command_from_msg(ctx: &mut CommandContext, msg: &[u8]) -> Result<u32> {
+    let mut reader = BoundedReader::new(msg);
 
-        let command_header = ipc_buf as *mut CommandHeader;
-        ipc_buf = command_header.offset(1) as *mut u8;
+    let command_header: CommandHeader = reader.read()?;
+    let command_type = command_header.get_command_type();
+    let data_size = command_header.get_data_word_count() * cmem::size_of::<u32>() as u32;
+    ctx.in_params.data_size = data_size;
+    ctx.in_params.data_words_size = data_size;
 
-        let command_type = (*command_header).get_command_type();
-        let data_size = (*command_header).get_data_word_count() * cmem::size_of::<u32>() as u32;
-        ctx.in_params.data_size = data_size;
+    if command_header.get_has_special_header() {
+        let special_header: CommandSpecialHeader = reader.read()?;
 
-        if (*command_header).get_has_special_header() {
-            let special_header = ipc_buf as *mut CommandSpecialHeader;
-            ipc_buf = special_header.offset(1) as *mut u8;
+        ctx.in_params.send_process_id = special_header.get_send_process_id();
+        if ctx.in_params.send_process_id {
+            ctx.in_params.process_id = reader.read()?;
+        }
 
-            ctx.in_params.send_process_id = (*special_header).get_send_process_id();
-            if ctx.in_params.send_process_id {
-                let process_id_ptr = ipc_buf as *mut u64;
-                ctx.in_params.process_id = *process_id_ptr;
-                ipc_buf = process_id_ptr.offset(1) as *mut u8;
-            }
+        reader.read_array(special_header.get_copy_handle_count(), &mut ctx.in_params.copy_handles, results::lib::ipc::ResultCopyHandlesFull::make())?;
+        reader.read_array(special_header.get_move_handle_count(), &mut ctx.in_params.move_handles, results::lib::ipc::ResultMoveHandlesFull::make())?;
+    }
 
-            let copy_handle_count = (*special_header).get_copy_handle_count();
-            ipc_buf = read_array_from_buffer(ipc_buf, copy_handle_count, &mut ctx.in_params.copy_handles);
-            let move_handle_count = (*special_header).get_move_handle_count();
-            ipc_buf = read_array_from_buffer(ipc_buf, move_handle_count, &mut ctx.in_params.move_handles);
-        }
+    reader.read_array(command_header.get_send_buffer_count(), &mut ctx.send_buffers, results::lib::ipc::ResultInvalidSendBufferCount::make())?;
+    reader.read_array(command_header.get_receive_buffer_count(), &mut ctx.receive_buffers, results::lib::ipc::ResultInvalidReceiveBufferCount::make())?;
+    reader.read_array(command_header.get_exchange_buffer_count(), &mut ctx.exchange_buffers, results::lib::ipc::ResultInvalidExchangeBufferCount::make())?;
 
-        let send_buffer_count = (*command_header).get_send_buffer_count();
-        ipc_buf = read_array_from_buffer(ipc_buf, send_buffer_count, &mut ctx.send_buffers);
-        let receive_buffer_count = (*command_header).get_receive_buffer_count();
-        ipc_buf = read_array_from_buffer(ipc_buf, receive_buffer_count, &mut ctx.receive_buffers);
-        let exchange_buffer_count = (*command_header).get_exchange_buffer_count();
-        ipc_buf = read_array_from_buffer(ipc_buf, exchange_buffer_count, &mut ctx.exchange_buffers);
+    ctx.in_params.data_words_offset = reader.get_ptr();
+    reader.skip(data_size as usize)?;
+    Ok(command_type)
+}
 
-        ctx.in_params.data_words_offset = ipc_buf;
-        command_type
-    }
+#[inline(always)]
+pub fn read_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<u32> {
+    read_command_from_msg(ctx, get_msg_buffer_slice())
 }
 
 #[inline(always)]
@@ -75,25 +71,28 @@ pub fn write_command_response_on_msg_buffer(ctx: &mut CommandContext, command_ty
     }
 }
 
-#[inline(always)]
-pub fn read_request_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<()> {
-    let ipc_buf = get_msg_buffer();
-    let data_offset = get_aligned_data_offset(ctx.in_params.data_words_offset, ipc_buf);
+pub fn read_request_command_from_msg(ctx: &mut CommandContext, msg: &[u8]) -> Result<u32> {
+    let command_header: CommandHeader = BoundedReader::new(msg).read()?;
+    let command_type = command_header.get_command_type();
+    result_return_if!(command_type < CommandType::Request as u32, results::lib::ipc::ResultInvalidCommandType);
+
+    // TIPC has no padding or data header, raw data directly follows the descriptors
+    ctx.in_params.data_offset = ctx.in_params.data_words_offset;
+    Ok(command_type - CommandType::Request as u32)
+}
 
-    ctx.in_params.data_offset = data_offset;
-    Ok(())
+#[inline(always)]
+pub fn read_request_command_from_msg_buffer(ctx: &mut CommandContext) -> Result<u32> {
+    read_request_command_from_msg(ctx, get_msg_buffer_slice())
 }
 
 #[inline(always)]
 pub fn write_request_command_response_on_msg_buffer(ctx: &mut CommandContext, result: ResultCode, request_type: u32) {
     unsafe {
-        let ipc_buf = get_msg_buffer();
         let data_size = cmem::size_of::<ResultCode>() as u32 + ctx.out_params.data_size;
-        // data_size = (data_size + 1) & !1;
 
         write_command_response_on_msg_buffer(ctx, request_type, data_size);
-        let data_offset = get_aligned_data_offset(ctx.out_params.data_words_offset, ipc_buf);
-        let rc_ref = data_offset as *mut ResultCode;
+        let rc_ref = ctx.out_params.data_words_offset as *mut ResultCode;
         *rc_ref = result;
 
         ctx.out_params.data_offset = rc_ref.offset(1) as *mut u8;
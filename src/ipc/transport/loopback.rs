@@ -0,0 +1,470 @@
+use crate::result::*;
+use crate::results;
+use crate::svc;
+use crate::mem;
+use crate::ipc::*;
+use crate::ipc::server;
+use super::*;
+use alloc::vec::Vec;
+use core::mem as cmem;
+
+// In-process transport: client requests are directly dispatched to the sessions of a ServerManager owned by the transport,
+// emulating what the kernel does in between (message copying, process ID filling, pointer buffer copying)
+// Since everything lives in the same address space, buffer descriptors and handles are passed as-is
+
+const LOOPBACK_HANDLE_BASE: svc::Handle = 0x1000;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct LoopbackSession {
+    client_handle: svc::Handle,
+    server_handle: svc::Handle,
+    client_open: bool,
+    server_open: bool
+}
+
+struct LoopbackState {
+    msg_buffer: [u8; MSG_BUFFER_SIZE],
+    sessions: Vec<LoopbackSession>,
+    next_handle: svc::Handle,
+    pending_requests: Vec<[u8; MSG_BUFFER_SIZE]>,
+    pending_replies: Vec<[u8; MSG_BUFFER_SIZE]>,
+    client_process_id: u64
+}
+
+impl LoopbackState {
+    fn new() -> Self {
+        Self { msg_buffer: [0; MSG_BUFFER_SIZE], sessions: Vec::new(), next_handle: LOOPBACK_HANDLE_BASE, pending_requests: Vec::new(), pending_replies: Vec::new(), client_process_id: 0 }
+    }
+
+    fn allocate_handle(&mut self) -> svc::Handle {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        handle
+    }
+
+    fn find_client_session(&self, client_handle: svc::Handle) -> Result<LoopbackSession> {
+        for session in &self.sessions {
+            if session.client_open && (session.client_handle == client_handle) {
+                return Ok(*session);
+            }
+        }
+
+        Err(results::os::ResultInvalidHandle::make())
+    }
+}
+
+#[inline(always)]
+fn read_msg_value<T: Copy>(msg: &[u8; MSG_BUFFER_SIZE], offset: usize) -> Option<T> {
+    if (offset + cmem::size_of::<T>()) > MSG_BUFFER_SIZE {
+        return None;
+    }
+    unsafe {
+        Some((msg.as_ptr().add(offset) as *const T).read_unaligned())
+    }
+}
+
+struct StaticLayout {
+    process_id_offset: Option<usize>,
+    send_statics_offset: usize,
+    send_static_count: u32,
+    receive_statics_offset: usize,
+    receive_static_count: u32
+}
+
+fn read_static_layout(msg: &[u8; MSG_BUFFER_SIZE]) -> Option<StaticLayout> {
+    let header: CommandHeader = read_msg_value(msg, 0)?;
+    let mut offset = cmem::size_of::<CommandHeader>();
+
+    let mut process_id_offset: Option<usize> = None;
+    if header.get_has_special_header() {
+        let special_header: CommandSpecialHeader = read_msg_value(msg, offset)?;
+        offset += cmem::size_of::<CommandSpecialHeader>();
+        if special_header.get_send_process_id() {
+            process_id_offset = Some(offset);
+            offset += cmem::size_of::<u64>();
+        }
+        offset += cmem::size_of::<svc::Handle>() * (special_header.get_copy_handle_count() + special_header.get_move_handle_count()) as usize;
+    }
+
+    let send_statics_offset = offset;
+    let send_static_count = header.get_send_static_count();
+    offset += cmem::size_of::<SendStaticDescriptor>() * send_static_count as usize;
+    offset += cmem::size_of::<BufferDescriptor>() * (header.get_send_buffer_count() + header.get_receive_buffer_count() + header.get_exchange_buffer_count()) as usize;
+    offset += cmem::size_of::<u32>() * header.get_data_word_count() as usize;
+
+    // A count of 0xFF means a single receive static covering the whole buffer
+    let receive_static_count = match header.get_receive_static_count() {
+        0xFF => 1,
+        count => count
+    };
+
+    Some(StaticLayout { process_id_offset, send_statics_offset, send_static_count, receive_statics_offset: offset, receive_static_count })
+}
+
+fn copy_reply_statics(request: &[u8; MSG_BUFFER_SIZE], reply: &[u8; MSG_BUFFER_SIZE]) {
+    let (request_layout, reply_layout) = match (read_static_layout(request), read_static_layout(reply)) {
+        (Some(request_layout), Some(reply_layout)) => (request_layout, reply_layout),
+        _ => return
+    };
+
+    for i in 0..reply_layout.send_static_count as usize {
+        let send_static: SendStaticDescriptor = match read_msg_value(reply, reply_layout.send_statics_offset + i * cmem::size_of::<SendStaticDescriptor>()) {
+            Some(send_static) => send_static,
+            None => return
+        };
+
+        let index = send_static.get_index();
+        if index >= request_layout.receive_static_count {
+            continue;
+        }
+
+        let receive_static: ReceiveStaticDescriptor = match read_msg_value(request, request_layout.receive_statics_offset + index as usize * cmem::size_of::<ReceiveStaticDescriptor>()) {
+            Some(receive_static) => receive_static,
+            None => return
+        };
+
+        let copy_size = core::cmp::min(send_static.get_size(), receive_static.get_size());
+        if (copy_size > 0) && !send_static.get_address().is_null() && !receive_static.get_address().is_null() {
+            unsafe {
+                core::ptr::copy(send_static.get_address(), receive_static.get_address(), copy_size);
+            }
+        }
+    }
+}
+
+// Loopback servers are meant for a few sessions at a time (tests, fuzzing...)
+pub const MAX_SERVER_HOLDER_COUNT: usize = 0x10;
+pub const MAX_DOMAIN_OBJECT_COUNT: usize = 0x10;
+
+pub type LoopbackServerManager<const P: usize> = server::ServerManager<P, MAX_SERVER_HOLDER_COUNT, MAX_DOMAIN_OBJECT_COUNT>;
+
+pub struct LoopbackTransport<const P: usize> {
+    server_manager: mem::Shared<LoopbackServerManager<P>>,
+    // Note: the state is kept separately since it's re-entrantly accessed (through the global transport) while the server manager processes requests
+    state: mem::Shared<LoopbackState>
+}
+
+impl<const P: usize> LoopbackTransport<P> {
+    pub fn new() -> Result<Self> {
+        Ok(Self { server_manager: mem::Shared::new(server::ServerManager::new()?), state: mem::Shared::new(LoopbackState::new()) })
+    }
+
+    pub fn get_server_manager(&self) -> mem::Shared<LoopbackServerManager<P>> {
+        self.server_manager.clone()
+    }
+
+    pub fn set_client_process_id(&mut self, process_id: u64) {
+        self.state.get().client_process_id = process_id;
+    }
+
+    pub fn connect<S: server::IServerObject + 'static>(&mut self, protocol: CommandProtocol) -> Result<ObjectInfo> {
+        let (server_handle, client_handle) = self.create_session()?;
+        self.server_manager.get().register_session::<S>(server_handle, protocol)?;
+        Ok(ObjectInfo::from_handle_with_protocol(client_handle, protocol))
+    }
+}
+
+impl<const P: usize> Transport for LoopbackTransport<P> {
+    fn get_msg_buffer(&mut self) -> *mut u8 {
+        self.state.get().msg_buffer.as_mut_ptr()
+    }
+
+    fn send_sync_request(&mut self, handle: svc::Handle) -> Result<()> {
+        let session = self.state.get().find_client_session(handle)?;
+        result_return_unless!(session.server_open, results::os::ResultSessionClosed);
+
+        let mut request = self.state.get().msg_buffer;
+        if let Some(layout) = read_static_layout(&request) {
+            if let Some(process_id_offset) = layout.process_id_offset {
+                let process_id = self.state.get().client_process_id;
+                request[process_id_offset..process_id_offset + cmem::size_of::<u64>()].copy_from_slice(&process_id.to_le_bytes());
+            }
+        }
+
+        // Nested requests (made by the server while handling this one) push/pop their own messages on top of these
+        self.state.get().pending_requests.push(request);
+        let reply_count = self.state.get().pending_replies.len();
+        let rc = self.server_manager.get().process_signaled_handle(session.server_handle);
+        self.state.get().pending_requests.pop();
+
+        let reply = match self.state.get().pending_replies.len() > reply_count {
+            true => self.state.get().pending_replies.pop(),
+            false => None
+        };
+        rc?;
+
+        match reply {
+            Some(reply) => {
+                copy_reply_statics(&request, &reply);
+                self.state.get().msg_buffer = reply;
+                Ok(())
+            },
+            None => Err(results::os::ResultSessionClosed::make())
+        }
+    }
+
+    fn reply_and_receive(&mut self, handles: &[svc::Handle], reply_target: svc::Handle, _timeout: i64) -> Result<usize> {
+        let state = self.state.get();
+        if reply_target != svc::INVALID_HANDLE {
+            // Deliver the reply to the client, like the kernel would (timing out afterwards, since there is nothing else to receive)
+            state.pending_replies.push(state.msg_buffer);
+            return Err(results::os::ResultTimeout::make());
+        }
+
+        result_return_if!(handles.is_empty(), results::os::ResultTimeout);
+        match state.pending_requests.last() {
+            Some(request) => {
+                state.msg_buffer = *request;
+                Ok(0)
+            },
+            None => Err(results::os::ResultTimeout::make())
+        }
+    }
+
+    fn create_session(&mut self) -> Result<(svc::Handle, svc::Handle)> {
+        let state = self.state.get();
+        let server_handle = state.allocate_handle();
+        let client_handle = state.allocate_handle();
+        state.sessions.push(LoopbackSession { client_handle, server_handle, client_open: true, server_open: true });
+        Ok((server_handle, client_handle))
+    }
+
+    fn close_handle(&mut self, handle: svc::Handle) -> Result<()> {
+        let state = self.state.get();
+        let mut handle_found = false;
+        for session in state.sessions.iter_mut() {
+            if session.client_open && (session.client_handle == handle) {
+                session.client_open = false;
+                handle_found = true;
+            }
+            else if session.server_open && (session.server_handle == handle) {
+                session.server_open = false;
+                handle_found = true;
+            }
+        }
+        state.sessions.retain(|session| session.client_open || session.server_open);
+
+        // Any other handle is a regular kernel handle (events, shared memory...) which got sent through loopback sessions
+        match handle_found {
+            true => Ok(()),
+            false => svc::close_handle(handle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::result::*;
+    use crate::mem;
+    use crate::version;
+    use crate::service;
+    use crate::service::IClientObject;
+    use crate::ipc::sf;
+    use crate::ipc::sf::IObject;
+    use crate::ipc::server;
+    use crate::ipc::*;
+    use super::*;
+
+    ipc_sf_define_interface_trait! {
+        trait ICounter [cmif_tipc] {
+            add [0, version::VersionInterval::all()]: (value: u32) => (total: u32);
+            open_counter [1, version::VersionInterval::all()]: () => (counter: mem::Shared<dyn sf::IObject>);
+            crash [2, version::VersionInterval::all()]: () => ();
+            merge [3, version::VersionInterval::all()]: (counter: mem::Shared<dyn sf::IObject>) => (total: u32);
+            reset [4, version::VersionInterval::from(version::Version::new(99, 0, 0))]: () => ();
+        }
+    }
+
+    pub struct Counter {
+        session: sf::Session,
+        total: u32
+    }
+
+    impl ICounterServer for Counter {
+        fn add(&mut self, value: u32) -> Result<u32> {
+            self.total += value;
+            Ok(self.total)
+        }
+
+        fn open_counter(&mut self) -> Result<mem::Shared<dyn sf::IObject>> {
+            Ok(mem::Shared::new(<Self as server::IServerObject>::new()))
+        }
+
+        fn crash(&mut self) -> Result<()> {
+            panic!("Counter crashed");
+        }
+
+        fn merge(&mut self, counter: mem::Shared<dyn sf::IObject>) -> Result<u32> {
+            let counter = server::get_incoming_object::<Counter>(&counter)?;
+            self.total += counter.get().total;
+            Ok(self.total)
+        }
+
+        fn reset(&mut self) -> Result<()> {
+            self.total = 0;
+            Ok(())
+        }
+    }
+
+    impl sf::IObject for Counter {
+        fn get_session(&mut self) -> &mut sf::Session {
+            &mut self.session
+        }
+
+        fn get_command_table(&self) -> sf::CommandMetadataTable {
+            Self::get_interface_command_table()
+        }
+    }
+
+    impl server::IServerObject for Counter {
+        fn new() -> Self {
+            Self { session: sf::Session::new(), total: 0 }
+        }
+    }
+
+    pub struct CounterClient {
+        session: sf::Session
+    }
+
+    impl ICounter for CounterClient {
+        // Out objects need to be received as a known client type
+        fn open_counter(&mut self) -> Result<mem::Shared<dyn sf::IObject>> {
+            let object_info = self.get_info();
+            ipc_client_send_request_command!([object_info; 1] () => (counter: mem::Shared<CounterClient>))
+        }
+    }
+
+    impl sf::IObject for CounterClient {
+        fn get_session(&mut self) -> &mut sf::Session {
+            &mut self.session
+        }
+
+        fn get_command_table(&self) -> sf::CommandMetadataTable {
+            sf::CommandMetadataTable::new()
+        }
+    }
+
+    impl service::IClientObject for CounterClient {
+        fn new(session: sf::Session) -> Self {
+            Self { session }
+        }
+    }
+
+    // Transports are set per thread, thus tests running in parallel don't get in each other's way
+    fn with_loopback<F: FnOnce(ObjectInfo, &mem::Shared<LoopbackServerManager<0x100>>)>(protocol: CommandProtocol, f: F) {
+        let mut loopback_transport = LoopbackTransport::<0x100>::new().unwrap();
+        let object_info = loopback_transport.connect::<Counter>(protocol).unwrap();
+        let server_manager = loopback_transport.get_server_manager();
+        transport::set_transport(mem::Shared::new(loopback_transport));
+        assert!(transport::has_custom_transport());
+
+        f(object_info, &server_manager);
+
+        server_manager.get().close_all();
+        transport::reset_transport();
+        assert!(!transport::has_custom_transport());
+    }
+
+    #[test]
+    fn cmif_round_trip() {
+        with_loopback(CommandProtocol::Cmif, |object_info, server_manager| {
+            let mut counter = CounterClient::new(sf::Session::from(object_info));
+            assert_eq!(counter.add(2).unwrap(), 2);
+            assert_eq!(counter.add(3).unwrap(), 5);
+            assert_eq!(counter.get_info().query_pointer_buffer_size().unwrap(), 0x100);
+
+            // Objects outside of domains get a session of their own
+            let child_counter = counter.open_counter().unwrap().to::<CounterClient>();
+            assert_eq!(server_manager.get().get_server_holder_count(), 2);
+            assert_eq!(child_counter.get().add(7).unwrap(), 7);
+            assert_eq!(counter.add(1).unwrap(), 6);
+        });
+    }
+
+    #[test]
+    fn tipc_round_trip() {
+        with_loopback(CommandProtocol::Tipc, |object_info, _| {
+            let mut counter = CounterClient::new(sf::Session::from(object_info));
+            assert_eq!(counter.add(4).unwrap(), 4);
+            assert_eq!(counter.add(6).unwrap(), 10);
+
+            // TIPC has no control commands
+            assert!(counter.convert_to_domain().is_err());
+        });
+    }
+
+    #[test]
+    fn domain_round_trip() {
+        with_loopback(CommandProtocol::Cmif, |object_info, server_manager| {
+            let mut counter = CounterClient::new(sf::Session::from(object_info));
+            counter.convert_to_domain().unwrap();
+            assert!(counter.get_info().is_domain());
+            assert_eq!(counter.add(1).unwrap(), 1);
+
+            // Domain objects are reached through the domain's session
+            let child_counter = counter.open_counter().unwrap().to::<CounterClient>();
+            assert!(child_counter.get().get_info().is_domain());
+            assert_eq!(child_counter.get().get_info().handle, counter.get_info().handle);
+            assert_eq!(server_manager.get().get_server_holder_count(), 1);
+
+            assert_eq!(child_counter.get().add(10).unwrap(), 10);
+            assert_eq!(counter.add(1).unwrap(), 2);
+            assert_eq!(child_counter.get().add(5).unwrap(), 15);
+        });
+    }
+
+    #[test]
+    fn domain_in_object() {
+        with_loopback(CommandProtocol::Cmif, |object_info, _| {
+            let mut counter = CounterClient::new(sf::Session::from(object_info));
+            counter.convert_to_domain().unwrap();
+            assert_eq!(counter.add(2).unwrap(), 2);
+
+            let child_counter = counter.open_counter().unwrap();
+            assert_eq!(child_counter.to::<CounterClient>().get().add(5).unwrap(), 5);
+            assert_eq!(counter.merge(child_counter).unwrap(), 7);
+        });
+    }
+
+    #[test]
+    fn incoming_object_type_mismatch() {
+        let object: mem::Shared<dyn sf::IObject> = mem::Shared::new(server::InSessionObject::new(sf::Session::new()));
+        assert!(server::get_incoming_object::<server::InSessionObject>(&object).is_ok());
+        assert_eq!(server::get_incoming_object::<Counter>(&object).err(), Some(results::lib::ipc::ResultInvalidObjectType::make()));
+
+        let null_object: mem::Shared<dyn sf::IObject> = mem::Shared::<Counter>::empty();
+        assert_eq!(server::get_incoming_object::<Counter>(&null_object).err(), Some(results::lib::ipc::ResultInvalidObjectType::make()));
+    }
+
+    #[test]
+    fn command_panic_closes_session() {
+        with_loopback(CommandProtocol::Cmif, |object_info, server_manager| {
+            let mut counter = CounterClient::new(sf::Session::from(object_info));
+            let other_counter = counter.open_counter().unwrap().to::<CounterClient>();
+            assert_eq!(server_manager.get().get_server_holder_count(), 2);
+
+            // The panic is reported to the client, then the session gets closed (the default policy)
+            assert_eq!(counter.crash(), Err(results::lib::ResultPanicked::make()));
+            assert_eq!(counter.add(1), Err(results::os::ResultSessionClosed::make()));
+            assert_eq!(server_manager.get().get_server_holder_count(), 1);
+
+            // Other sessions keep being processed
+            assert_eq!(other_counter.get().add(3).unwrap(), 3);
+        });
+    }
+
+    #[test]
+    fn unsupported_command_not_sent() {
+        with_loopback(CommandProtocol::Cmif, |object_info, _| {
+            let mut counter = CounterClient::new(sf::Session::from(object_info));
+            assert_eq!(counter.add(2).unwrap(), 2);
+
+            // Every other test command exists in any version, thus setting the version doesn't affect them
+            version::set_version(version::Version::new(1, 0, 0));
+            assert_eq!(counter.reset(), Err(results::lib::ipc::ResultUnsupportedCommandVersion::make()));
+            version::set_version(version::Version::empty());
+
+            assert_eq!(counter.add(1).unwrap(), 3);
+        });
+    }
+}
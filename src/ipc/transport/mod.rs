@@ -0,0 +1,207 @@
+use crate::result::*;
+use crate::svc;
+use crate::thread;
+use crate::mem;
+use crate::diag;
+use crate::ipc;
+use crate::ipc::decode;
+use alloc::boxed::Box;
+use core::ptr;
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::Ordering;
+
+// Every IPC message goes through a transport: the default one is the kernel itself (TLS message buffer + IPC SVCs, see SvcTransport),
+// but any other transport can be set (like the in-process loopback) to run clients/servers without real kernel sessions
+
+pub const MSG_BUFFER_SIZE: usize = 0x100;
+
+pub trait Transport {
+    fn get_msg_buffer(&mut self) -> *mut u8;
+    fn send_sync_request(&mut self, handle: svc::Handle) -> Result<()>;
+    fn reply_and_receive(&mut self, handles: &[svc::Handle], reply_target: svc::Handle, timeout: i64) -> Result<usize>;
+    fn create_session(&mut self) -> Result<(svc::Handle, svc::Handle)>;
+    fn close_handle(&mut self, handle: svc::Handle) -> Result<()>;
+}
+
+pub struct SvcTransport;
+
+impl Transport for SvcTransport {
+    fn get_msg_buffer(&mut self) -> *mut u8 {
+        get_tls_msg_buffer()
+    }
+
+    fn send_sync_request(&mut self, handle: svc::Handle) -> Result<()> {
+        svc::send_sync_request(handle)
+    }
+
+    fn reply_and_receive(&mut self, handles: &[svc::Handle], reply_target: svc::Handle, timeout: i64) -> Result<usize> {
+        Ok(svc::reply_and_receive(handles.as_ptr(), handles.len() as u32, reply_target, timeout)? as usize)
+    }
+
+    // Session handles are owned by the IPC objects using them, which close them through the transport
+    fn create_session(&mut self) -> Result<(svc::Handle, svc::Handle)> {
+        let (server_handle, client_handle) = svc::create_session(false, 0)?;
+        Ok((server_handle.into_raw(), client_handle.into_raw()))
+    }
+
+    fn close_handle(&mut self, handle: svc::Handle) -> Result<()> {
+        svc::close_handle(handle)
+    }
+}
+
+// Custom transports are set per thread (in the thread's TLS slot), thus other threads keep using their own transport (the kernel by default)
+// Only the owning thread ever accesses its slot, thus no locking is needed and the usual path (no custom transport) is just a null check
+// Threads must reset their transport before they exit, otherwise their reference to it is leaked
+
+#[inline(always)]
+fn get_transport_slot() -> &'static mut *mut u8 {
+    &mut thread::get_current_thread().unused_tls_slots[thread::IPC_TRANSPORT_TLS_SLOT]
+}
+
+pub fn set_transport(transport: mem::Shared<dyn Transport>) {
+    reset_transport();
+    *get_transport_slot() = Box::into_raw(Box::new(transport)) as *mut u8;
+}
+
+pub fn reset_transport() {
+    let slot = get_transport_slot();
+    if !slot.is_null() {
+        unsafe {
+            drop(Box::from_raw(*slot as *mut mem::Shared<dyn Transport>));
+        }
+        *slot = ptr::null_mut();
+    }
+}
+
+pub fn has_custom_transport() -> bool {
+    get_custom_transport().is_some()
+}
+
+#[inline(always)]
+fn get_custom_transport() -> Option<&'static mut dyn Transport> {
+    let slot = *get_transport_slot();
+    if slot.is_null() {
+        None
+    }
+    else {
+        unsafe {
+            Some((*(slot as *mut mem::Shared<dyn Transport>)).get())
+        }
+    }
+}
+
+// Opt-in logging of every message sent/received through the transport (decoded, see ipc::decode) with the given logger
+// The logger can be set/reset at any time from any thread, while the in-progress flag is per-thread (in a TLS slot, since every thread logs its own messages)
+
+type MessageLogFn = fn(&diag::log::LogMetadata);
+
+static G_MESSAGE_LOG_FN: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+pub fn set_message_logger<L: diag::log::Logger>() {
+    let log_fn: MessageLogFn = diag::log::log_with::<L>;
+    G_MESSAGE_LOG_FN.store(log_fn as *mut (), Ordering::Release);
+}
+
+pub fn reset_message_logger() {
+    G_MESSAGE_LOG_FN.store(ptr::null_mut(), Ordering::Release);
+}
+
+#[inline(always)]
+fn get_message_logging_slot() -> &'static mut *mut u8 {
+    &mut thread::get_current_thread().unused_tls_slots[thread::IPC_MESSAGE_LOGGING_TLS_SLOT]
+}
+
+fn log_message(kind: &str, handle: svc::Handle) {
+    let log_fn_ptr = G_MESSAGE_LOG_FN.load(Ordering::Acquire);
+    if log_fn_ptr.is_null() {
+        return;
+    }
+
+    unsafe {
+        let log_fn: MessageLogFn = core::mem::transmute(log_fn_ptr);
+
+        // Loggers might send IPC requests themselves: their messages aren't logged, and ours must be kept intact meanwhile
+        let logging = get_message_logging_slot();
+        if !logging.is_null() {
+            return;
+        }
+        *logging = ptr::NonNull::dangling().as_ptr();
+
+        let msg_buf = get_msg_buffer();
+        let mut msg_backup: [u8; MSG_BUFFER_SIZE] = [0; MSG_BUFFER_SIZE];
+        ptr::copy(msg_buf, msg_backup.as_mut_ptr(), MSG_BUFFER_SIZE);
+
+        let msg = match decode::decode_message(&msg_backup) {
+            Ok(message) => format!("{} (handle {:#X}):\n{}", kind, handle, message),
+            Err(rc) => format!("{} (handle {:#X}): unable to decode message ({})", kind, handle, rc)
+        };
+        let metadata = diag::log::LogMetadata::new(diag::log::LogSeverity::Trace, false, msg, file!(), cur_fn_name!(), line!());
+        (log_fn)(&metadata);
+
+        ptr::copy(msg_backup.as_ptr(), msg_buf, MSG_BUFFER_SIZE);
+
+        *get_message_logging_slot() = ptr::null_mut();
+    }
+}
+
+#[inline(always)]
+pub fn get_tls_msg_buffer() -> *mut u8 {
+    unsafe {
+        (*thread::get_thread_local_region()).msg_buffer.as_mut_ptr()
+    }
+}
+
+// Note: when no custom transport is set, these directly use SvcTransport (avoiding any dynamic dispatch in the usual path)
+
+#[inline(always)]
+pub fn get_msg_buffer() -> *mut u8 {
+    match get_custom_transport() {
+        Some(transport) => transport.get_msg_buffer(),
+        None => SvcTransport.get_msg_buffer()
+    }
+}
+
+#[inline(always)]
+pub fn send_sync_request(handle: svc::Handle) -> Result<()> {
+    log_message("Request", handle);
+    match get_custom_transport() {
+        Some(transport) => transport.send_sync_request(handle),
+        None => SvcTransport.send_sync_request(handle)
+    }?;
+    log_message("Response", handle);
+    Ok(())
+}
+
+#[inline(always)]
+pub fn reply_and_receive(handles: &[svc::Handle], reply_target: svc::Handle, timeout: i64) -> Result<usize> {
+    if reply_target != svc::INVALID_HANDLE {
+        log_message("Reply", reply_target);
+    }
+    let index = match get_custom_transport() {
+        Some(transport) => transport.reply_and_receive(handles, reply_target, timeout),
+        None => SvcTransport.reply_and_receive(handles, reply_target, timeout)
+    }?;
+    if let Some(handle) = handles.get(index) {
+        log_message("Received request", *handle);
+    }
+    Ok(index)
+}
+
+#[inline(always)]
+pub fn create_session() -> Result<(svc::Handle, svc::Handle)> {
+    match get_custom_transport() {
+        Some(transport) => transport.create_session(),
+        None => SvcTransport.create_session()
+    }
+}
+
+#[inline(always)]
+pub fn close_handle(handle: svc::Handle) -> Result<()> {
+    ipc::forget_pointer_buffer_size(handle);
+    match get_custom_transport() {
+        Some(transport) => transport.close_handle(handle),
+        None => SvcTransport.close_handle(handle)
+    }
+}
+
+pub mod loopback;
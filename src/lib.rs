@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![allow(incomplete_features)]
 #![allow(non_snake_case)]
 #![feature(alloc_error_handler)]
@@ -14,16 +14,24 @@
 #![feature(derive_default_enum)]
 #![feature(const_fn_trait_bound)]
 #![feature(fn_traits)]
+#![feature(thread_local)]
 #![macro_use]
 
+#[cfg(target_arch = "aarch64")]
 use core::arch::global_asm;
 
 // Required assembly bits (those which essentially cannot/shouldn't be inlined)
+// Note: host builds (tests, fuzzing) only get the portable parts of the library, SVCs fail there with ResultNotSupported (see svc.rs)
 
+#[cfg(target_arch = "aarch64")]
 global_asm!(include_str!("asm.s"));
+#[cfg(target_arch = "aarch64")]
 global_asm!(include_str!("rrt0.s"));
+#[cfg(target_arch = "aarch64")]
 global_asm!(include_str!("arm.s"));
+#[cfg(target_arch = "aarch64")]
 global_asm!(include_str!("mem.s"));
+#[cfg(target_arch = "aarch64")]
 global_asm!(include_str!("svc.s"));
 
 #[macro_use]
@@ -75,6 +83,8 @@ pub mod wait;
 
 pub mod fs;
 
+pub mod nro;
+
 pub mod version;
 
 pub use paste;
@@ -28,7 +28,7 @@ macro_rules! ipc_client_send_request_command {
                 }
             )*
 
-            $crate::svc::send_sync_request($session.handle)?;
+            $crate::ipc::transport::send_sync_request($session.handle)?;
 
             match $session.protocol {
                 $crate::ipc::CommandProtocol::Cmif => $crate::ipc::cmif::client::read_request_command_response_from_msg_buffer(&mut ctx)?,
@@ -73,7 +73,7 @@ macro_rules! ipc_client_send_control_command {
                 }
             )*
 
-            $crate::svc::send_sync_request($session.handle)?;
+            $crate::ipc::transport::send_sync_request($session.handle)?;
 
             $crate::ipc::cmif::client::read_control_command_response_from_msg_buffer(&mut ctx)?;
 
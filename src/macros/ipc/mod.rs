@@ -2,190 +2,200 @@
 
 pub mod client;
 
+// Interfaces defined with this macro have their command table and their client implementation generated from this single definition (see the fs interfaces for an example)
+// Each command is defined as 'name [request ID, version interval]: (in params) => (out params)', where output objects may specify the client type they are created as ('obj: mem::Shared<dyn sf::IObject> as ClientType')
+// The protocol the interface is accessed through can be specified after its name: 'cmif' (default), 'tipc', 'cmif_tipc' or 'control'
+// Two traits are generated: the interface trait itself for clients, which just need to implement it (commands can still be overridden for special cases),
+// and the server trait ('IFoo' -> 'IFooServer') where every command must be implemented, thus a server missing any command doesn't even compile
+// Mitm servers implement every command too, returning ResultShouldForwardToSession from the ones they don't intercept (they get forwarded to the original service)
+
 #[macro_export]
-macro_rules! ipc_cmif_interface_define_command {
-    ($name:ident: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty ),* )) => {
-        #[allow(unused_parens)]
-        fn $name(&mut self, $( $in_param_name: $in_param_type ),* ) -> $crate::result::Result<( $( $out_param_type ),* )>;
+macro_rules! ipc_sf_define_interface_trait {
+    (
+        trait $intf:ident [control] {
+            $(
+                $name:ident [$rq_id:expr, $ver_intv:expr]: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty ),* )
+            );* $(;)?
+        }
+    ) => {
+        // Control commands are only implemented by the server-side HIPC manager (clients send them through ObjectInfo)
+        pub trait $intf {
+            $(
+                #[allow(unused_parens)]
+                fn $name(&mut self, $( $in_param_name: $in_param_type ),* ) -> $crate::result::Result<( $( $out_param_type ),* )>;
+                $crate::ipc_sf_define_interface_command_server_fns!(control; $name: ( $( $in_param_name: $in_param_type ),* ) => ( $( $out_param_name: $out_param_type ),* ));
+            )*
+
+            fn get_interface_command_table() -> $crate::ipc::sf::CommandMetadataTable where Self: Sized {
+                let mut command_table = $crate::ipc::sf::CommandMetadataTable::new();
+                $( $crate::ipc_sf_push_interface_command_meta!(command_table, control, $name, $rq_id, $ver_intv); )*
+                command_table
+            }
+        }
+    };
+    (
+        trait $intf:ident [$protocol:ident] {
+            $(
+                $name:ident [$rq_id:expr, $ver_intv:expr]: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty $( as $out_client_type:ty )? ),* )
+            );* $(;)?
+        }
+    ) => {
+        pub trait $intf: $crate::ipc::sf::IObject {
+            $(
+                $crate::ipc_sf_define_interface_command_client_fn!($name [$rq_id, $ver_intv]: ( $( $in_param_name: $in_param_type ),* ) => ( $( $out_param_name: $out_param_type $( as $out_client_type )? ),* ));
+            )*
+        }
 
         paste::paste! {
-            #[allow(unused_assignments)]
-            #[allow(unused_parens)]
-            fn [<$name _cmif_impl>](&mut self, mut ctx: &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()> {
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.in_params.data_offset);
-                $( let $in_param_name = <$in_param_type as $crate::ipc::server::CommandParameter<_>>::after_request_read(&mut ctx)?; )*
-
-                let ( $( $out_param_name ),* ) = self.$name( $( $in_param_name ),* )?;
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(core::ptr::null_mut());
-                $( $crate::ipc::server::CommandParameter::<_>::before_response_write(&$out_param_name, &mut ctx)?; )*
-                ctx.ctx.out_params.data_size = ctx.raw_data_walker.get_offset() as u32;
-
-                $crate::ipc::cmif::server::write_request_command_response_on_msg_buffer(&mut ctx.ctx, $crate::result::ResultSuccess::make(), $crate::ipc::cmif::CommandType::Request);
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.out_params.data_offset);
-                $( $crate::ipc::server::CommandParameter::<_>::after_response_write(&$out_param_name, &mut ctx)?; )*
-
-                Ok(())
+            pub trait [<$intf Server>]: $crate::ipc::sf::IObject {
+                $(
+                    #[allow(unused_parens)]
+                    fn $name(&mut self, $( $in_param_name: $in_param_type ),* ) -> $crate::result::Result<( $( $out_param_type ),* )>;
+                    $crate::ipc_sf_define_interface_command_server_fns!($protocol; $name: ( $( $in_param_name: $in_param_type ),* ) => ( $( $out_param_name: $out_param_type ),* ));
+                )*
+
+                fn get_interface_command_table() -> $crate::ipc::sf::CommandMetadataTable where Self: Sized {
+                    let mut command_table = $crate::ipc::sf::CommandMetadataTable::new();
+                    $( $crate::ipc_sf_push_interface_command_meta!(command_table, $protocol, $name, $rq_id, $ver_intv); )*
+                    command_table
+                }
+            }
+        }
+    };
+    (
+        trait $intf:ident {
+            $(
+                $name:ident [$rq_id:expr, $ver_intv:expr]: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty $( as $out_client_type:ty )? ),* )
+            );* $(;)?
+        }
+    ) => {
+        $crate::ipc_sf_define_interface_trait! {
+            trait $intf [cmif] {
+                $(
+                    $name [$rq_id, $ver_intv]: ( $( $in_param_name: $in_param_type ),* ) => ( $( $out_param_name: $out_param_type $( as $out_client_type )? ),* );
+                )*
             }
         }
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! ipc_tipc_interface_define_command {
-    ($name:ident: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty ),* )) => {
+macro_rules! ipc_sf_define_interface_command_client_fn {
+    ($name:ident [$rq_id:expr, $ver_intv:expr]: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty $( as $out_client_type:ty )? ),* )) => {
         #[allow(unused_parens)]
-        fn $name(&mut self, $( $in_param_name: $in_param_type ),* ) -> $crate::result::Result<( $( $out_param_type ),* )>;
-
-        paste::paste! {
-            #[allow(unused_assignments)]
-            #[allow(unused_parens)]
-            fn [<$name _tipc_impl>](&mut self, mut ctx: &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()> {
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.in_params.data_offset);
-                $( let $in_param_name = <$in_param_type as $crate::ipc::server::CommandParameter<_>>::after_request_read(&mut ctx)?; )*
-
-                let ( $( $out_param_name ),* ) = self.$name( $( $in_param_name ),* )?;
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(core::ptr::null_mut());
-                $( $crate::ipc::server::CommandParameter::<_>::before_response_write(&$out_param_name, &mut ctx)?; )*
-                ctx.ctx.out_params.data_size = ctx.raw_data_walker.get_offset() as u32;
-
-                $crate::ipc::tipc::server::write_request_command_response_on_msg_buffer(&mut ctx.ctx, $crate::result::ResultSuccess::make(), 16); // TODO: is this command type actually read/used/relevant?
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.out_params.data_offset);
-                $( $crate::ipc::server::CommandParameter::<_>::after_response_write(&$out_param_name, &mut ctx)?; )*
-
-                Ok(())
-            }
+        fn $name(&mut self, $( $in_param_name: $in_param_type ),* ) -> $crate::result::Result<( $( $out_param_type ),* )> {
+            $crate::ipc::client::check_command_version($ver_intv)?;
+            let object_info = self.get_info();
+            $crate::ipc_client_send_request_command!([object_info; $rq_id] ( $( $in_param_name ),* ) => ( $( $out_param_name: $crate::ipc_sf_interface_client_out_type!($out_param_type $(, $out_client_type)?) ),* ))
         }
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! ipc_cmif_tipc_interface_define_command {
-    ($name:ident: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty ),* )) => {
-        #[allow(unused_parens)]
-        fn $name(&mut self, $( $in_param_name: $in_param_type ),* ) -> $crate::result::Result<( $( $out_param_type ),* )>;
+macro_rules! ipc_sf_interface_client_out_type {
+    ($out_param_type:ty) => {
+        $out_param_type
+    };
+    ($out_param_type:ty, $out_client_type:ty) => {
+        $crate::mem::Shared<$out_client_type>
+    };
+}
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ipc_sf_define_interface_command_server_fns {
+    (cmif; $name:ident: $in_params:tt => $out_params:tt) => {
         paste::paste! {
-            #[allow(unused_assignments)]
-            #[allow(unused_parens)]
-            fn [<$name _cmif_impl>](&mut self, mut ctx: &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()> {
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.in_params.data_offset);
-                $( let $in_param_name = <$in_param_type as $crate::ipc::server::CommandParameter<_>>::after_request_read(&mut ctx)?; )*
-
-                let ( $( $out_param_name ),* ) = self.$name( $( $in_param_name ),* )?;
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(core::ptr::null_mut());
-                $( $crate::ipc::server::CommandParameter::<_>::before_response_write(&$out_param_name, &mut ctx)?; )*
-                ctx.ctx.out_params.data_size = ctx.raw_data_walker.get_offset() as u32;
-
-                $crate::ipc::cmif::server::write_request_command_response_on_msg_buffer(&mut ctx.ctx, $crate::result::ResultSuccess::make(), $crate::ipc::cmif::CommandType::Request);
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.out_params.data_offset);
-                $( $crate::ipc::server::CommandParameter::<_>::after_response_write(&$out_param_name, &mut ctx)?; )*
-
-                Ok(())
-            }
-
-            #[allow(unused_assignments)]
-            #[allow(unused_parens)]
-            fn [<$name _tipc_impl>](&mut self, mut ctx: &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()> {
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.in_params.data_offset);
-                $( let $in_param_name = <$in_param_type as $crate::ipc::server::CommandParameter<_>>::after_request_read(&mut ctx)?; )*
-
-                let ( $( $out_param_name ),* ) = self.$name( $( $in_param_name ),* )?;
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(core::ptr::null_mut());
-                $( $crate::ipc::server::CommandParameter::<_>::before_response_write(&$out_param_name, &mut ctx)?; )*
-                ctx.ctx.out_params.data_size = ctx.raw_data_walker.get_offset() as u32;
-
-                $crate::ipc::tipc::server::write_request_command_response_on_msg_buffer(&mut ctx.ctx, $crate::result::ResultSuccess::make(), 16); // TODO: is this command type actually read/used/relevant?
-
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.out_params.data_offset);
-                $( $crate::ipc::server::CommandParameter::<_>::after_response_write(&$out_param_name, &mut ctx)?; )*
-
-                Ok(())
-            }
+            $crate::ipc_sf_define_interface_command_server_fn!(cmif, [<$name _cmif_impl>]; $name: $in_params => $out_params);
+        }
+    };
+    (tipc; $name:ident: $in_params:tt => $out_params:tt) => {
+        paste::paste! {
+            $crate::ipc_sf_define_interface_command_server_fn!(tipc, [<$name _tipc_impl>]; $name: $in_params => $out_params);
+        }
+    };
+    (cmif_tipc; $name:ident: $in_params:tt => $out_params:tt) => {
+        paste::paste! {
+            $crate::ipc_sf_define_interface_command_server_fn!(cmif, [<$name _cmif_impl>]; $name: $in_params => $out_params);
+            $crate::ipc_sf_define_interface_command_server_fn!(tipc, [<$name _tipc_impl>]; $name: $in_params => $out_params);
+        }
+    };
+    (control; $name:ident: $in_params:tt => $out_params:tt) => {
+        paste::paste! {
+            $crate::ipc_sf_define_interface_command_server_fn!(control, [<$name _cmif_impl>]; $name: $in_params => $out_params);
         }
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! ipc_control_interface_define_command {
-    ($name:ident: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty ),* )) => {
+macro_rules! ipc_sf_define_interface_command_server_fn {
+    ($kind:ident, $impl_name:ident; $name:ident: ( $( $in_param_name:ident: $in_param_type:ty ),* ) => ( $( $out_param_name:ident: $out_param_type:ty ),* )) => {
+        #[allow(unused_assignments)]
         #[allow(unused_parens)]
-        fn $name(&mut self, $( $in_param_name: $in_param_type ),* ) -> $crate::result::Result<( $( $out_param_type ),* )>;
-
-        paste::paste! {
-            #[allow(unused_assignments)]
-            #[allow(unused_parens)]
-            fn [<$name _cmif_impl>](&mut self, mut ctx: &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()> {
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.in_params.data_offset);
-                $( let $in_param_name = <$in_param_type as $crate::ipc::server::CommandParameter<_>>::after_request_read(&mut ctx)?; )*
+        fn $impl_name(&mut self, mut ctx: &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()> {
+            ctx.raw_data_walker = $crate::ipc::DataWalker::new_bounded(ctx.ctx.in_params.data_offset, ctx.ctx.in_params.data_size as usize);
+            $( let $in_param_name = <$in_param_type as $crate::ipc::server::CommandParameter<_>>::after_request_read(&mut ctx)?; )*
+            ctx.ctx.in_params.validate_handles_consumed()?;
 
-                let ( $( $out_param_name ),* ) = self.$name( $( $in_param_name ),* )?;
+            let ( $( $out_param_name ),* ) = self.$name( $( $in_param_name ),* )?;
 
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(core::ptr::null_mut());
-                $( $crate::ipc::server::CommandParameter::<_>::before_response_write(&$out_param_name, &mut ctx)?; )*
-                ctx.ctx.out_params.data_size = ctx.raw_data_walker.get_offset() as u32;
+            ctx.raw_data_walker = $crate::ipc::DataWalker::new(core::ptr::null_mut());
+            $( $crate::ipc::server::CommandParameter::<_>::before_response_write(&$out_param_name, &mut ctx)?; )*
+            ctx.ctx.out_params.data_size = ctx.raw_data_walker.get_offset() as u32;
 
-                $crate::ipc::cmif::server::write_control_command_response_on_msg_buffer(&mut ctx.ctx, $crate::result::ResultSuccess::make(), $crate::ipc::cmif::CommandType::Control);
+            $crate::ipc_sf_write_interface_command_response!($kind, ctx);
 
-                ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.out_params.data_offset);
-                $( $crate::ipc::server::CommandParameter::<_>::after_response_write(&$out_param_name, &mut ctx)?; )*
+            ctx.raw_data_walker = $crate::ipc::DataWalker::new(ctx.ctx.out_params.data_offset);
+            $( $crate::ipc::server::CommandParameter::<_>::after_response_write(&$out_param_name, &mut ctx)?; )*
 
-                Ok(())
-            }
+            Ok(())
         }
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! ipc_cmif_interface_make_command_meta {
-    ($name:ident: $id:expr) => {
-        paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Cmif, $id, unsafe { core::mem::transmute(Self::[<$name _cmif_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, None, None)
-        }
+macro_rules! ipc_sf_write_interface_command_response {
+    (cmif, $ctx:ident) => {
+        $crate::ipc::cmif::server::write_request_command_response_on_msg_buffer(&mut $ctx.ctx, $crate::result::ResultSuccess::make(), $crate::ipc::cmif::CommandType::Request)
     };
-    ($name:ident: $id:expr, [($major:expr, $minor:expr, $micro:expr) =>]) => {
-        paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Cmif, $id, unsafe { core::mem::transmute(Self::[<$name _cmif_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, Some($crate::version::Version::new($major, $minor, $micro)), None)
-        }
+    (tipc, $ctx:ident) => {
+        $crate::ipc::tipc::server::write_request_command_response_on_msg_buffer(&mut $ctx.ctx, $crate::result::ResultSuccess::make(), $crate::ipc::tipc::CommandType::Request as u32)
     };
-    ($name:ident: $id:expr, [=> ($major:expr, $minor:expr, $micro:expr)]) => {
-        paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Cmif, $id, unsafe { core::mem::transmute(Self::[<$name _cmif_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, None, Some($crate::version::Version::new($major, $minor, $micro)))
-        }
+    (control, $ctx:ident) => {
+        $crate::ipc::cmif::server::write_control_command_response_on_msg_buffer(&mut $ctx.ctx, $crate::result::ResultSuccess::make(), $crate::ipc::cmif::CommandType::Control)
     };
-    ($name:ident: $id:expr, [($major_a:expr, $minor_a:expr, $micro_a:expr) => ($major_b:expr, $minor_b:expr, $micro_b:expr)]) => {
-        paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Cmif, $id, unsafe { core::mem::transmute(Self::[<$name _cmif_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, Some($crate::version::Version::new($major_a, $minor_a, $micro_a)), Some($crate::version::Version::new($major_b, $minor_b, $micro_b)))
-        }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ipc_sf_make_interface_command_meta {
+    ($protocol:expr, $impl_name:ident, $rq_id:expr, $ver_intv:expr) => {
+        $crate::ipc::sf::CommandMetadata::new_with_interval($protocol, $rq_id, unsafe { core::mem::transmute(Self::$impl_name as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, $ver_intv)
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! ipc_tipc_interface_make_command_meta {
-    ($name:ident: $id:expr) => {
+macro_rules! ipc_sf_push_interface_command_meta {
+    ($command_table:ident, cmif, $name:ident, $rq_id:expr, $ver_intv:expr) => {
         paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Tipc, $id, unsafe { core::mem::transmute(Self::[<$name _tipc_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, None, None)
+            $command_table.push($crate::ipc_sf_make_interface_command_meta!($crate::ipc::CommandProtocol::Cmif, [<$name _cmif_impl>], $rq_id, $ver_intv));
         }
     };
-    ($name:ident: $id:expr, [($major:expr, $minor:expr, $micro:expr) =>]) => {
+    ($command_table:ident, tipc, $name:ident, $rq_id:expr, $ver_intv:expr) => {
         paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Tipc, $id, unsafe { core::mem::transmute(Self::[<$name _tipc_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, Some($crate::version::Version::new($major, $minor, $micro)), None)
+            $command_table.push($crate::ipc_sf_make_interface_command_meta!($crate::ipc::CommandProtocol::Tipc, [<$name _tipc_impl>], $rq_id, $ver_intv));
         }
     };
-    ($name:ident: $id:expr, [=> ($major:expr, $minor:expr, $micro:expr)]) => {
-        paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Tipc, $id, unsafe { core::mem::transmute(Self::[<$name _tipc_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, None, Some($crate::version::Version::new($major, $minor, $micro)))
-        }
+    ($command_table:ident, cmif_tipc, $name:ident, $rq_id:expr, $ver_intv:expr) => {
+        $crate::ipc_sf_push_interface_command_meta!($command_table, cmif, $name, $rq_id, $ver_intv);
+        $crate::ipc_sf_push_interface_command_meta!($command_table, tipc, $name, $rq_id, $ver_intv);
     };
-    ($name:ident: $id:expr, [($major_a:expr, $minor_a:expr, $micro_a:expr) => ($major_b:expr, $minor_b:expr, $micro_b:expr)]) => {
-        paste::paste! {
-            $crate::ipc::sf::CommandMetadata::new($crate::ipc::CommandProtocol::Tipc, $id, unsafe { core::mem::transmute(Self::[<$name _tipc_impl>] as fn(&mut Self, &mut $crate::ipc::server::ServerContext) -> $crate::result::Result<()>) }, Some($crate::version::Version::new($major_a, $minor_a, $micro_a)), Some($crate::version::Version::new($major_b, $minor_b, $micro_b)))
-        }
+    ($command_table:ident, control, $name:ident, $rq_id:expr, $ver_intv:expr) => {
+        $crate::ipc_sf_push_interface_command_meta!($command_table, cmif, $name, $rq_id, $ver_intv);
     };
-}
\ No newline at end of file
+}
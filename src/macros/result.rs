@@ -15,6 +15,10 @@ macro_rules! result_define {
                     $description
                 }
             }
+
+            impl [<Result $name>] {
+                pub const INFO: $crate::result::ResultInfo = $crate::result::ResultInfo::new($module, $description, module_path!(), concat!("Result", stringify!($name)));
+            }
         }
     };
 }
@@ -23,6 +27,11 @@ macro_rules! result_define {
 macro_rules! result_define_group {
     ($module:expr => { $( $name:ident: $description:expr ),* }) => {
         $( result_define!($name: $module, $description); )*
+
+        paste::paste! {
+            // Every result in the group, gathered in results::RESULT_INFO_TABLES
+            pub const RESULT_INFO_TABLE: &[$crate::result::ResultInfo] = &[ $( [<Result $name>]::INFO ),* ];
+        }
     };
 }
 
@@ -1,3 +1,4 @@
+#[cfg(not(any(test, feature = "std")))]
 use crate::diag::assert;
 use crate::result::*;
 use crate::results;
@@ -58,7 +59,8 @@ unsafe impl<A: Allocator> GlobalAlloc for sync::Locked<A> {
     }
 }
 
-#[global_allocator]
+// Note: std builds (tests included) already have std's global allocator
+#[cfg_attr(not(any(test, feature = "std")), global_allocator)]
 static mut G_ALLOCATOR_HOLDER: sync::Locked<LinkedListAllocator> = sync::Locked::new(false, LinkedListAllocator::empty());
 static mut G_ALLOCATOR_ENABLED: bool = false;
 
@@ -81,30 +83,58 @@ pub fn is_enabled() -> bool {
     }
 }
 
-pub fn allocate(align: usize, size: usize) -> Result<*mut u8> {
-    unsafe {
-        let layout = Layout::from_size_align_unchecked(size, align);
-        G_ALLOCATOR_HOLDER.get().allocate(layout)
+// std builds have no heap to initialize our allocator with, thus our own allocations go through std's allocator too
+
+#[cfg(any(test, feature = "std"))]
+struct HostAllocator;
+
+#[cfg(any(test, feature = "std"))]
+impl Allocator for HostAllocator {
+    fn allocate(&mut self, layout: Layout) -> Result<*mut u8> {
+        let addr = unsafe { alloc::alloc::alloc(layout) };
+        result_return_if!(addr.is_null(), results::lib::alloc::ResultOutOfMemory);
+        Ok(addr)
+    }
+
+    fn release(&mut self, addr: *mut u8, layout: Layout) {
+        if !addr.is_null() {
+            unsafe {
+                alloc::alloc::dealloc(addr, layout)
+            }
+        }
     }
 }
 
-pub fn release(addr: *mut u8, align: usize, size: usize) {
+#[cfg(not(any(test, feature = "std")))]
+#[inline(always)]
+fn get_allocator() -> &'static mut LinkedListAllocator {
     unsafe {
-        let layout = Layout::from_size_align_unchecked(size, align);
-        G_ALLOCATOR_HOLDER.get().release(addr, layout);
+        G_ALLOCATOR_HOLDER.get()
     }
 }
 
+#[cfg(any(test, feature = "std"))]
+#[inline(always)]
+fn get_allocator() -> HostAllocator {
+    HostAllocator
+}
+
+pub fn allocate(align: usize, size: usize) -> Result<*mut u8> {
+    let layout = unsafe { Layout::from_size_align_unchecked(size, align) };
+    get_allocator().allocate(layout)
+}
+
+pub fn release(addr: *mut u8, align: usize, size: usize) {
+    let layout = unsafe { Layout::from_size_align_unchecked(size, align) };
+    get_allocator().release(addr, layout);
+}
+
 pub fn new<T>() -> Result<*mut T> {
-    unsafe {
-        G_ALLOCATOR_HOLDER.get().new::<T>()
-    }
+    get_allocator().new::<T>()
 }
 
 pub fn delete<T>(t: *mut T) {
-    unsafe {
-        G_ALLOCATOR_HOLDER.get().delete(t);
-    }
+    get_allocator().delete(t);
 }
 
 pub struct Buffer<T> {
@@ -151,6 +181,7 @@ impl<T> Buffer<T> {
     }
 }
 
+#[cfg(not(any(test, feature = "std")))]
 #[alloc_error_handler]
 fn alloc_error_handler(_layout: core::alloc::Layout) -> ! {
     // Disable memory allocation for this crate, this will avoid assertion methods which would need to allocate memory
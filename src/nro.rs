@@ -0,0 +1,440 @@
+use crate::result::*;
+use crate::results;
+use crate::mem;
+use crate::util;
+use crate::fs;
+use crate::ipc::sf::set;
+use alloc::vec::Vec;
+use core::mem as cmem;
+
+// NRO executables (the homebrew format) optionally carry an asset section right after the NRO image, containing an icon (JPEG), a NACP and a RomFS image
+// Everything here works over fs::IStorage, thus NROs can be parsed from files in any filesystem or from bytes in memory
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct Start {
+    pub unused: u32,
+    pub mod_offset: u32,
+    pub padding: [u8; 0x8]
+}
+const_assert!(cmem::size_of::<Start>() == 0x10);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct SegmentHeader {
+    pub file_offset: u32,
+    pub size: u32
+}
+const_assert!(cmem::size_of::<SegmentHeader>() == 0x8);
+
+pub const HEADER_MAGIC: u32 = u32::from_le_bytes(*b"NRO0");
+
+// Segments are text, rodata and data (in that order), the second ones are the API info, dynstr and dynsym ones
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct Header {
+    pub magic: u32,
+    pub version: u32,
+    pub size: u32,
+    pub flags: u32,
+    pub segments: [SegmentHeader; 3],
+    pub bss_size: u32,
+    pub reserved: u32,
+    pub module_id: [u8; 0x20],
+    pub dso_handle_offset: u32,
+    pub reserved_2: u32,
+    pub segments_2: [SegmentHeader; 3]
+}
+const_assert!(cmem::size_of::<Header>() == 0x70);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AssetSection {
+    pub offset: u64,
+    pub size: u64
+}
+const_assert!(cmem::size_of::<AssetSection>() == 0x10);
+
+impl AssetSection {
+    pub const fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+pub const ASSET_HEADER_MAGIC: u32 = u32::from_le_bytes(*b"ASET");
+
+// Section offsets are relative to the asset header
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct AssetHeader {
+    pub magic: u32,
+    pub version: u32,
+    pub icon: AssetSection,
+    pub nacp: AssetSection,
+    pub romfs: AssetSection
+}
+const_assert!(cmem::size_of::<AssetHeader>() == 0x38);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct NacpTitle {
+    pub name: util::CString<0x200>,
+    pub publisher: util::CString<0x100>
+}
+const_assert!(cmem::size_of::<NacpTitle>() == 0x300);
+
+impl NacpTitle {
+    pub fn is_empty(&self) -> bool {
+        self.name.get_str().map_or(true, |name| name.is_empty())
+    }
+}
+
+// NACP titles are not stored in set::Language order, this is the order of their entries
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum NacpLanguage {
+    AmericanEnglish = 0,
+    BritishEnglish = 1,
+    Japanese = 2,
+    French = 3,
+    German = 4,
+    LatinAmericanSpanish = 5,
+    Spanish = 6,
+    Italian = 7,
+    Dutch = 8,
+    CanadianFrench = 9,
+    Portuguese = 10,
+    Russian = 11,
+    Korean = 12,
+    TraditionalChinese = 13,
+    SimplifiedChinese = 14,
+    BrazilianPortuguese = 15
+}
+
+impl NacpLanguage {
+    pub const COUNT: usize = 16;
+
+    pub const fn from(language: set::Language) -> Self {
+        match language {
+            set::Language::Japanese => Self::Japanese,
+            set::Language::AmericanEnglish => Self::AmericanEnglish,
+            set::Language::French => Self::French,
+            set::Language::German => Self::German,
+            set::Language::Italian => Self::Italian,
+            set::Language::Spanish => Self::Spanish,
+            set::Language::Chinese | set::Language::SimplifiedChinese => Self::SimplifiedChinese,
+            set::Language::Korean => Self::Korean,
+            set::Language::Dutch => Self::Dutch,
+            set::Language::Portuguese => Self::Portuguese,
+            set::Language::Russian => Self::Russian,
+            set::Language::Taiwanese | set::Language::TraditionalChinese => Self::TraditionalChinese,
+            set::Language::BritishEnglish => Self::BritishEnglish,
+            set::Language::CanadianFrench => Self::CanadianFrench,
+            set::Language::LatinAmericanSpanish => Self::LatinAmericanSpanish,
+            set::Language::BrazilianPortuguese => Self::BrazilianPortuguese
+        }
+    }
+
+    pub const fn get_language(self) -> set::Language {
+        match self {
+            Self::AmericanEnglish => set::Language::AmericanEnglish,
+            Self::BritishEnglish => set::Language::BritishEnglish,
+            Self::Japanese => set::Language::Japanese,
+            Self::French => set::Language::French,
+            Self::German => set::Language::German,
+            Self::LatinAmericanSpanish => set::Language::LatinAmericanSpanish,
+            Self::Spanish => set::Language::Spanish,
+            Self::Italian => set::Language::Italian,
+            Self::Dutch => set::Language::Dutch,
+            Self::CanadianFrench => set::Language::CanadianFrench,
+            Self::Portuguese => set::Language::Portuguese,
+            Self::Russian => set::Language::Russian,
+            Self::Korean => set::Language::Korean,
+            Self::TraditionalChinese => set::Language::TraditionalChinese,
+            Self::SimplifiedChinese => set::Language::SimplifiedChinese,
+            Self::BrazilianPortuguese => set::Language::BrazilianPortuguese
+        }
+    }
+
+    pub const fn get_flag(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Nacp {
+    pub titles: [NacpTitle; NacpLanguage::COUNT],
+    pub isbn: util::CString<0x25>,
+    pub startup_user_account: u8,
+    pub user_account_switch_lock: u8,
+    pub add_on_content_registration_type: u8,
+    pub attribute_flag: u32,
+    pub supported_language_flag: u32,
+    pub parental_control_flag: u32,
+    pub screenshot: u8,
+    pub video_capture: u8,
+    pub data_loss_confirmation: u8,
+    pub play_log_policy: u8,
+    pub presence_group_id: u64,
+    pub rating_age: [i8; 0x20],
+    pub display_version: util::CString<0x10>,
+    pub add_on_content_base_id: u64,
+    pub save_data_owner_id: u64,
+    pub user_account_save_data_size: u64,
+    pub user_account_save_data_journal_size: u64,
+    pub device_save_data_size: u64,
+    pub device_save_data_journal_size: u64,
+    pub bcat_delivery_cache_storage_size: u64,
+    pub application_error_code_category: util::CString<0x8>,
+    pub local_communication_ids: [u64; 0x8],
+    pub reserved: [u8; 0xF10]
+}
+const_assert!(cmem::size_of::<Nacp>() == 0x4000);
+
+impl Nacp {
+    pub fn get_title(&self, language: NacpLanguage) -> &NacpTitle {
+        &self.titles[language as usize]
+    }
+
+    // Most homebrew only fill a few (or just one) of the titles, thus fall back to the first non-empty one like qlaunch/hbmenu do
+    pub fn get_title_for(&self, language: set::Language) -> &NacpTitle {
+        let title = self.get_title(NacpLanguage::from(language));
+        match title.is_empty() {
+            true => self.titles.iter().find(|title| !title.is_empty()).unwrap_or(title),
+            false => title
+        }
+    }
+
+    pub fn is_language_supported(&self, language: NacpLanguage) -> bool {
+        (self.supported_language_flag & language.get_flag()) != 0
+    }
+}
+
+fn read_storage_exact(storage: &mem::Shared<dyn fs::IStorage>, offset: usize, out_buf: &mut [u8]) -> Result<()> {
+    let read_size = storage.get().read(offset, out_buf)?;
+    result_return_unless!(read_size == out_buf.len(), results::lib::fs::ResultInvalidNro);
+    Ok(())
+}
+
+fn read_storage_value<T: Copy>(storage: &mem::Shared<dyn fs::IStorage>, offset: usize) -> Result<T> {
+    let mut value: T = unsafe { cmem::zeroed() };
+    read_storage_exact(storage, offset, unsafe { core::slice::from_raw_parts_mut(&mut value as *mut _ as *mut u8, cmem::size_of::<T>()) })?;
+    Ok(value)
+}
+
+pub struct Nro {
+    storage: mem::Shared<dyn fs::IStorage>,
+    start: Start,
+    header: Header,
+    asset_header: Option<AssetHeader>
+}
+
+impl Nro {
+    pub fn new(storage: mem::Shared<dyn fs::IStorage>) -> Result<Self> {
+        let start: Start = read_storage_value(&storage, 0)?;
+        let header: Header = read_storage_value(&storage, cmem::size_of::<Start>())?;
+        result_return_unless!(header.magic == HEADER_MAGIC, results::lib::fs::ResultInvalidNro);
+
+        // The asset section is optional (and it's simply not there if the file ends with the NRO image)
+        let asset_header_offset = header.size as usize;
+        let asset_header = match storage.get().get_size()? >= asset_header_offset + cmem::size_of::<AssetHeader>() {
+            true => Some(read_storage_value::<AssetHeader>(&storage, asset_header_offset)?).filter(|asset_header| asset_header.magic == ASSET_HEADER_MAGIC),
+            false => None
+        };
+
+        Ok(Self { storage, start, header, asset_header })
+    }
+
+    pub fn from_file(file: mem::Shared<dyn fs::IFile>) -> Result<Self> {
+        let storage = fs::FileStorage::from_file(file)?;
+        Self::new(mem::Shared::new(storage))
+    }
+
+    pub fn get_start(&self) -> Start {
+        self.start
+    }
+
+    pub fn get_header(&self) -> Header {
+        self.header
+    }
+
+    pub fn get_asset_header(&self) -> Option<AssetHeader> {
+        self.asset_header
+    }
+
+    pub fn has_icon(&self) -> bool {
+        self.asset_header.map_or(false, |asset_header| !asset_header.icon.is_empty())
+    }
+
+    pub fn has_nacp(&self) -> bool {
+        self.asset_header.map_or(false, |asset_header| !asset_header.nacp.is_empty())
+    }
+
+    pub fn has_romfs(&self) -> bool {
+        self.asset_header.map_or(false, |asset_header| !asset_header.romfs.is_empty())
+    }
+
+    fn get_asset_section_offset(&self, section: AssetSection) -> Result<usize> {
+        result_return_if!(section.is_empty(), results::lib::fs::ResultAssetSectionNotFound);
+
+        let section_offset = (self.header.size as u64).checked_add(section.offset).ok_or(results::lib::fs::ResultInvalidNro::make())?;
+        let storage_size = self.storage.get().get_size()? as u64;
+        result_return_unless!(section_offset.checked_add(section.size).map_or(false, |end_offset| end_offset <= storage_size), results::lib::fs::ResultInvalidNro);
+        Ok(section_offset as usize)
+    }
+
+    fn get_asset_section(&self, get_section: impl FnOnce(&AssetHeader) -> AssetSection) -> Result<(usize, usize)> {
+        let asset_header = self.asset_header.ok_or(results::lib::fs::ResultAssetSectionNotFound::make())?;
+        let section = get_section(&asset_header);
+        Ok((self.get_asset_section_offset(section)?, section.size as usize))
+    }
+
+    // The icon is a JPEG image (usually 256x256)
+    pub fn read_icon(&self) -> Result<Vec<u8>> {
+        let (icon_offset, icon_size) = self.get_asset_section(|asset_header| asset_header.icon)?;
+        let mut icon: Vec<u8> = vec![0; icon_size];
+        read_storage_exact(&self.storage, icon_offset, &mut icon)?;
+        Ok(icon)
+    }
+
+    pub fn read_nacp(&self) -> Result<Nacp> {
+        let (nacp_offset, nacp_size) = self.get_asset_section(|asset_header| asset_header.nacp)?;
+        result_return_unless!(nacp_size >= cmem::size_of::<Nacp>(), results::lib::fs::ResultInvalidNro);
+        read_storage_value(&self.storage, nacp_offset)
+    }
+
+    pub fn get_romfs_storage(&self) -> Result<fs::SubStorage> {
+        let (romfs_offset, romfs_size) = match self.get_asset_section(|asset_header| asset_header.romfs) {
+            Err(rc) if results::lib::fs::ResultAssetSectionNotFound::matches(rc) => return Err(results::lib::fs::ResultRomFsNotFound::make()),
+            section => section?
+        };
+        Ok(fs::SubStorage::new(self.storage.clone(), romfs_offset, romfs_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+
+    const NRO_SIZE: usize = 0x200;
+
+    fn push_value<T: Copy>(image: &mut Vec<u8>, value: T) {
+        image.extend_from_slice(unsafe { core::slice::from_raw_parts(&value as *const T as *const u8, cmem::size_of::<T>()) });
+    }
+
+    fn make_nacp(titles: &[(NacpLanguage, &str)]) -> Nacp {
+        let mut nacp: Nacp = unsafe { cmem::zeroed() };
+        for (language, name) in titles {
+            nacp.titles[*language as usize].name.set_str(name).unwrap();
+            nacp.supported_language_flag |= language.get_flag();
+        }
+        nacp
+    }
+
+    // Sections are given in ASET order (icon, NACP, RomFS), empty ones are left out of the asset section
+    fn build_nro(assets: Option<[&[u8]; 3]>) -> Vec<u8> {
+        let header = Header { magic: HEADER_MAGIC, size: NRO_SIZE as u32, ..Default::default() };
+        let mut image: Vec<u8> = Vec::new();
+        push_value(&mut image, Start::default());
+        push_value(&mut image, header);
+        image.resize(NRO_SIZE, 0);
+
+        if let Some(assets) = assets {
+            let mut sections = [AssetSection::default(); 3];
+            let mut cur_offset = cmem::size_of::<AssetHeader>() as u64;
+            for (section, data) in sections.iter_mut().zip(assets.iter()) {
+                *section = AssetSection { offset: cur_offset, size: data.len() as u64 };
+                cur_offset += data.len() as u64;
+            }
+
+            push_value(&mut image, AssetHeader { magic: ASSET_HEADER_MAGIC, version: 0, icon: sections[0], nacp: sections[1], romfs: sections[2] });
+            assets.iter().for_each(|data| image.extend_from_slice(data));
+        }
+        image
+    }
+
+    fn open_nro(image: Vec<u8>) -> Result<Nro> {
+        // MemoryStorage only takes static data, leaking it is fine for tests
+        Nro::new(mem::Shared::new(fs::MemoryStorage::new(Box::leak(image.into_boxed_slice()))))
+    }
+
+    fn nacp_bytes(nacp: &Nacp) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        push_value(&mut data, *nacp);
+        data
+    }
+
+    #[test]
+    fn header() {
+        let nro = open_nro(build_nro(None)).unwrap();
+        assert_eq!(nro.get_header().magic, HEADER_MAGIC);
+        assert_eq!(nro.get_header().size as usize, NRO_SIZE);
+        assert!(nro.get_asset_header().is_none());
+        assert!(!nro.has_icon() && !nro.has_nacp() && !nro.has_romfs());
+        assert!(results::lib::fs::ResultAssetSectionNotFound::matches(nro.read_icon().err().unwrap()));
+        assert!(results::lib::fs::ResultRomFsNotFound::matches(nro.get_romfs_storage().err().unwrap()));
+
+        let mut bad_magic_image = build_nro(None);
+        bad_magic_image[cmem::size_of::<Start>()] = 0;
+        assert!(results::lib::fs::ResultInvalidNro::matches(open_nro(bad_magic_image).err().unwrap()));
+        assert!(results::lib::fs::ResultInvalidNro::matches(open_nro(vec![0; 0x20]).err().unwrap()));
+    }
+
+    #[test]
+    fn asset_section() {
+        let nacp = make_nacp(&[(NacpLanguage::AmericanEnglish, "Homebrew")]);
+        let nro = open_nro(build_nro(Some([b"icon data", &nacp_bytes(&nacp), b"romfs data"]))).unwrap();
+        assert!(nro.has_icon() && nro.has_nacp() && nro.has_romfs());
+        assert_eq!(nro.read_icon().unwrap(), b"icon data");
+        assert_eq!(nro.read_nacp().unwrap().get_title(NacpLanguage::AmericanEnglish).name.get_str().unwrap(), "Homebrew");
+
+        let mut romfs_storage = nro.get_romfs_storage().unwrap();
+        let mut romfs_data = [0u8; 0x20];
+        assert_eq!(fs::IStorage::get_size(&mut romfs_storage).unwrap(), 10);
+        assert_eq!(fs::IStorage::read(&mut romfs_storage, 0, &mut romfs_data).unwrap(), 10);
+        assert_eq!(&romfs_data[..10], b"romfs data");
+
+        // Missing sections, and sections past the end of the storage
+        let nro = open_nro(build_nro(Some([b"icon data", b"", b""]))).unwrap();
+        assert!(nro.has_icon() && !nro.has_nacp() && !nro.has_romfs());
+        assert!(results::lib::fs::ResultAssetSectionNotFound::matches(nro.read_nacp().err().unwrap()));
+        assert!(results::lib::fs::ResultRomFsNotFound::matches(nro.get_romfs_storage().err().unwrap()));
+
+        let mut truncated_image = build_nro(Some([b"icon data", b"", b""]));
+        truncated_image.pop();
+        assert!(results::lib::fs::ResultInvalidNro::matches(open_nro(truncated_image).unwrap().read_icon().err().unwrap()));
+
+        // A NACP section too small for a NACP
+        let nro = open_nro(build_nro(Some([b"", b"nacp", b""]))).unwrap();
+        assert!(results::lib::fs::ResultInvalidNro::matches(nro.read_nacp().err().unwrap()));
+    }
+
+    #[test]
+    fn nacp_title_fallback() {
+        let nacp = make_nacp(&[(NacpLanguage::Japanese, "Japanese title"), (NacpLanguage::TraditionalChinese, "Chinese title")]);
+        assert_eq!(nacp.get_title_for(set::Language::Japanese).name.get_str().unwrap(), "Japanese title");
+        assert_eq!(nacp.get_title_for(set::Language::Taiwanese).name.get_str().unwrap(), "Chinese title");
+        // The first non-empty title (in NACP order) is used for languages without one
+        assert_eq!(nacp.get_title_for(set::Language::AmericanEnglish).name.get_str().unwrap(), "Japanese title");
+        assert!(nacp.get_title(NacpLanguage::AmericanEnglish).is_empty());
+
+        assert!(nacp.is_language_supported(NacpLanguage::Japanese));
+        assert!(!nacp.is_language_supported(NacpLanguage::French));
+
+        let empty_nacp = make_nacp(&[]);
+        assert!(empty_nacp.get_title_for(set::Language::French).is_empty());
+    }
+
+    #[test]
+    fn nacp_languages() {
+        for i in 0..NacpLanguage::COUNT as u32 {
+            let language: NacpLanguage = unsafe { cmem::transmute(i) };
+            assert_eq!(NacpLanguage::from(language.get_language()), language);
+        }
+    }
+}
@@ -15,21 +15,20 @@ use crate::ipc::sf;
 use crate::service;
 use crate::service::spl;
 use crate::service::spl::IRandomInterface;
-use crate::mem;
 
 pub struct SplCsrngGenerator {
-    csrng: mem::Shared<spl::RandomInterface>
+    csrng: service::ServiceRef<spl::RandomInterface>
 }
 
 impl SplCsrngGenerator {
     pub fn new() -> Result<Self> {
-        let csrng = service::new_service_object::<spl::RandomInterface>()?;
+        let csrng = service::get_service::<spl::RandomInterface>()?;
         Ok(Self { csrng })
     }
 }
 
 impl RandomGenerator for SplCsrngGenerator {
     fn random_bytes(&mut self, buf: *mut u8, size: usize) -> Result<()> {
-        self.csrng.get().generate_random_bytes(sf::Buffer::from_mut(buf, size))
+        self.csrng.call(|csrng| unsafe { csrng.generate_random_bytes(sf::OutBuffer::from_mut(buf, size)) })
     }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::results;
 use core::result;
 use core::fmt;
 
@@ -38,6 +39,35 @@ pub trait ResultBase {
     }
 }
 
+// Every result defined through result_define! has one of these, thus result codes can be mapped back to their names
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ResultInfo {
+    pub value: u32,
+    pub module_path: &'static str,
+    pub name: &'static str
+}
+
+impl ResultInfo {
+    pub const fn new(module: u32, description: u32, module_path: &'static str, name: &'static str) -> Self {
+        Self { value: pack_value(module, description), module_path, name }
+    }
+
+    // The path relative to the results module (like "fs" or "lib::ipc"), if the result is defined there
+    pub fn get_module_name(&self) -> Option<&'static str> {
+        self.module_path.strip_prefix(results::MODULE_PATH_PREFIX)
+    }
+}
+
+impl fmt::Display for ResultInfo {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        match self.get_module_name() {
+            Some(module_name) => write!(fmt, "{}::{}", module_name, self.name),
+            None => write!(fmt, "{}", self.name)
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Default)]
 #[repr(C)]
 pub struct ResultCode {
@@ -68,6 +98,14 @@ impl ResultCode {
     pub const fn get_description(&self) -> u32 {
         unpack_description(self.value)
     }
+
+    pub fn get_info(&self) -> Option<&'static ResultInfo> {
+        results::RESULT_INFO_TABLES.iter().flat_map(|table| table.iter()).find(|info| info.value == self.value)
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        self.get_info().map(|info| info.name)
+    }
 }
 
 impl fmt::Debug for ResultCode {
@@ -78,7 +116,10 @@ impl fmt::Debug for ResultCode {
 
 impl fmt::Display for ResultCode {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
-        write!(fmt, "{:0>4}-{:0>4}", 2000 + self.get_module(), self.get_description())
+        match self.get_info() {
+            Some(info) => write!(fmt, "{} ({:0>4}-{:0>4})", info, 2000 + self.get_module(), self.get_description()),
+            None => write!(fmt, "{:0>4}-{:0>4}", 2000 + self.get_module(), self.get_description())
+        }
     }
 }
 
@@ -94,4 +135,42 @@ pub fn wrap<T>(rc: ResultCode, value: T) -> Result<T> {
     else {
         Err(rc)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_names() {
+        let rc = results::fs::ResultPathNotFound::make();
+        assert_eq!(rc.name(), Some("ResultPathNotFound"));
+        assert_eq!(rc.get_info().unwrap().get_module_name(), Some("fs"));
+        assert_eq!(format!("{}", rc), "fs::ResultPathNotFound (2002-0001)");
+
+        let rc = results::lib::ipc::ResultInvalidObjectType::make();
+        assert_eq!(rc.name(), Some("ResultInvalidObjectType"));
+        assert_eq!(rc.get_info().unwrap().get_module_name(), Some("lib::ipc"));
+
+        // Results outside of the results module have no module name
+        assert_eq!(format!("{}", ResultSuccess::make()), "ResultSuccess (2000-0000)");
+    }
+
+    #[test]
+    fn unknown_result_names() {
+        let rc = ResultCode::new(pack_value(0x1FF, 0x1FFF));
+        assert!(rc.get_info().is_none());
+        assert!(rc.name().is_none());
+        assert_eq!(format!("{}", rc), "2511-8191");
+    }
+
+    #[test]
+    fn every_result_is_found() {
+        for info in results::RESULT_INFO_TABLES.iter().flat_map(|table| table.iter()) {
+            assert_eq!(ResultCode::new(info.value).get_info(), Some(info));
+            assert!(info.name.starts_with("Result"));
+            if info.value != SUCCESS_VALUE {
+                assert!(info.get_module_name().is_some(), "{} is outside of the results module", info.name);
+            }
+        }
+    }
+}
@@ -6,5 +6,6 @@ result_define_group!(RESULT_MODULE => {
     InvalidOutputHeader: 212,
     InvalidCommandRequestId: 221,
     InvalidInObjectCount: 235,
-    InvalidOutObjectCount: 236
+    InvalidOutObjectCount: 236,
+    RequestDeferredByUser: 812
 });
\ No newline at end of file
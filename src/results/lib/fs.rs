@@ -1,5 +1,13 @@
 pub const RESULT_SUBMODULE: u32 = 700;
 
 result_define_subgroup!(super::RESULT_MODULE, RESULT_SUBMODULE => {
-    DeviceNotFound: 1
+    DeviceNotFound: 1,
+    ReadOnlyFileSystem: 2,
+    InvalidRomFs: 3,
+    ProgramPathUnavailable: 4,
+    InvalidNro: 5,
+    RomFsNotFound: 6,
+    AssetSectionNotFound: 7,
+    CrossDeviceRename: 8,
+    InvalidSeekOffset: 9
 });
\ No newline at end of file
@@ -21,5 +21,17 @@ result_define_subgroup!(super::RESULT_MODULE, RESULT_SUBMODULE => {
     InvalidSendBufferCount: 18,
     InvalidReceiveBufferCount: 19,
     InvalidExchangeBufferCount: 20,
-    InvalidBufferAttributes: 21
+    InvalidBufferAttributes: 21,
+    TargetNotDomain: 22,
+    InvalidMessageSize: 23,
+    BufferTooSmall: 24,
+    InvalidBufferAlignment: 25,
+    HandleNotOwned: 26,
+    ServerHoldersFull: 27,
+    NewSessionsFull: 28,
+    DomainTableFull: 29,
+    DeferredBuffersFull: 30,
+    InvalidWorkerCount: 31,
+    InvalidObjectType: 32,
+    UnsupportedCommandVersion: 33
 });
\ No newline at end of file
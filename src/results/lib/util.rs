@@ -3,5 +3,6 @@ pub const RESULT_SUBMODULE: u32 = 300;
 result_define_subgroup!(super::RESULT_MODULE, RESULT_SUBMODULE => {
     InvalidPointer: 1,
     InvalidSize: 2,
-    InvalidConversion: 3
+    InvalidConversion: 3,
+    FixedVecFull: 4
 });
\ No newline at end of file
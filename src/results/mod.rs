@@ -1,3 +1,5 @@
+use crate::result::ResultInfo;
+
 pub mod lib;
 
 pub mod os;
@@ -10,4 +12,60 @@ pub mod sm;
 
 pub mod fs;
 
-pub mod nfp;
\ No newline at end of file
+pub mod nfp;
+
+// Result groups add their own tables (see result_define_group!), but those still need to be listed here to be found by ResultCode::get_info
+
+pub const RESULT_INFO_TABLES: &[&[ResultInfo]] = &[
+    &[crate::result::ResultSuccess::INFO],
+    lib::RESULT_INFO_TABLE,
+    lib::dynamic::RESULT_INFO_TABLE,
+    lib::elf::RESULT_INFO_TABLE,
+    lib::util::RESULT_INFO_TABLE,
+    lib::assert::RESULT_INFO_TABLE,
+    lib::gpu::RESULT_INFO_TABLE,
+    lib::ipc::RESULT_INFO_TABLE,
+    lib::fs::RESULT_INFO_TABLE,
+    lib::input::RESULT_INFO_TABLE,
+    lib::thread::RESULT_INFO_TABLE,
+    lib::alloc::RESULT_INFO_TABLE,
+    os::RESULT_INFO_TABLE,
+    cmif::RESULT_INFO_TABLE,
+    hipc::RESULT_INFO_TABLE,
+    sm::RESULT_INFO_TABLE,
+    sm::mitm::RESULT_INFO_TABLE,
+    fs::RESULT_INFO_TABLE,
+    nfp::RESULT_INFO_TABLE
+];
+
+pub(crate) const MODULE_PATH_PREFIX: &str = concat!(module_path!(), "::");
+
+const fn has_duplicate_results(tables: &[&[ResultInfo]]) -> bool {
+    let mut table_idx = 0;
+    while table_idx < tables.len() {
+        let mut info_idx = 0;
+        while info_idx < tables[table_idx].len() {
+            let value = tables[table_idx][info_idx].value;
+
+            // Only check against the results after this one
+            let mut other_table_idx = table_idx;
+            let mut other_info_idx = info_idx + 1;
+            while other_table_idx < tables.len() {
+                while other_info_idx < tables[other_table_idx].len() {
+                    if tables[other_table_idx][other_info_idx].value == value {
+                        return true;
+                    }
+                    other_info_idx += 1;
+                }
+                other_table_idx += 1;
+                other_info_idx = 0;
+            }
+            info_idx += 1;
+        }
+        table_idx += 1;
+    }
+    false
+}
+
+// Two results sharing a value would make name lookups ambiguous
+const_assert!(!has_duplicate_results(RESULT_INFO_TABLES));
@@ -59,8 +59,8 @@ fn initialize_version(hbl_hos_version: hbl::Version) {
     }
     else {
         let set_sys = service::new_service_object::<set::SystemSettingsServer>().unwrap();
-        let fw_version: set::FirmwareVersion = Default::default();
-        set_sys.get().get_firmware_version(sf::Buffer::from_var(&fw_version)).unwrap();
+        let mut fw_version: set::FirmwareVersion = Default::default();
+        set_sys.get().get_firmware_version(sf::OutBuffer::from_mut_var(&mut fw_version)).unwrap();
 
         let version = version::Version::new(fw_version.major, fw_version.minor, fw_version.micro);
         version::set_version(version);
@@ -124,7 +124,14 @@ unsafe extern "C" fn __nx_rrt0_entry(abi_ptr: *const hbl::AbiConfigEntry, raw_ma
                     // todo!("OverrideService");
                 },
                 hbl::AbiConfigEntryKey::Argv => {
-                    // todo!("Argv");
+                    let argv_data = (*abi_entry).value[1] as *const u8;
+                    if !argv_data.is_null() {
+                        let argv_data_len = util::str_ptr_len(argv_data);
+                        let argv_slice = core::slice::from_raw_parts(argv_data, argv_data_len);
+                        if let Ok(argv) = core::str::from_utf8(argv_slice) {
+                            hbl::set_argv(argv);
+                        }
+                    }
                 },
                 hbl::AbiConfigEntryKey::SyscallAvailableHint => {
                     // todo!("SyscallAvailableHint");
@@ -1,7 +1,6 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::service;
-use crate::mem;
 
 pub use crate::ipc::sf::applet::*;
 
@@ -15,11 +14,7 @@ impl sf::IObject for StorageAccessor {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_size: 0),
-            ipc_cmif_interface_make_command_meta!(write: 10),
-            ipc_cmif_interface_make_command_meta!(read: 11)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -29,19 +24,7 @@ impl service::IClientObject for StorageAccessor {
     }
 }
 
-impl IStorageAccessor for StorageAccessor {
-    fn get_size(&mut self) -> Result<usize> {
-        ipc_client_send_request_command!([self.session.object_info; 0] () => (size: usize))
-    }
-
-    fn write(&mut self, offset: usize, buf: sf::InAutoSelectBuffer) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 10] (offset, buf) => ())
-    }
-
-    fn read(&mut self, offset: usize, buf: sf::OutAutoSelectBuffer) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 11] (offset, buf) => ())
-    }
-}
+impl IStorageAccessor for StorageAccessor {}
 
 pub struct Storage {
     session: sf::Session
@@ -53,9 +36,7 @@ impl sf::IObject for Storage {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(open: 0)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -65,11 +46,7 @@ impl service::IClientObject for Storage {
     }
 }
 
-impl IStorage for Storage {
-    fn open(&mut self) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 0] () => (storage_accessor: mem::Shared<StorageAccessor>))
-    }
-}
+impl IStorage for Storage {}
 
 pub struct LibraryAppletAccessor {
     session: sf::Session
@@ -81,11 +58,7 @@ impl sf::IObject for LibraryAppletAccessor {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_applet_state_changed_event: 0),
-            ipc_cmif_interface_make_command_meta!(start: 10),
-            ipc_cmif_interface_make_command_meta!(push_in_data: 100)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -95,19 +68,7 @@ impl service::IClientObject for LibraryAppletAccessor {
     }
 }
 
-impl ILibraryAppletAccessor for LibraryAppletAccessor {
-    fn get_applet_state_changed_event(&mut self) -> Result<sf::CopyHandle> {
-        ipc_client_send_request_command!([self.session.object_info; 0] () => (applet_state_changed_event: sf::CopyHandle))
-    }
-
-    fn start(&mut self) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 10] () => ())
-    }
-
-    fn push_in_data(&mut self, storage: mem::Shared<dyn sf::IObject>) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 100] (storage) => ())
-    }
-}
+impl ILibraryAppletAccessor for LibraryAppletAccessor {}
 
 pub struct LibraryAppletCreator {
     session: sf::Session
@@ -119,10 +80,7 @@ impl sf::IObject for LibraryAppletCreator {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(create_library_applet: 0),
-            ipc_cmif_interface_make_command_meta!(create_storage: 10)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -132,15 +90,7 @@ impl service::IClientObject for LibraryAppletCreator {
     }
 }
 
-impl ILibraryAppletCreator for LibraryAppletCreator {
-    fn create_library_applet(&mut self, id: AppletId, mode: LibraryAppletMode) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (id, mode) => (library_applet_accessor: mem::Shared<LibraryAppletAccessor>))
-    }
-
-    fn create_storage(&mut self, size: usize) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 10] (size) => (storage: mem::Shared<Storage>))
-    }
-}
+impl ILibraryAppletCreator for LibraryAppletCreator {}
 
 pub struct WindowController {
     session: sf::Session
@@ -152,9 +102,7 @@ impl sf::IObject for WindowController {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(acquire_foreground_rights: 10)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -164,11 +112,7 @@ impl service::IClientObject for WindowController {
     }
 }
 
-impl IWindowController for WindowController {
-    fn acquire_foreground_rights(&mut self) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 10] () => ())
-    }
-}
+impl IWindowController for WindowController {}
 
 pub struct SelfController {
     session: sf::Session
@@ -180,9 +124,7 @@ impl sf::IObject for SelfController {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(set_screenshot_permission: 10)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -192,11 +134,7 @@ impl service::IClientObject for SelfController {
     }
 }
 
-impl ISelfController for SelfController {
-    fn set_screenshot_permission(&mut self, permission: ScreenShotPermission) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 10] (permission) => ())
-    }
-}
+impl ISelfController for SelfController {}
 
 pub struct LibraryAppletProxy {
     session: sf::Session
@@ -208,11 +146,7 @@ impl sf::IObject for LibraryAppletProxy {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_self_controller: 1),
-            ipc_cmif_interface_make_command_meta!(get_window_controller: 2),
-            ipc_cmif_interface_make_command_meta!(get_library_applet_creator: 11)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -222,19 +156,7 @@ impl service::IClientObject for LibraryAppletProxy {
     }
 }
 
-impl ILibraryAppletProxy for LibraryAppletProxy {
-    fn get_self_controller(&mut self) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 1] () => (self_controller: mem::Shared<SelfController>))
-    }
-
-    fn get_window_controller(&mut self) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 2] () => (window_controller: mem::Shared<WindowController>))
-    }
-
-    fn get_library_applet_creator(&mut self) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 11] () => (library_applet_creator: mem::Shared<LibraryAppletCreator>))
-    }
-}
+impl ILibraryAppletProxy for LibraryAppletProxy {}
 
 pub struct AllSystemAppletProxiesService {
     session: sf::Session
@@ -246,9 +168,7 @@ impl sf::IObject for AllSystemAppletProxiesService {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(open_library_applet_proxy: 201)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -258,11 +178,7 @@ impl service::IClientObject for AllSystemAppletProxiesService {
     }
 }
 
-impl IAllSystemAppletProxiesService for AllSystemAppletProxiesService {
-    fn open_library_applet_proxy(&mut self, process_id: sf::ProcessId, self_process_handle: sf::CopyHandle, applet_attribute: sf::InMapAliasBuffer) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 201] (process_id, self_process_handle, applet_attribute) => (library_applet_proxy: mem::Shared<LibraryAppletProxy>))
-    }
-}
+impl IAllSystemAppletProxiesService for AllSystemAppletProxiesService {}
 
 impl service::IService for AllSystemAppletProxiesService {
     fn get_name() -> &'static str {
@@ -14,12 +14,7 @@ impl sf::IObject for HOSBinderDriver {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(transact_parcel: 0),
-            ipc_cmif_interface_make_command_meta!(adjust_refcount: 1),
-            ipc_cmif_interface_make_command_meta!(get_native_handle: 2),
-            ipc_cmif_interface_make_command_meta!(transact_parcel_auto: 3)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -29,23 +24,7 @@ impl service::IClientObject for HOSBinderDriver {
     }
 }
 
-impl IHOSBinderDriver for HOSBinderDriver {
-    fn transact_parcel(&mut self, binder_handle: BinderHandle, transaction_id: ParcelTransactionId, flags: u32, in_parcel: sf::InMapAliasBuffer, out_parcel: sf::OutMapAliasBuffer) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (binder_handle, transaction_id, flags, in_parcel, out_parcel) => ())
-    }
-
-    fn adjust_refcount(&mut self, binder_handle: BinderHandle, add_value: i32, refcount_type: RefcountType) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 1] (binder_handle, add_value, refcount_type) => ())
-    }
-
-    fn get_native_handle(&mut self, binder_handle: BinderHandle, handle_type: NativeHandleType) -> Result<sf::CopyHandle> {
-        ipc_client_send_request_command!([self.session.object_info; 2] (binder_handle, handle_type) => (native_handle: sf::CopyHandle))
-    }
-
-    fn transact_parcel_auto(&mut self, binder_handle: BinderHandle, transaction_id: ParcelTransactionId, flags: u32, in_parcel: sf::InAutoSelectBuffer, out_parcel: sf::OutAutoSelectBuffer) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 3] (binder_handle, transaction_id, flags, in_parcel, out_parcel) => ())
-    }
-}
+impl IHOSBinderDriver for HOSBinderDriver {}
 
 impl service::IService for HOSBinderDriver {
     fn get_name() -> &'static str {
@@ -14,9 +14,7 @@ impl sf::IObject for Service {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(throw_with_policy: 1)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -26,11 +24,7 @@ impl service::IClientObject for Service {
     }
 }
 
-impl IService for Service {
-    fn throw_with_policy(&mut self, rc: ResultCode, policy: Policy, process_id: sf::ProcessId) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 1] (rc, policy, process_id) => ())
-    }
-}
+impl IService for Service {}
 
 impl service::IService for Service {
     fn get_name() -> &'static str {
@@ -1,7 +1,6 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::service;
-use crate::mem;
 
 pub use crate::ipc::sf::lm::*;
 
@@ -15,10 +14,7 @@ impl sf::IObject for Logger {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(log: 0),
-            ipc_cmif_interface_make_command_meta!(set_destination: 1)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -28,15 +24,7 @@ impl service::IClientObject for Logger {
     }
 }
 
-impl ILogger for Logger {
-    fn log(&mut self, log_buf: sf::InAutoSelectBuffer) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (log_buf) => ())
-    }
-
-    fn set_destination(&mut self, log_destination: LogDestination) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 1] (log_destination) => ())
-    }
-}
+impl ILogger for Logger {}
 
 pub struct LogService {
     session: sf::Session
@@ -48,9 +36,7 @@ impl sf::IObject for LogService {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(open_logger: 0)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -60,11 +46,7 @@ impl service::IClientObject for LogService {
     }
 }
 
-impl ILogService for LogService {
-    fn open_logger(&mut self, process_id: sf::ProcessId) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (process_id) => (logger: mem::Shared<Logger>))
-    }
-}
+impl ILogService for LogService {}
 
 impl service::IService for LogService {
     fn get_name() -> &'static str {
@@ -1,7 +1,6 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::service;
-use crate::mem;
 
 pub use crate::ipc::sf::mii::*;
 
@@ -15,13 +14,7 @@ impl sf::IObject for DatabaseService {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(is_updated: 0),
-            ipc_cmif_interface_make_command_meta!(is_full: 1),
-            ipc_cmif_interface_make_command_meta!(get_count: 2),
-            ipc_cmif_interface_make_command_meta!(get_1: 4),
-            ipc_cmif_interface_make_command_meta!(build_random: 6)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -31,27 +24,7 @@ impl service::IClientObject for DatabaseService {
     }
 }
 
-impl IDatabaseService for DatabaseService {
-    fn is_updated(&mut self, flag: SourceFlag) -> Result<bool> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (flag) => (updated: bool))
-    }
-
-    fn is_full(&mut self) -> Result<bool> {
-        ipc_client_send_request_command!([self.session.object_info; 1] () => (full: bool))
-    }
-
-    fn get_count(&mut self, flag: SourceFlag) -> Result<u32> {
-        ipc_client_send_request_command!([self.session.object_info; 2] (flag) => (count: u32))
-    }
-
-    fn get_1(&mut self, flag: SourceFlag, out_char_infos: sf::OutMapAliasBuffer) -> Result<u32> {
-        ipc_client_send_request_command!([self.session.object_info; 4] (flag, out_char_infos) => (count: u32))
-    }
-
-    fn build_random(&mut self, age: Age, gender: Gender, face_color: FaceColor) -> Result<CharInfo> {
-        ipc_client_send_request_command!([self.session.object_info; 6] (age, gender, face_color) => (char_info: CharInfo))
-    }
-}
+impl IDatabaseService for DatabaseService {}
 
 pub struct StaticService {
     session: sf::Session
@@ -63,9 +36,7 @@ impl sf::IObject for StaticService {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_database_service: 0)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -75,11 +46,7 @@ impl service::IClientObject for StaticService {
     }
 }
 
-impl IStaticService for StaticService {
-    fn get_database_service(&mut self, key_code: SpecialKeyCode) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (key_code) => (database_service: mem::Shared<DatabaseService>))
-    }
-}
+impl IStaticService for StaticService {}
 
 impl service::IService for StaticService {
     fn get_name() -> &'static str {
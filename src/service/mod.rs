@@ -1,7 +1,11 @@
 use crate::ipc::sf;
 use crate::mem;
 use crate::svc;
+use crate::sync;
 use crate::result::*;
+use crate::results;
+use alloc::vec::Vec;
+use core::any::TypeId;
 
 pub mod sm;
 use crate::service::sm::IUserInterface;
@@ -23,7 +27,7 @@ pub trait IService: IClientObject {
 
 pub fn new_named_port_object<T: INamedPort + 'static>() -> Result<mem::Shared<T>> {
     let handle = svc::connect_to_named_port(T::get_name().as_ptr())?;
-    let mut object = T::new(sf::Session::from_handle(handle));
+    let mut object = T::new(sf::Session::from_handle(handle.into_raw()));
     object.post_initialize()?;
     Ok(mem::Shared::new(object))
 }
@@ -32,7 +36,7 @@ pub fn new_service_object<T: IService + 'static>() -> Result<mem::Shared<T>> {
     let sm = new_named_port_object::<sm::UserInterface>()?;
     let session_handle = sm.get().get_service_handle(sm::ServiceName::new(T::get_name()))?;
     sm.get().detach_client(sf::ProcessId::new())?;
-    let mut object = T::new(sf::Session::from_handle(session_handle.handle));
+    let mut object = T::new(sf::Session::from_handle(session_handle.into_raw()));
     if T::as_domain() {
         object.convert_to_domain()?;
     }
@@ -40,6 +44,111 @@ pub fn new_service_object<T: IService + 'static>() -> Result<mem::Shared<T>> {
     Ok(mem::Shared::new(object))
 }
 
+// Process-wide cache of service objects: every service is opened on first use and shared by everyone using it afterwards
+// Shared objects don't have an atomic refcount, thus registry objects are only ever cloned and dropped with the registry lock held (see ServiceRef)
+// Once the last ServiceRef of a service is dropped nobody uses it anymore, so its session gets finalized
+
+struct ServiceEntry {
+    type_id: TypeId,
+    object: mem::Shared<dyn sf::IObject>
+}
+
+static mut G_SERVICES_LOCK: sync::Mutex = sync::Mutex::new(true);
+static mut G_SERVICES: Vec<ServiceEntry> = Vec::new();
+
+pub struct ServiceRef<T: IService + 'static> {
+    object: mem::Shared<T>
+}
+
+impl<T: IService + 'static> ServiceRef<T> {
+    pub fn get(&self) -> &mut T {
+        self.object.get()
+    }
+
+    // Calls the given function with the service, re-creating its session once if it got closed meanwhile (like when the service's process restarted)
+    pub fn call<R, F: FnMut(&mut T) -> Result<R>>(&mut self, mut f: F) -> Result<R> {
+        match f(self.get()) {
+            Err(rc) if is_session_closed(rc) => {
+                // Anyone else still using the closed session will get the same error and re-create it the same way, reusing the new session if we got here first
+                self.forget_closed_session();
+                *self = get_service::<T>()?;
+                f(self.get())
+            },
+            rc => rc
+        }
+    }
+
+    fn is_same_object(&self, object: &mem::Shared<dyn sf::IObject>) -> bool {
+        object.get() as *mut dyn sf::IObject as *mut u8 == self.object.get() as *mut T as *mut u8
+    }
+
+    fn forget_closed_session(&self) {
+        unsafe {
+            let _lock = sync::ScopedLock::new(&mut G_SERVICES_LOCK);
+            G_SERVICES.retain(|entry| !self.is_same_object(&entry.object));
+        }
+    }
+}
+
+impl<T: IService + 'static> Drop for ServiceRef<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let _lock = sync::ScopedLock::new(&mut G_SERVICES_LOCK);
+            self.object = mem::Shared::empty();
+
+            // Only the registry's own reference is left, thus the session is finalized here
+            G_SERVICES.retain(|entry| (entry.type_id != TypeId::of::<T>()) || (entry.object.use_count() > 1));
+        }
+    }
+}
+
+pub fn get_service<T: IService + 'static>() -> Result<ServiceRef<T>> {
+    unsafe {
+        let _lock = sync::ScopedLock::new(&mut G_SERVICES_LOCK);
+
+        let type_id = TypeId::of::<T>();
+        if let Some(entry) = G_SERVICES.iter().find(|entry| entry.type_id == type_id) {
+            return Ok(ServiceRef { object: entry.object.to::<T>() });
+        }
+
+        let object = new_service_object::<T>()?;
+        G_SERVICES.push(ServiceEntry { type_id, object: object.clone() });
+        Ok(ServiceRef { object })
+    }
+}
+
+pub fn is_service_cached<T: IService + 'static>() -> bool {
+    unsafe {
+        let _lock = sync::ScopedLock::new(&mut G_SERVICES_LOCK);
+        G_SERVICES.iter().any(|entry| entry.type_id == TypeId::of::<T>())
+    }
+}
+
+// Forgets the service even if it's still being used, its session is finalized once the last ServiceRef using it is dropped
+pub fn release_service<T: IService + 'static>() {
+    unsafe {
+        let _lock = sync::ScopedLock::new(&mut G_SERVICES_LOCK);
+        G_SERVICES.retain(|entry| entry.type_id != TypeId::of::<T>());
+    }
+}
+
+pub fn release_all_services() {
+    unsafe {
+        let _lock = sync::ScopedLock::new(&mut G_SERVICES_LOCK);
+        G_SERVICES.clear();
+    }
+}
+
+#[inline(always)]
+pub fn is_session_closed(rc: ResultCode) -> bool {
+    results::os::ResultSessionClosed::matches(rc) || results::hipc::ResultSessionClosed::matches(rc)
+}
+
+// One-shot version of ServiceRef::call, the session is finalized afterwards unless someone else keeps a ServiceRef of the service
+pub fn call_service<T: IService + 'static, R, F: FnMut(&mut T) -> Result<R>>(f: F) -> Result<R> {
+    get_service::<T>()?.call(f)
+}
+
 pub mod psm;
 
 pub mod fspsrv;
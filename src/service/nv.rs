@@ -34,23 +34,7 @@ pub fn convert_error_code(err: ErrorCode) -> Result<()> {
 // NvDrvService is the base trait for all the different services, since the only difference is their service names :P
 pub trait NvDrvService: service::IClientObject {}
 
-impl<S: NvDrvService> INvDrvService for S {
-    fn open(&mut self, path: sf::InMapAliasBuffer) -> Result<(Fd, ErrorCode)> {
-        ipc_client_send_request_command!([self.get_info(); 0] (path) => (fd: Fd, error_code: ErrorCode))
-    }
-
-    fn ioctl(&mut self, fd: Fd, id: IoctlId, in_buf: sf::InAutoSelectBuffer, out_buf: sf::OutAutoSelectBuffer) -> Result<ErrorCode> {
-        ipc_client_send_request_command!([self.get_info(); 1] (fd, id, in_buf, out_buf) => (error_code: ErrorCode))
-    }
-
-    fn close(&mut self, fd: Fd) -> Result<ErrorCode> {
-        ipc_client_send_request_command!([self.get_info(); 2] (fd) => (error_code: ErrorCode))
-    }
-
-    fn initialize(&mut self, transfer_mem_size: u32, self_process_handle: sf::CopyHandle, transfer_mem_handle: sf::CopyHandle) -> Result<ErrorCode> {
-        ipc_client_send_request_command!([self.get_info(); 3] (transfer_mem_size, self_process_handle, transfer_mem_handle) => (error_code: ErrorCode))
-    }
-}
+impl<S: NvDrvService> INvDrvService for S {}
 
 pub struct AppletNvDrvService {
     session: sf::Session
@@ -62,12 +46,7 @@ impl sf::IObject for AppletNvDrvService {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(open: 0),
-            ipc_cmif_interface_make_command_meta!(ioctl: 1),
-            ipc_cmif_interface_make_command_meta!(close: 2),
-            ipc_cmif_interface_make_command_meta!(initialize: 3)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -103,12 +82,7 @@ impl sf::IObject for SystemNvDrvService {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(open: 0),
-            ipc_cmif_interface_make_command_meta!(ioctl: 1),
-            ipc_cmif_interface_make_command_meta!(close: 2),
-            ipc_cmif_interface_make_command_meta!(initialize: 3)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -14,9 +14,7 @@ impl sf::IObject for InformationInterface {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_program_id: 0)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -26,11 +24,7 @@ impl service::IClientObject for InformationInterface {
     }
 }
 
-impl IInformationInterface for InformationInterface {
-    fn get_program_id(&mut self, process_id: u64) -> Result<u64> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (process_id) => (program_id: u64))
-    }
-}
+impl IInformationInterface for InformationInterface {}
 
 impl service::IService for InformationInterface {
     fn get_name() -> &'static str {
@@ -56,9 +50,7 @@ impl sf::IObject for DebugMonitorInterface {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_application_process_id: 5)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -68,11 +60,7 @@ impl service::IClientObject for DebugMonitorInterface {
     }
 }
 
-impl IDebugMonitorInterface for DebugMonitorInterface {
-    fn get_application_process_id(&mut self) -> Result<u64> {
-        ipc_client_send_request_command!([self.session.object_info; 5] () => (process_id: u64))
-    }
-}
+impl IDebugMonitorInterface for DebugMonitorInterface {}
 
 impl service::IService for DebugMonitorInterface {
     fn get_name() -> &'static str {
@@ -1,7 +1,6 @@
 use crate::result::*;
 use crate::ipc::sf;
 use crate::service;
-use crate::mem;
 
 pub use crate::ipc::sf::psc::*;
 
@@ -15,13 +14,7 @@ impl sf::IObject for PmModule {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(initialize: 0),
-            ipc_cmif_interface_make_command_meta!(get_request: 1),
-            ipc_cmif_interface_make_command_meta!(acknowledge: 2),
-            ipc_cmif_interface_make_command_meta!(finalize: 3),
-            ipc_cmif_interface_make_command_meta!(acknowledge_ex: 4)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -31,27 +24,7 @@ impl service::IClientObject for PmModule {
     }
 }
 
-impl IPmModule for PmModule {
-    fn initialize(&mut self, id: ModuleId, dependencies: sf::InMapAliasBuffer) -> Result<sf::CopyHandle> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (id, dependencies) => (event_handle: sf::CopyHandle))
-    }
-
-    fn get_request(&mut self) -> Result<(State, u32)> {
-        ipc_client_send_request_command!([self.session.object_info; 1] () => (state: State, flags: u32))
-    }
-
-    fn acknowledge(&mut self) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 2] () => ())
-    }
-
-    fn finalize(&mut self) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 3] () => ())
-    }
-
-    fn acknowledge_ex(&mut self, state: State) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 4] (state) => ())
-    }
-}
+impl IPmModule for PmModule {}
 
 pub struct PmService {
     session: sf::Session
@@ -63,9 +36,7 @@ impl sf::IObject for PmService {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_pm_module: 0)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -75,11 +46,7 @@ impl service::IClientObject for PmService {
     }
 }
 
-impl IPmService for PmService {
-    fn get_pm_module(&mut self) -> Result<mem::Shared<dyn sf::IObject>> {
-        ipc_client_send_request_command!([self.session.object_info; 0] () => (pm_module: mem::Shared<PmModule>))
-    }
-}
+impl IPmService for PmService {}
 
 impl service::IService for PmService {
     fn get_name() -> &'static str {
@@ -14,9 +14,7 @@ impl sf::IObject for PsmServer {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_battery_charge_percentage: 0)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -26,11 +24,7 @@ impl service::IClientObject for PsmServer {
     }
 }
 
-impl IPsmServer for PsmServer {
-    fn get_battery_charge_percentage(&mut self) -> Result<u32> {
-        ipc_client_send_request_command!([self.session.object_info; 0] () => (charge: u32))
-    }
-}
+impl IPsmServer for PsmServer {}
 
 impl service::IService for PsmServer {
     fn get_name() -> &'static str {
@@ -14,10 +14,7 @@ impl sf::IObject for SystemSettingsServer {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(get_firmware_version: 3),
-            ipc_cmif_interface_make_command_meta!(get_firmware_version_2: 4)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -27,15 +24,7 @@ impl service::IClientObject for SystemSettingsServer {
     }
 }
 
-impl ISystemSettingsServer for SystemSettingsServer {
-    fn get_firmware_version(&mut self, out_version: sf::OutFixedPointerBuffer<FirmwareVersion>) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 3] (out_version) => ())
-    }
-
-    fn get_firmware_version_2(&mut self, out_version: sf::OutFixedPointerBuffer<FirmwareVersion>) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 4] (out_version) => ())
-    }
-}
+impl ISystemSettingsServer for SystemSettingsServer {}
 
 impl service::IService for SystemSettingsServer {
     fn get_name() -> &'static str {
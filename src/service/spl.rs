@@ -14,9 +14,7 @@ impl sf::IObject for RandomInterface {
     }
 
     fn get_command_table(&self) -> sf::CommandMetadataTable {
-        vec! [
-            ipc_cmif_interface_make_command_meta!(generate_random_bytes: 0)
-        ]
+        sf::CommandMetadataTable::new()
     }
 }
 
@@ -26,11 +24,7 @@ impl service::IClientObject for RandomInterface {
     }
 }
 
-impl IRandomInterface for RandomInterface {
-    fn generate_random_bytes(&mut self, out_buf: sf::OutMapAliasBuffer) -> Result<()> {
-        ipc_client_send_request_command!([self.session.object_info; 0] (out_buf) => ())
-    }
-}
+impl IRandomInterface for RandomInterface {}
 
 impl service::IService for RandomInterface {
     fn get_name() -> &'static str {
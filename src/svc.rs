@@ -3,8 +3,10 @@ use crate::smc;
 use crate::arm;
 use crate::util;
 use crate::version;
+use crate::ipc;
 use core::ptr;
 use core::mem;
+use core::marker::PhantomData;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(u32)]
@@ -108,8 +110,177 @@ pub enum InfoId {
     TotalNonSystemMemorySize = 21,
     UsedNonSystemMemorySize = 22,
     IsApplication = 23,
+    FreeThreadCount = 24,
+    ThreadTickCount = 25,
+    IsSvcPermitted = 26,
+    IoRegionHint = 27,
+    AliasRegionExtraSize = 28,
+    MesosphereMeta = 65000,
+    MesosphereCurrentProcess = 65001,
+    ThreadTickCountDeprecated = 0xF0000002
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u64)]
+pub enum SystemInfoId {
+    TotalPhysicalMemorySize = 0,
+    UsedPhysicalMemorySize = 1,
+    InitialProcessIdRange = 2
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum LimitableResource {
+    PhysicalMemory = 0,
+    Threads = 1,
+    Events = 2,
+    TransferMemories = 3,
+    Sessions = 4
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum ThreadActivity {
+    Runnable = 0,
+    Paused = 1
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum ProcessActivity {
+    Runnable = 0,
+    Paused = 1
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum ArbitrationType {
+    WaitIfLessThan = 0,
+    DecrementAndWaitIfLessThan = 1,
+    WaitIfEqual = 2
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum SignalType {
+    Signal = 0,
+    SignalAndIncrementIfEqual = 1,
+    SignalAndModifyByWaitingCountIfEqual = 2
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum CodeMapOperation {
+    MapOwner = 0,
+    MapSlave = 1,
+    UnmapOwner = 2,
+    UnmapSlave = 3
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum DebugThreadParam {
+    ActualPriority = 0,
+    State = 1,
+    IdealCore = 2,
+    CurrentCore = 3,
+    CoreMask = 4
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum ProcessInfoType {
+    ProcessState = 0
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum ProcessState {
+    Created = 0,
+    CreatedAttached = 1,
+    Running = 2,
+    Crashed = 3,
+    RunningAttached = 4,
+    Exiting = 5,
+    Exited = 6,
+    DebugSuspended = 7
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum KernelDebugType {
+    Thread = 0,
+    ThreadCallStack = 1,
+    KernelObject = 2,
+    Handle = 3,
+    Memory = 4,
+    PageTable = 5,
+    CpuUtilization = 6,
+    Process = 7,
+    SuspendProcess = 8,
+    ResumeProcess = 9,
+    Port = 10
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum KernelTraceState {
+    Disabled = 0,
+    Enabled = 1
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum IoPoolType {
+    PcieA2 = 0
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum MemoryMapping {
+    IoRegister = 0,
+    Uncached = 1,
+    Memory = 2
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum InterruptType {
+    Edge = 0,
+    Level = 1
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct LastThreadContext {
+    pub fp: u64,
+    pub sp: u64,
+    pub lr: u64,
+    pub pc: u64
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct PhysicalMemoryInfo {
+    pub physical_address: u64,
+    pub virtual_address: u64,
+    pub size: u64
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(C)]
+pub struct CreateProcessInfo {
+    pub name: util::CString<12>,
+    pub version: u32,
+    pub program_id: u64,
+    pub code_address: u64,
+    pub code_page_count: u32,
+    pub flags: u32,
+    pub resource_limit_handle: Handle,
+    pub system_resource_page_count: u32
+}
+const_assert!(core::mem::size_of::<CreateProcessInfo>() == 0x30);
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(C)]
 pub struct AttachProcessDebugEventInfo {
@@ -181,6 +352,86 @@ pub const INVALID_HANDLE: Handle = 0;
 pub const CURRENT_THREAD_PSEUDO_HANDLE: Handle = 0xFFFF8000;
 pub const CURRENT_PROCESS_PSEUDO_HANDLE: Handle = 0xFFFF8001;
 
+// Handles we own get closed when dropped, while borrowed ones can't outlive the handle they were borrowed from
+// Note: raw handles are still used where ownership is tracked elsewhere (like IPC sessions, which get closed through the IPC transport)
+
+#[derive(PartialEq, Eq, Debug)]
+#[repr(transparent)]
+pub struct OwnedHandle {
+    handle: Handle
+}
+
+impl OwnedHandle {
+    pub const fn invalid() -> Self {
+        Self { handle: INVALID_HANDLE }
+    }
+
+    pub const unsafe fn from_raw(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    pub const fn as_raw(&self) -> Handle {
+        self.handle
+    }
+
+    pub fn into_raw(self) -> Handle {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+
+    pub const fn is_valid(&self) -> bool {
+        self.handle != INVALID_HANDLE
+    }
+
+    pub const fn as_borrowed(&self) -> BorrowedHandle<'_> {
+        BorrowedHandle { handle: self.handle, phantom: PhantomData }
+    }
+}
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        if self.is_valid() {
+            // This might have been an IPC session handle
+            ipc::forget_pointer_buffer_size(self.handle);
+            let _ = close_handle(self.handle);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(transparent)]
+pub struct BorrowedHandle<'a> {
+    handle: Handle,
+    phantom: PhantomData<&'a OwnedHandle>
+}
+
+impl<'a> BorrowedHandle<'a> {
+    pub const unsafe fn borrow_raw(handle: Handle) -> Self {
+        Self { handle, phantom: PhantomData }
+    }
+
+    pub const fn invalid() -> Self {
+        Self { handle: INVALID_HANDLE, phantom: PhantomData }
+    }
+
+    pub const fn current_thread() -> Self {
+        Self { handle: CURRENT_THREAD_PSEUDO_HANDLE, phantom: PhantomData }
+    }
+
+    pub const fn current_process() -> Self {
+        Self { handle: CURRENT_PROCESS_PSEUDO_HANDLE, phantom: PhantomData }
+    }
+
+    pub const fn as_raw(&self) -> Handle {
+        self.handle
+    }
+
+    pub const fn is_valid(&self) -> bool {
+        self.handle != INVALID_HANDLE
+    }
+}
+
 #[inline(always)]
 pub fn set_heap_size(size: Size) -> Result<*mut u8> {
     extern "C" {
@@ -195,6 +446,18 @@ pub fn set_heap_size(size: Size) -> Result<*mut u8> {
     }
 }
 
+#[inline(always)]
+pub fn set_memory_permission(address: Address, size: Size, permission: MemoryPermission) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_memory_permission(address: Address, size: Size, permission: MemoryPermission) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_memory_permission(address, size, permission);
+        wrap(rc, ())
+    }
+}
+
 #[inline(always)]
 pub fn set_memory_attribute(address: Address, size: Size, mask: u32, value: MemoryAttribute) -> Result<()> {
     extern "C" {
@@ -207,6 +470,30 @@ pub fn set_memory_attribute(address: Address, size: Size, mask: u32, value: Memo
     }
 }
 
+#[inline(always)]
+pub fn map_memory(dst_address: Address, src_address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_memory(dst_address: Address, src_address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_memory(dst_address, src_address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_memory(dst_address: Address, src_address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_memory(dst_address: Address, src_address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_memory(dst_address, src_address, size);
+        wrap(rc, ())
+    }
+}
+
 #[inline(always)]
 pub fn query_memory(address: Address) -> Result<(MemoryInfo, PageInfo)> {
     extern "C" {
@@ -234,7 +521,7 @@ pub fn exit_process() -> ! {
 }
 
 #[inline(always)]
-pub fn create_thread(entry: ThreadEntrypointFn, entry_arg: Address, stack_top: Address, priority: i32, processor_id: i32) -> Result<Handle> {
+pub fn create_thread(entry: ThreadEntrypointFn, entry_arg: Address, stack_top: Address, priority: i32, processor_id: i32) -> Result<OwnedHandle> {
     extern "C" {
         fn __nx_svc_create_thread(handle: *mut Handle, entry: ThreadEntrypointFn, entry_arg: Address, stack_top: Address, priority: i32, processor_id: i32) -> ResultCode;
     }
@@ -243,18 +530,18 @@ pub fn create_thread(entry: ThreadEntrypointFn, entry_arg: Address, stack_top: A
         let mut handle: Handle = 0;
 
         let rc = __nx_svc_create_thread(&mut handle, entry, entry_arg, stack_top, priority, processor_id);
-        wrap(rc, handle)
+        wrap(rc, OwnedHandle::from_raw(handle))
     }
 }
 
 #[inline(always)]
-pub fn start_thread(handle: Handle) -> Result<()> {
+pub fn start_thread(handle: BorrowedHandle) -> Result<()> {
     extern "C" {
         fn __nx_svc_start_thread(handle: Handle) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_start_thread(handle);
+        let rc = __nx_svc_start_thread(handle.as_raw());
         wrap(rc, ())
     }
 }
@@ -283,7 +570,7 @@ pub fn sleep_thread(timeout: i64) -> Result<()> {
 }
 
 #[inline(always)]
-pub fn get_thread_priority(handle: Handle) -> Result<i32> {
+pub fn get_thread_priority(handle: BorrowedHandle) -> Result<i32> {
     extern "C" {
         fn __nx_svc_get_thread_priority(out_priority: *mut i32, handle: Handle) -> ResultCode;
     }
@@ -291,49 +578,111 @@ pub fn get_thread_priority(handle: Handle) -> Result<i32> {
     unsafe {
         let mut priority: i32 = 0;
 
-        let rc = __nx_svc_get_thread_priority(&mut priority, handle);
+        let rc = __nx_svc_get_thread_priority(&mut priority, handle.as_raw());
         wrap(rc, priority)
     }
 }
 
 #[inline(always)]
-pub fn signal_event(handle: Handle) -> Result<()> {
+pub fn set_thread_priority(handle: BorrowedHandle, priority: i32) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_thread_priority(handle: Handle, priority: i32) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_thread_priority(handle.as_raw(), priority);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn get_thread_core_mask(handle: BorrowedHandle) -> Result<(i32, u64)> {
+    extern "C" {
+        fn __nx_svc_get_thread_core_mask(out_ideal_core: *mut i32, out_affinity_mask: *mut u64, handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let mut ideal_core: i32 = 0;
+        let mut affinity_mask: u64 = 0;
+
+        let rc = __nx_svc_get_thread_core_mask(&mut ideal_core, &mut affinity_mask, handle.as_raw());
+        wrap(rc, (ideal_core, affinity_mask))
+    }
+}
+
+#[inline(always)]
+pub fn set_thread_core_mask(handle: BorrowedHandle, ideal_core: i32, affinity_mask: u64) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_thread_core_mask(handle: Handle, ideal_core: i32, affinity_mask: u64) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_thread_core_mask(handle.as_raw(), ideal_core, affinity_mask);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn get_current_processor_number() -> u32 {
+    extern "C" {
+        fn __nx_svc_get_current_processor_number() -> u32;
+    }
+
+    unsafe {
+        __nx_svc_get_current_processor_number()
+    }
+}
+
+#[inline(always)]
+pub fn signal_event(handle: BorrowedHandle) -> Result<()> {
     extern "C" {
         fn __nx_svc_signal_event(handle: Handle) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_signal_event(handle);
+        let rc = __nx_svc_signal_event(handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn clear_event(handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_clear_event(handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_clear_event(handle.as_raw());
         wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn map_shared_memory(handle: Handle, address: Address, size: Size, permission: MemoryPermission) -> Result<()> {
+pub fn map_shared_memory(handle: BorrowedHandle, address: Address, size: Size, permission: MemoryPermission) -> Result<()> {
     extern "C" {
         fn __nx_svc_map_shared_memory(handle: Handle, address: Address, size: Size, permission: MemoryPermission) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_map_shared_memory(handle, address, size, permission);
+        let rc = __nx_svc_map_shared_memory(handle.as_raw(), address, size, permission);
         wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn unmap_shared_memory(handle: Handle, address: Address, size: Size) -> Result<()> {
+pub fn unmap_shared_memory(handle: BorrowedHandle, address: Address, size: Size) -> Result<()> {
     extern "C" {
         fn __nx_svc_unmap_shared_memory(handle: Handle, address: Address, size: Size) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_unmap_shared_memory(handle, address, size);
+        let rc = __nx_svc_unmap_shared_memory(handle.as_raw(), address, size);
         wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn create_transfer_memory(address: Address, size: Size, permissions: MemoryPermission) -> Result<Handle> {
+pub fn create_transfer_memory(address: Address, size: Size, permissions: MemoryPermission) -> Result<OwnedHandle> {
     extern "C" {
         fn __nx_svc_create_transfer_memory(out_handle: *mut Handle, address: Address, size: Size, permissions: MemoryPermission) -> ResultCode;
     }
@@ -342,7 +691,7 @@ pub fn create_transfer_memory(address: Address, size: Size, permissions: MemoryP
         let mut handle: Handle = 0;
 
         let rc = __nx_svc_create_transfer_memory(&mut handle, address, size, permissions);
-        wrap(rc, handle)
+        wrap(rc, OwnedHandle::from_raw(handle))
     }
 }
 
@@ -359,13 +708,13 @@ pub fn close_handle(handle: Handle) -> Result<()> {
 }
 
 #[inline(always)]
-pub fn reset_signal(handle: Handle) -> Result<()> {
+pub fn reset_signal(handle: BorrowedHandle) -> Result<()> {
     extern "C" {
         fn __nx_svc_reset_signal(handle: Handle) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_reset_signal(handle);
+        let rc = __nx_svc_reset_signal(handle.as_raw());
         wrap(rc, ())
     }
 }
@@ -384,6 +733,18 @@ pub fn wait_synchronization(handles: *const Handle, handle_count: u32, timeout:
     }
 }
 
+#[inline(always)]
+pub fn cancel_synchronization(thread_handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_cancel_synchronization(thread_handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_cancel_synchronization(thread_handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
 #[inline(always)]
 pub fn arbitrate_lock(thread_handle: Handle, tag_location: Address, tag: u32) -> Result<()> {
     extern "C" {
@@ -409,7 +770,41 @@ pub fn arbitrate_unlock(tag_location: Address) -> Result<()> {
 }
 
 #[inline(always)]
-pub fn connect_to_named_port(name: Address) -> Result<Handle> {
+pub fn wait_process_wide_key_atomic(tag_location: Address, condvar_key: Address, tag: u32, timeout: i64) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_wait_process_wide_key_atomic(tag_location: Address, condvar_key: Address, tag: u32, timeout: i64) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_wait_process_wide_key_atomic(tag_location, condvar_key, tag, timeout);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn signal_process_wide_key(condvar_key: Address, count: i32) {
+    extern "C" {
+        fn __nx_svc_signal_process_wide_key(condvar_key: Address, count: i32);
+    }
+
+    unsafe {
+        __nx_svc_signal_process_wide_key(condvar_key, count);
+    }
+}
+
+#[inline(always)]
+pub fn get_system_tick() -> u64 {
+    extern "C" {
+        fn __nx_svc_get_system_tick() -> u64;
+    }
+
+    unsafe {
+        __nx_svc_get_system_tick()
+    }
+}
+
+#[inline(always)]
+pub fn connect_to_named_port(name: Address) -> Result<OwnedHandle> {
     extern "C" {
         fn __nx_svc_connect_to_named_port(out_handle: *mut Handle, name: Address) -> ResultCode;
     }
@@ -418,7 +813,19 @@ pub fn connect_to_named_port(name: Address) -> Result<Handle> {
         let mut handle: Handle = 0;
 
         let rc = __nx_svc_connect_to_named_port(&mut handle, name);
-        wrap(rc, handle)
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn send_sync_request_light(handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_send_sync_request_light(handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_send_sync_request_light(handle.as_raw());
+        wrap(rc, ())
     }
 }
 
@@ -435,7 +842,33 @@ pub fn send_sync_request(handle: Handle) -> Result<()> {
 }
 
 #[inline(always)]
-pub fn get_process_id(process_handle: Handle) -> Result<u64> {
+pub fn send_sync_request_with_user_buffer(buffer: *mut u8, buffer_size: Size, handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_send_sync_request_with_user_buffer(buffer: *mut u8, buffer_size: Size, handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_send_sync_request_with_user_buffer(buffer, buffer_size, handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn send_async_request_with_user_buffer(buffer: *mut u8, buffer_size: Size, handle: BorrowedHandle) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_send_async_request_with_user_buffer(out_event_handle: *mut Handle, buffer: *mut u8, buffer_size: Size, handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let mut event_handle: Handle = 0;
+
+        let rc = __nx_svc_send_async_request_with_user_buffer(&mut event_handle, buffer, buffer_size, handle.as_raw());
+        wrap(rc, OwnedHandle::from_raw(event_handle))
+    }
+}
+
+#[inline(always)]
+pub fn get_process_id(process_handle: BorrowedHandle) -> Result<u64> {
     extern "C" {
         fn __nx_svc_get_process_id(out_process_id: *mut u64, process_handle: Handle) -> ResultCode;
     }
@@ -443,13 +876,13 @@ pub fn get_process_id(process_handle: Handle) -> Result<u64> {
     unsafe {
         let mut process_id: u64 = 0;
 
-        let rc = __nx_svc_get_process_id(&mut process_id, process_handle);
+        let rc = __nx_svc_get_process_id(&mut process_id, process_handle.as_raw());
         wrap(rc, process_id)
     }
 }
 
 #[inline(always)]
-pub fn get_thread_id(handle: Handle) -> Result<u64> {
+pub fn get_thread_id(handle: BorrowedHandle) -> Result<u64> {
     extern "C" {
         fn __nx_svc_get_thread_id(out_thread_id: *mut u64, handle: Handle) -> ResultCode;
     }
@@ -457,7 +890,7 @@ pub fn get_thread_id(handle: Handle) -> Result<u64> {
     unsafe {
         let mut thread_id: u64 = 0;
 
-        let rc = __nx_svc_get_thread_id(&mut thread_id, handle);
+        let rc = __nx_svc_get_thread_id(&mut thread_id, handle.as_raw());
         wrap(rc, thread_id)
     }
 }
@@ -499,7 +932,7 @@ pub fn return_from_exception(res: ResultCode) {
 }
 
 #[inline(always)]
-pub fn get_info(id: InfoId, handle: Handle, sub_id: u64) -> Result<u64> {
+pub fn get_info(id: InfoId, handle: BorrowedHandle, sub_id: u64) -> Result<u64> {
     extern "C" {
         fn __nx_svc_get_info(out_info: *mut u64, id: InfoId, handle: Handle, sub_id: u64) -> ResultCode;
     }
@@ -507,71 +940,677 @@ pub fn get_info(id: InfoId, handle: Handle, sub_id: u64) -> Result<u64> {
     unsafe {
         let mut info: u64 = 0;
 
-        let rc = __nx_svc_get_info(&mut info, id, handle, sub_id);
+        let rc = __nx_svc_get_info(&mut info, id, handle.as_raw(), sub_id);
         wrap(rc, info)
     }
 }
 
 #[inline(always)]
-pub fn create_session(is_light: bool, unk_name: u64) -> Result<(Handle, Handle)> {
+pub fn flush_entire_data_cache() {
     extern "C" {
-        fn __nx_svc_create_session(out_server_handle: *mut Handle, out_client_handle: *mut Handle, is_light: bool, unk_name: u64) -> ResultCode;
+        fn __nx_svc_flush_entire_data_cache();
     }
 
     unsafe {
-        let mut server_handle: Handle = 0;
-        let mut client_handle: Handle = 0;
-
-        let rc = __nx_svc_create_session(&mut server_handle, &mut client_handle, is_light, unk_name);
-        wrap(rc, (server_handle, client_handle))
+        __nx_svc_flush_entire_data_cache();
     }
 }
 
 #[inline(always)]
-pub fn accept_session(handle: Handle) -> Result<Handle> {
+pub fn flush_data_cache(address: Address, size: Size) -> Result<()> {
     extern "C" {
-        fn __nx_svc_accept_session(out_session_handle: *mut Handle, handle: Handle) -> ResultCode;
+        fn __nx_svc_flush_data_cache(address: Address, size: Size) -> ResultCode;
     }
 
     unsafe {
-        let mut session_handle: Handle = 0;
-        
-        let rc = __nx_svc_accept_session(&mut session_handle, handle);
-        wrap(rc, session_handle)
+        let rc = __nx_svc_flush_data_cache(address, size);
+        wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn reply_and_receive(handles: *const Handle, handle_count: u32, reply_target: Handle, timeout: i64) -> Result<i32> {
+pub fn map_physical_memory(address: Address, size: Size) -> Result<()> {
     extern "C" {
-        fn __nx_svc_reply_and_receive(out_index: *mut i32, handles: *const Handle, handle_count: u32, reply_target: Handle, timeout: i64) -> ResultCode;
+        fn __nx_svc_map_physical_memory(address: Address, size: Size) -> ResultCode;
     }
 
     unsafe {
-        let mut index: i32 = 0;
-
-        let rc = __nx_svc_reply_and_receive(&mut index, handles, handle_count, reply_target, timeout);
+        let rc = __nx_svc_map_physical_memory(address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_physical_memory(address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_physical_memory(address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_physical_memory(address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn get_debug_future_thread_info(debug_handle: BorrowedHandle, timeout: i64) -> Result<(LastThreadContext, u64)> {
+    extern "C" {
+        fn __nx_svc_get_debug_future_thread_info(out_context: *mut LastThreadContext, out_thread_id: *mut u64, debug_handle: Handle, timeout: i64) -> ResultCode;
+    }
+
+    unsafe {
+        let mut context: LastThreadContext = Default::default();
+        let mut thread_id: u64 = 0;
+
+        let rc = __nx_svc_get_debug_future_thread_info(&mut context, &mut thread_id, debug_handle.as_raw(), timeout);
+        wrap(rc, (context, thread_id))
+    }
+}
+
+#[inline(always)]
+pub fn get_last_thread_info() -> Result<(LastThreadContext, usize, u32)> {
+    extern "C" {
+        fn __nx_svc_get_last_thread_info(out_context: *mut LastThreadContext, out_tls_address: *mut usize, out_flags: *mut u32) -> ResultCode;
+    }
+
+    unsafe {
+        let mut context: LastThreadContext = Default::default();
+        let mut tls_address: usize = 0;
+        let mut flags: u32 = 0;
+
+        let rc = __nx_svc_get_last_thread_info(&mut context, &mut tls_address, &mut flags);
+        wrap(rc, (context, tls_address, flags))
+    }
+}
+
+#[inline(always)]
+pub fn get_resource_limit_limit_value(resource_limit_handle: BorrowedHandle, resource: LimitableResource) -> Result<i64> {
+    extern "C" {
+        fn __nx_svc_get_resource_limit_limit_value(out_value: *mut i64, resource_limit_handle: Handle, resource: LimitableResource) -> ResultCode;
+    }
+
+    unsafe {
+        let mut value: i64 = 0;
+
+        let rc = __nx_svc_get_resource_limit_limit_value(&mut value, resource_limit_handle.as_raw(), resource);
+        wrap(rc, value)
+    }
+}
+
+#[inline(always)]
+pub fn get_resource_limit_current_value(resource_limit_handle: BorrowedHandle, resource: LimitableResource) -> Result<i64> {
+    extern "C" {
+        fn __nx_svc_get_resource_limit_current_value(out_value: *mut i64, resource_limit_handle: Handle, resource: LimitableResource) -> ResultCode;
+    }
+
+    unsafe {
+        let mut value: i64 = 0;
+
+        let rc = __nx_svc_get_resource_limit_current_value(&mut value, resource_limit_handle.as_raw(), resource);
+        wrap(rc, value)
+    }
+}
+
+#[inline(always)]
+pub fn set_thread_activity(thread_handle: BorrowedHandle, activity: ThreadActivity) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_thread_activity(thread_handle: Handle, activity: ThreadActivity) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_thread_activity(thread_handle.as_raw(), activity);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+#[allow(improper_ctypes)]
+pub fn get_thread_context3(thread_handle: BorrowedHandle) -> Result<arm::ThreadContext> {
+    extern "C" {
+        fn __nx_svc_get_thread_context3(out_context: *mut arm::ThreadContext, thread_handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let mut thread_context: arm::ThreadContext = Default::default();
+
+        let rc = __nx_svc_get_thread_context3(&mut thread_context, thread_handle.as_raw());
+        wrap(rc, thread_context)
+    }
+}
+
+#[inline(always)]
+pub fn wait_for_address(address: Address, arbitration_type: ArbitrationType, value: i32, timeout: i64) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_wait_for_address(address: Address, arbitration_type: ArbitrationType, value: i32, timeout: i64) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_wait_for_address(address, arbitration_type, value, timeout);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn signal_to_address(address: Address, signal_type: SignalType, value: i32, count: i32) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_signal_to_address(address: Address, signal_type: SignalType, value: i32, count: i32) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_signal_to_address(address, signal_type, value, count);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn synchronize_preemption_state() {
+    extern "C" {
+        fn __nx_svc_synchronize_preemption_state();
+    }
+
+    unsafe {
+        __nx_svc_synchronize_preemption_state();
+    }
+}
+
+#[inline(always)]
+pub fn get_resource_limit_peak_value(resource_limit_handle: BorrowedHandle, resource: LimitableResource) -> Result<i64> {
+    extern "C" {
+        fn __nx_svc_get_resource_limit_peak_value(out_value: *mut i64, resource_limit_handle: Handle, resource: LimitableResource) -> ResultCode;
+    }
+
+    unsafe {
+        let mut value: i64 = 0;
+
+        let rc = __nx_svc_get_resource_limit_peak_value(&mut value, resource_limit_handle.as_raw(), resource);
+        wrap(rc, value)
+    }
+}
+
+#[inline(always)]
+pub fn create_io_pool(pool_type: IoPoolType) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_io_pool(out_handle: *mut Handle, pool_type: IoPoolType) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_io_pool(&mut handle, pool_type);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn create_io_region(io_pool_handle: BorrowedHandle, physical_address: u64, size: Size, mapping: MemoryMapping, permission: MemoryPermission) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_io_region(out_handle: *mut Handle, io_pool_handle: Handle, physical_address: u64, size: Size, mapping: MemoryMapping, permission: MemoryPermission) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_io_region(&mut handle, io_pool_handle.as_raw(), physical_address, size, mapping, permission);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+// DumpInfo (1.0.0-3.0.2) got replaced by KernelDebug (4.0.0+) under the same SVC number, the latter only takes extra arguments
+
+#[inline(always)]
+pub fn kernel_debug(debug_type: KernelDebugType, arg0: u64, arg1: u64, arg2: u64) {
+    extern "C" {
+        fn __nx_svc_dump_info(dump_info_type: KernelDebugType, arg0: u64);
+        fn __nx_svc_kernel_debug(debug_type: KernelDebugType, arg0: u64, arg1: u64, arg2: u64);
+    }
+
+    unsafe {
+        if version::get_version() < version::Version::new(4, 0, 0) {
+            __nx_svc_dump_info(debug_type, arg0);
+        }
+        else {
+            __nx_svc_kernel_debug(debug_type, arg0, arg1, arg2);
+        }
+    }
+}
+
+#[inline(always)]
+pub fn change_kernel_trace_state(state: KernelTraceState) {
+    extern "C" {
+        fn __nx_svc_change_kernel_trace_state(state: KernelTraceState);
+    }
+
+    unsafe {
+        __nx_svc_change_kernel_trace_state(state);
+    }
+}
+
+#[inline(always)]
+pub fn create_session(is_light: bool, unk_name: u64) -> Result<(OwnedHandle, OwnedHandle)> {
+    extern "C" {
+        fn __nx_svc_create_session(out_server_handle: *mut Handle, out_client_handle: *mut Handle, is_light: bool, unk_name: u64) -> ResultCode;
+    }
+
+    unsafe {
+        let mut server_handle: Handle = 0;
+        let mut client_handle: Handle = 0;
+
+        let rc = __nx_svc_create_session(&mut server_handle, &mut client_handle, is_light, unk_name);
+        wrap(rc, (OwnedHandle::from_raw(server_handle), OwnedHandle::from_raw(client_handle)))
+    }
+}
+
+#[inline(always)]
+pub fn accept_session(handle: BorrowedHandle) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_accept_session(out_session_handle: *mut Handle, handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let mut session_handle: Handle = 0;
+        
+        let rc = __nx_svc_accept_session(&mut session_handle, handle.as_raw());
+        wrap(rc, OwnedHandle::from_raw(session_handle))
+    }
+}
+
+#[inline(always)]
+pub fn reply_and_receive_light(handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_reply_and_receive_light(handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_reply_and_receive_light(handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn reply_and_receive(handles: *const Handle, handle_count: u32, reply_target: Handle, timeout: i64) -> Result<i32> {
+    extern "C" {
+        fn __nx_svc_reply_and_receive(out_index: *mut i32, handles: *const Handle, handle_count: u32, reply_target: Handle, timeout: i64) -> ResultCode;
+    }
+
+    unsafe {
+        let mut index: i32 = 0;
+
+        let rc = __nx_svc_reply_and_receive(&mut index, handles, handle_count, reply_target, timeout);
+        wrap(rc, index)
+    }
+}
+
+#[inline(always)]
+pub fn reply_and_receive_with_user_buffer(buffer: *mut u8, buffer_size: Size, handles: *const Handle, handle_count: u32, reply_target: Handle, timeout: i64) -> Result<i32> {
+    extern "C" {
+        fn __nx_svc_reply_and_receive_with_user_buffer(out_index: *mut i32, buffer: *mut u8, buffer_size: Size, handles: *const Handle, handle_count: u32, reply_target: Handle, timeout: i64) -> ResultCode;
+    }
+
+    unsafe {
+        let mut index: i32 = 0;
+
+        let rc = __nx_svc_reply_and_receive_with_user_buffer(&mut index, buffer, buffer_size, handles, handle_count, reply_target, timeout);
         wrap(rc, index)
     }
 }
 
 #[inline(always)]
-pub fn create_event() -> Result<(Handle, Handle)> {
+pub fn create_event() -> Result<(OwnedHandle, OwnedHandle)> {
+    extern "C" {
+        fn __nx_svc_create_event(out_server_handle: *mut Handle, out_client_handle: *mut Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let mut server_handle: Handle = 0;
+        let mut client_handle: Handle = 0;
+
+        let rc = __nx_svc_create_event(&mut server_handle, &mut client_handle);
+        wrap(rc, (OwnedHandle::from_raw(server_handle), OwnedHandle::from_raw(client_handle)))
+    }
+}
+
+// SVCs 0x48/0x49 were MapPhysicalMemoryUnsafe/UnmapPhysicalMemoryUnsafe (5.0.0-12.1.0) before becoming MapIoRegion/UnmapIoRegion (13.0.0+)
+
+#[inline(always)]
+pub fn map_physical_memory_unsafe(address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_physical_memory_unsafe(address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_physical_memory_unsafe(address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_physical_memory_unsafe(address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_physical_memory_unsafe(address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_physical_memory_unsafe(address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn map_io_region(io_region_handle: BorrowedHandle, address: Address, size: Size, permission: MemoryPermission) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_io_region(io_region_handle: Handle, address: Address, size: Size, permission: MemoryPermission) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_io_region(io_region_handle.as_raw(), address, size, permission);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_io_region(io_region_handle: BorrowedHandle, address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_io_region(io_region_handle: Handle, address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_io_region(io_region_handle.as_raw(), address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn set_unsafe_limit(size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_unsafe_limit(size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_unsafe_limit(size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn create_code_memory(address: Address, size: Size) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_code_memory(out_handle: *mut Handle, address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_code_memory(&mut handle, address, size);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn control_code_memory(code_memory_handle: BorrowedHandle, operation: CodeMapOperation, address: Address, size: Size, permission: MemoryPermission) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_control_code_memory(code_memory_handle: Handle, operation: CodeMapOperation, address: Address, size: Size, permission: u64) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_control_code_memory(code_memory_handle.as_raw(), operation, address, size, permission.get() as u64);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn sleep_system() {
+    extern "C" {
+        fn __nx_svc_sleep_system();
+    }
+
+    unsafe {
+        __nx_svc_sleep_system();
+    }
+}
+
+#[inline(always)]
+pub fn read_write_register(register_address: u64, mask: u32, value: u32) -> Result<u32> {
+    extern "C" {
+        fn __nx_svc_read_write_register(out_value: *mut u32, register_address: u64, mask: u32, value: u32) -> ResultCode;
+    }
+
+    unsafe {
+        let mut out_value: u32 = 0;
+
+        let rc = __nx_svc_read_write_register(&mut out_value, register_address, mask, value);
+        wrap(rc, out_value)
+    }
+}
+
+#[inline(always)]
+pub fn set_process_activity(process_handle: BorrowedHandle, activity: ProcessActivity) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_process_activity(process_handle: Handle, activity: ProcessActivity) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_process_activity(process_handle.as_raw(), activity);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn create_shared_memory(size: Size, local_permission: MemoryPermission, remote_permission: MemoryPermission) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_shared_memory(out_handle: *mut Handle, size: Size, local_permission: MemoryPermission, remote_permission: MemoryPermission) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_shared_memory(&mut handle, size, local_permission, remote_permission);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn map_transfer_memory(handle: BorrowedHandle, address: Address, size: Size, permission: MemoryPermission) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_transfer_memory(handle: Handle, address: Address, size: Size, permission: MemoryPermission) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_transfer_memory(handle.as_raw(), address, size, permission);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_transfer_memory(handle: BorrowedHandle, address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_transfer_memory(handle: Handle, address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_transfer_memory(handle.as_raw(), address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn create_interrupt_event(irq_number: u64, interrupt_type: InterruptType) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_interrupt_event(out_handle: *mut Handle, irq_number: u64, interrupt_type: InterruptType) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_interrupt_event(&mut handle, irq_number, interrupt_type);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn query_physical_address(address: Address) -> Result<PhysicalMemoryInfo> {
+    extern "C" {
+        fn __nx_svc_query_physical_address(out_info: *mut PhysicalMemoryInfo, address: Address) -> ResultCode;
+    }
+
+    unsafe {
+        let mut info: PhysicalMemoryInfo = Default::default();
+
+        let rc = __nx_svc_query_physical_address(&mut info, address);
+        wrap(rc, info)
+    }
+}
+
+// Before 10.0.0 the mapping size isn't returned, thus we just return the one we asked for
+
+#[inline(always)]
+pub fn query_io_mapping(physical_address: u64, size: Size) -> Result<(usize, Size)> {
+    extern "C" {
+        fn __nx_svc_legacy_query_io_mapping(out_address: *mut usize, physical_address: u64, size: Size) -> ResultCode;
+        fn __nx_svc_query_io_mapping(out_address: *mut usize, out_size: *mut Size, physical_address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let mut address: usize = 0;
+
+        if version::get_version() < version::Version::new(10, 0, 0) {
+            let rc = __nx_svc_legacy_query_io_mapping(&mut address, physical_address, size);
+            wrap(rc, (address, size))
+        }
+        else {
+            let mut mapping_size: Size = 0;
+
+            let rc = __nx_svc_query_io_mapping(&mut address, &mut mapping_size, physical_address, size);
+            wrap(rc, (address, mapping_size))
+        }
+    }
+}
+
+#[inline(always)]
+pub fn create_device_address_space(address: u64, size: u64) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_device_address_space(out_handle: *mut Handle, address: u64, size: u64) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_device_address_space(&mut handle, address, size);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn attach_device_address_space(device_name: u64, handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_attach_device_address_space(device_name: u64, handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_attach_device_address_space(device_name, handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn detach_device_address_space(device_name: u64, handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_detach_device_address_space(device_name: u64, handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_detach_device_address_space(device_name, handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn map_device_address_space_by_force(handle: BorrowedHandle, process_handle: BorrowedHandle, address: u64, size: Size, device_address: u64, option: u32) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_device_address_space_by_force(handle: Handle, process_handle: Handle, address: u64, size: Size, device_address: u64, option: u32) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_device_address_space_by_force(handle.as_raw(), process_handle.as_raw(), address, size, device_address, option);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn map_device_address_space_aligned(handle: BorrowedHandle, process_handle: BorrowedHandle, address: u64, size: Size, device_address: u64, option: u32) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_device_address_space_aligned(handle: Handle, process_handle: Handle, address: u64, size: Size, device_address: u64, option: u32) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_device_address_space_aligned(handle.as_raw(), process_handle.as_raw(), address, size, device_address, option);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn map_device_address_space(handle: BorrowedHandle, process_handle: BorrowedHandle, address: u64, size: Size, device_address: u64, permission: MemoryPermission) -> Result<Size> {
+    extern "C" {
+        fn __nx_svc_map_device_address_space(out_mapped_size: *mut Size, handle: Handle, process_handle: Handle, address: u64, size: Size, device_address: u64, permission: MemoryPermission) -> ResultCode;
+    }
+
+    unsafe {
+        let mut mapped_size: Size = 0;
+
+        let rc = __nx_svc_map_device_address_space(&mut mapped_size, handle.as_raw(), process_handle.as_raw(), address, size, device_address, permission);
+        wrap(rc, mapped_size)
+    }
+}
+
+#[inline(always)]
+pub fn unmap_device_address_space(handle: BorrowedHandle, process_handle: BorrowedHandle, address: u64, size: Size, device_address: u64) -> Result<()> {
     extern "C" {
-        fn __nx_svc_create_event(out_server_handle: *mut Handle, out_client_handle: *mut Handle) -> ResultCode;
+        fn __nx_svc_unmap_device_address_space(handle: Handle, process_handle: Handle, address: u64, size: Size, device_address: u64) -> ResultCode;
     }
 
     unsafe {
-        let mut server_handle: Handle = 0;
-        let mut client_handle: Handle = 0;
+        let rc = __nx_svc_unmap_device_address_space(handle.as_raw(), process_handle.as_raw(), address, size, device_address);
+        wrap(rc, ())
+    }
+}
 
-        let rc = __nx_svc_create_event(&mut server_handle, &mut client_handle);
-        wrap(rc, (server_handle, client_handle))
+#[inline(always)]
+pub fn invalidate_process_data_cache(process_handle: BorrowedHandle, address: u64, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_invalidate_process_data_cache(process_handle: Handle, address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_invalidate_process_data_cache(process_handle.as_raw(), address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn store_process_data_cache(process_handle: BorrowedHandle, address: u64, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_store_process_data_cache(process_handle: Handle, address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_store_process_data_cache(process_handle.as_raw(), address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn flush_process_data_cache(process_handle: BorrowedHandle, address: u64, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_flush_process_data_cache(process_handle: Handle, address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_flush_process_data_cache(process_handle.as_raw(), address, size);
+        wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn debug_active_process(process_id: u64) -> Result<Handle> {
+pub fn debug_active_process(process_id: u64) -> Result<OwnedHandle> {
     extern "C" {
         fn __nx_svc_debug_active_process(out_handle: *mut Handle, process_id: u64) -> ResultCode;
     }
@@ -580,24 +1619,36 @@ pub fn debug_active_process(process_id: u64) -> Result<Handle> {
         let mut handle: Handle = 0;
 
         let rc = __nx_svc_debug_active_process(&mut handle, process_id);
-        wrap(rc, handle)
+        wrap(rc, OwnedHandle::from_raw(handle))
     }
 }
 
 #[inline(always)]
-pub fn break_debug_process(debug_handle: Handle) -> Result<()> {
+pub fn break_debug_process(debug_handle: BorrowedHandle) -> Result<()> {
     extern "C" {
         fn __nx_svc_break_debug_process(debug_handle: Handle) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_break_debug_process(debug_handle);
+        let rc = __nx_svc_break_debug_process(debug_handle.as_raw());
         wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn get_debug_event(debug_handle: Handle) -> Result<DebugEvent> {
+pub fn terminate_debug_process(debug_handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_terminate_debug_process(debug_handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_terminate_debug_process(debug_handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn get_debug_event(debug_handle: BorrowedHandle) -> Result<DebugEvent> {
     extern "C" {
         fn __nx_svc_get_debug_event(out_debug_event: *mut DebugEvent, debug_handle: Handle) -> ResultCode;
     }
@@ -605,13 +1656,13 @@ pub fn get_debug_event(debug_handle: Handle) -> Result<DebugEvent> {
     unsafe {
         let mut debug_event: DebugEvent = mem::zeroed();
 
-        let rc = __nx_svc_get_debug_event(&mut debug_event, debug_handle);
+        let rc = __nx_svc_get_debug_event(&mut debug_event, debug_handle.as_raw());
         wrap(rc, debug_event)
     }
 }
 
 #[inline(always)]
-pub fn continue_debug_event(debug_handle: Handle, flags: u32, thread_ids: &[u64]) -> Result<()> {
+pub fn continue_debug_event(debug_handle: BorrowedHandle, flags: u32, thread_ids: &[u64]) -> Result<()> {
     extern "C" {
         fn __nx_svc_legacy_continue_debug_event(debug_handle: Handle, flags: u32, thread_id: u64) -> ResultCode;
         fn __nx_svc_continue_debug_event(debug_handle: Handle, flags: u32, thread_ids: *const u64, thread_id_count: u32) -> ResultCode;
@@ -619,11 +1670,11 @@ pub fn continue_debug_event(debug_handle: Handle, flags: u32, thread_ids: &[u64]
 
     unsafe {
         if version::get_version() < version::Version::new(3, 0, 0) {
-            let rc = __nx_svc_legacy_continue_debug_event(debug_handle, flags, thread_ids[0]);
+            let rc = __nx_svc_legacy_continue_debug_event(debug_handle.as_raw(), flags, thread_ids[0]);
             wrap(rc, ())
         }
         else {
-            let rc = __nx_svc_continue_debug_event(debug_handle, flags, thread_ids.as_ptr(), thread_ids.len() as u32);
+            let rc = __nx_svc_continue_debug_event(debug_handle.as_raw(), flags, thread_ids.as_ptr(), thread_ids.len() as u32);
             wrap(rc, ())
         }
     }
@@ -644,7 +1695,7 @@ pub fn get_process_list(process_list: &mut [u64]) -> Result<usize> {
 }
 
 #[inline(always)]
-pub fn get_thread_list(debug_handle: Handle, thread_id_list: &mut [u64]) -> Result<usize> {
+pub fn get_thread_list(debug_handle: BorrowedHandle, thread_id_list: &mut [u64]) -> Result<usize> {
     extern "C" {
         fn __nx_svc_get_thread_list(out_count: *mut u32, out_thread_ids: *mut u64, thread_id_count: u32, debug_handle: Handle) -> ResultCode;
     }
@@ -652,14 +1703,14 @@ pub fn get_thread_list(debug_handle: Handle, thread_id_list: &mut [u64]) -> Resu
     unsafe {
         let mut count: u32 = 0;
         
-        let rc = __nx_svc_get_thread_list(&mut count, thread_id_list.as_mut_ptr(), thread_id_list.len() as u32, debug_handle);
+        let rc = __nx_svc_get_thread_list(&mut count, thread_id_list.as_mut_ptr(), thread_id_list.len() as u32, debug_handle.as_raw());
         wrap(rc, count as usize)
     }
 }
 
 #[inline(always)]
 #[allow(improper_ctypes)]
-pub fn get_debug_thread_context(debug_handle: Handle, thread_id: u64, register_group: arm::RegisterGroup) -> Result<arm::ThreadContext>  {
+pub fn get_debug_thread_context(debug_handle: BorrowedHandle, thread_id: u64, register_group: arm::RegisterGroup) -> Result<arm::ThreadContext>  {
     extern "C" {
         fn __nx_svc_get_debug_thread_context(thread_context: *mut arm::ThreadContext, debug_handle: Handle, thread_id: u64, register_group: arm::RegisterGroup) -> ResultCode;
     }
@@ -667,26 +1718,26 @@ pub fn get_debug_thread_context(debug_handle: Handle, thread_id: u64, register_g
     unsafe {
         let mut thread_context: arm::ThreadContext = Default::default();
 
-        let rc = __nx_svc_get_debug_thread_context(&mut thread_context, debug_handle, thread_id, register_group);
+        let rc = __nx_svc_get_debug_thread_context(&mut thread_context, debug_handle.as_raw(), thread_id, register_group);
         wrap(rc, thread_context)
     }
 }
 
 #[inline(always)]
 #[allow(improper_ctypes)]
-pub fn set_debug_thread_context(debug_handle: Handle, thread_context: arm::ThreadContext, thread_id: u64, register_group: arm::RegisterGroup) -> Result<()>  {
+pub fn set_debug_thread_context(debug_handle: BorrowedHandle, thread_context: arm::ThreadContext, thread_id: u64, register_group: arm::RegisterGroup) -> Result<()>  {
     extern "C" {
         fn __nx_svc_set_debug_thread_context(debug_handle: Handle, thread_id: u64, thread_context: *const arm::ThreadContext, register_group: arm::RegisterGroup) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_set_debug_thread_context(debug_handle, thread_id, &thread_context as *const _, register_group);
+        let rc = __nx_svc_set_debug_thread_context(debug_handle.as_raw(), thread_id, &thread_context as *const _, register_group);
         wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn query_debug_process_memory(debug_handle: Handle, address: Address) -> Result<(MemoryInfo, PageInfo)> {
+pub fn query_debug_process_memory(debug_handle: BorrowedHandle, address: Address) -> Result<(MemoryInfo, PageInfo)> {
     extern "C" {
         fn __nx_svc_query_debug_process_memory(out_info: *mut MemoryInfo, out_page_info: *mut PageInfo, debug_handle: Handle, address: Address) -> ResultCode;
     }
@@ -695,37 +1746,93 @@ pub fn query_debug_process_memory(debug_handle: Handle, address: Address) -> Res
         let mut memory_info: MemoryInfo = Default::default();
         let mut page_info: PageInfo = 0;
 
-        let rc = __nx_svc_query_debug_process_memory(&mut memory_info, &mut page_info, debug_handle, address);
+        let rc = __nx_svc_query_debug_process_memory(&mut memory_info, &mut page_info, debug_handle.as_raw(), address);
         wrap(rc, (memory_info, page_info))
     }
 }
 
 #[inline(always)]
-pub fn read_debug_process_memory(debug_handle: Handle, read_address: usize, read_size: usize, buffer: *mut u8) -> Result<()> {
+pub fn read_debug_process_memory(debug_handle: BorrowedHandle, read_address: usize, read_size: usize, buffer: *mut u8) -> Result<()> {
     extern "C" {
         fn __nx_svc_read_debug_process_memory(buffer: *mut u8, debug_handle: Handle, address: usize, size: usize) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_read_debug_process_memory(buffer, debug_handle, read_address, read_size);
+        let rc = __nx_svc_read_debug_process_memory(buffer, debug_handle.as_raw(), read_address, read_size);
         wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn write_debug_process_memory(debug_handle: Handle, write_address: usize, write_size: usize, buffer: Address) -> Result<()> {
+pub fn write_debug_process_memory(debug_handle: BorrowedHandle, write_address: usize, write_size: usize, buffer: Address) -> Result<()> {
     extern "C" {
         fn __nx_svc_write_debug_process_memory(debug_handle: Handle, buffer: Address, address: usize, size: usize) -> ResultCode;
     }
 
     unsafe {
-        let rc = __nx_svc_write_debug_process_memory(debug_handle, buffer, write_address, write_size);
+        let rc = __nx_svc_write_debug_process_memory(debug_handle.as_raw(), buffer, write_address, write_size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn set_hardware_break_point(which: u32, flags: u64, value: u64) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_hardware_break_point(which: u32, flags: u64, value: u64) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_hardware_break_point(which, flags, value);
         wrap(rc, ())
     }
 }
 
 #[inline(always)]
-pub fn manage_named_port(name: Address, max_sessions: i32) -> Result<Handle> {
+pub fn get_debug_thread_param(debug_handle: BorrowedHandle, thread_id: u64, param: DebugThreadParam) -> Result<(u64, u32)> {
+    extern "C" {
+        fn __nx_svc_get_debug_thread_param(out_value_64: *mut u64, out_value_32: *mut u32, debug_handle: Handle, thread_id: u64, param: DebugThreadParam) -> ResultCode;
+    }
+
+    unsafe {
+        let mut value_64: u64 = 0;
+        let mut value_32: u32 = 0;
+
+        let rc = __nx_svc_get_debug_thread_param(&mut value_64, &mut value_32, debug_handle.as_raw(), thread_id, param);
+        wrap(rc, (value_64, value_32))
+    }
+}
+
+#[inline(always)]
+pub fn get_system_info(id: SystemInfoId, handle: BorrowedHandle, sub_id: u64) -> Result<u64> {
+    extern "C" {
+        fn __nx_svc_get_system_info(out_info: *mut u64, id: SystemInfoId, handle: Handle, sub_id: u64) -> ResultCode;
+    }
+
+    unsafe {
+        let mut info: u64 = 0;
+
+        let rc = __nx_svc_get_system_info(&mut info, id, handle.as_raw(), sub_id);
+        wrap(rc, info)
+    }
+}
+
+#[inline(always)]
+pub fn create_port(max_sessions: i32, is_light: bool, name: Address) -> Result<(OwnedHandle, OwnedHandle)> {
+    extern "C" {
+        fn __nx_svc_create_port(out_server_handle: *mut Handle, out_client_handle: *mut Handle, max_sessions: i32, is_light: bool, name: Address) -> ResultCode;
+    }
+
+    unsafe {
+        let mut server_handle: Handle = 0;
+        let mut client_handle: Handle = 0;
+
+        let rc = __nx_svc_create_port(&mut server_handle, &mut client_handle, max_sessions, is_light, name);
+        wrap(rc, (OwnedHandle::from_raw(server_handle), OwnedHandle::from_raw(client_handle)))
+    }
+}
+
+#[inline(always)]
+pub fn manage_named_port(name: Address, max_sessions: i32) -> Result<OwnedHandle> {
     extern "C" {
         fn __nx_svc_manage_named_port(out_handle: *mut Handle, name: Address, max_sessions: i32) -> ResultCode;
     }
@@ -734,7 +1841,182 @@ pub fn manage_named_port(name: Address, max_sessions: i32) -> Result<Handle> {
         let mut handle: Handle = 0;
 
         let rc = __nx_svc_manage_named_port(&mut handle, name, max_sessions);
-        wrap(rc, handle)
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+// Managing a named port with no sessions unregisters it (no handle is returned then)
+
+#[inline(always)]
+pub fn unmanage_named_port(name: Address) -> Result<()> {
+    manage_named_port(name, 0)?;
+    Ok(())
+}
+
+#[inline(always)]
+pub fn connect_to_port(port_handle: BorrowedHandle) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_connect_to_port(out_handle: *mut Handle, port_handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_connect_to_port(&mut handle, port_handle.as_raw());
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn set_process_memory_permission(process_handle: BorrowedHandle, address: u64, size: Size, permission: MemoryPermission) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_process_memory_permission(process_handle: Handle, address: u64, size: Size, permission: MemoryPermission) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_process_memory_permission(process_handle.as_raw(), address, size, permission);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn map_process_memory(dst_address: Address, process_handle: BorrowedHandle, src_address: u64, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_process_memory(dst_address: Address, process_handle: Handle, src_address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_process_memory(dst_address, process_handle.as_raw(), src_address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_process_memory(dst_address: Address, process_handle: BorrowedHandle, src_address: u64, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_process_memory(dst_address: Address, process_handle: Handle, src_address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_process_memory(dst_address, process_handle.as_raw(), src_address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn query_process_memory(process_handle: BorrowedHandle, address: u64) -> Result<(MemoryInfo, PageInfo)> {
+    extern "C" {
+        fn __nx_svc_query_process_memory(out_info: *mut MemoryInfo, out_page_info: *mut PageInfo, process_handle: Handle, address: u64) -> ResultCode;
+    }
+
+    unsafe {
+        let mut memory_info: MemoryInfo = Default::default();
+        let mut page_info: PageInfo = 0;
+
+        let rc = __nx_svc_query_process_memory(&mut memory_info, &mut page_info, process_handle.as_raw(), address);
+        wrap(rc, (memory_info, page_info))
+    }
+}
+
+#[inline(always)]
+pub fn map_process_code_memory(process_handle: BorrowedHandle, dst_address: u64, src_address: u64, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_process_code_memory(process_handle: Handle, dst_address: u64, src_address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_process_code_memory(process_handle.as_raw(), dst_address, src_address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_process_code_memory(process_handle: BorrowedHandle, dst_address: u64, src_address: u64, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_process_code_memory(process_handle: Handle, dst_address: u64, src_address: u64, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_process_code_memory(process_handle.as_raw(), dst_address, src_address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn create_process(info: &CreateProcessInfo, capabilities: &[u32]) -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_process(out_handle: *mut Handle, info: *const CreateProcessInfo, capabilities: *const u32, capability_count: u32) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_process(&mut handle, info, capabilities.as_ptr(), capabilities.len() as u32);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn start_process(process_handle: BorrowedHandle, main_thread_priority: i32, default_cpu_core: i32, main_thread_stack_size: u32) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_start_process(process_handle: Handle, main_thread_priority: i32, default_cpu_core: i32, main_thread_stack_size: u32) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_start_process(process_handle.as_raw(), main_thread_priority, default_cpu_core, main_thread_stack_size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn terminate_process(process_handle: BorrowedHandle) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_terminate_process(process_handle: Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_terminate_process(process_handle.as_raw());
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn get_process_info(process_handle: BorrowedHandle, info_type: ProcessInfoType) -> Result<i64> {
+    extern "C" {
+        fn __nx_svc_get_process_info(out_info: *mut i64, process_handle: Handle, info_type: ProcessInfoType) -> ResultCode;
+    }
+
+    unsafe {
+        let mut info: i64 = 0;
+
+        let rc = __nx_svc_get_process_info(&mut info, process_handle.as_raw(), info_type);
+        wrap(rc, info)
+    }
+}
+
+#[inline(always)]
+pub fn create_resource_limit() -> Result<OwnedHandle> {
+    extern "C" {
+        fn __nx_svc_create_resource_limit(out_handle: *mut Handle) -> ResultCode;
+    }
+
+    unsafe {
+        let mut handle: Handle = 0;
+
+        let rc = __nx_svc_create_resource_limit(&mut handle);
+        wrap(rc, OwnedHandle::from_raw(handle))
+    }
+}
+
+#[inline(always)]
+pub fn set_resource_limit_limit_value(resource_limit_handle: BorrowedHandle, resource: LimitableResource, value: i64) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_set_resource_limit_limit_value(resource_limit_handle: Handle, resource: LimitableResource, value: i64) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_set_resource_limit_limit_value(resource_limit_handle.as_raw(), resource, value);
+        wrap(rc, ())
     }
 }
 
@@ -751,3 +2033,442 @@ pub fn call_secure_monitor(input: smc::Input) -> smc::Output {
         args.to_output()
     }
 }
+
+#[inline(always)]
+pub fn map_insecure_physical_memory(address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_map_insecure_physical_memory(address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_map_insecure_physical_memory(address, size);
+        wrap(rc, ())
+    }
+}
+
+#[inline(always)]
+pub fn unmap_insecure_physical_memory(address: Address, size: Size) -> Result<()> {
+    extern "C" {
+        fn __nx_svc_unmap_insecure_physical_memory(address: Address, size: Size) -> ResultCode;
+    }
+
+    unsafe {
+        let rc = __nx_svc_unmap_insecure_physical_memory(address, size);
+        wrap(rc, ())
+    }
+}
+
+// Every stub in svc.s (without the "__nx_svc_" prefix) along with its SVC number, in the same order they're defined there
+// Tests check this table against both the stubs and the official Horizon SVC numbers, thus any stub added, removed or renumbered in only one place gets caught
+
+pub const SVC_TABLE: &[(&str, u32)] = &[
+    ("set_heap_size", 0x1),
+    ("set_memory_permission", 0x2),
+    ("set_memory_attribute", 0x3),
+    ("map_memory", 0x4),
+    ("unmap_memory", 0x5),
+    ("query_memory", 0x6),
+    ("exit_process", 0x7),
+    ("create_thread", 0x8),
+    ("start_thread", 0x9),
+    ("exit_thread", 0xA),
+    ("sleep_thread", 0xB),
+    ("get_thread_priority", 0xC),
+    ("set_thread_priority", 0xD),
+    ("get_thread_core_mask", 0xE),
+    ("set_thread_core_mask", 0xF),
+    ("get_current_processor_number", 0x10),
+    ("signal_event", 0x11),
+    ("clear_event", 0x12),
+    ("map_shared_memory", 0x13),
+    ("unmap_shared_memory", 0x14),
+    ("create_transfer_memory", 0x15),
+    ("close_handle", 0x16),
+    ("reset_signal", 0x17),
+    ("wait_synchronization", 0x18),
+    ("cancel_synchronization", 0x19),
+    ("arbitrate_lock", 0x1A),
+    ("arbitrate_unlock", 0x1B),
+    ("wait_process_wide_key_atomic", 0x1C),
+    ("signal_process_wide_key", 0x1D),
+    ("get_system_tick", 0x1E),
+    ("connect_to_named_port", 0x1F),
+    ("send_sync_request_light", 0x20),
+    ("send_sync_request", 0x21),
+    ("send_sync_request_with_user_buffer", 0x22),
+    ("send_async_request_with_user_buffer", 0x23),
+    ("get_process_id", 0x24),
+    ("get_thread_id", 0x25),
+    ("break", 0x26),
+    ("output_debug_string", 0x27),
+    ("return_from_exception", 0x28),
+    ("get_info", 0x29),
+    ("flush_entire_data_cache", 0x2A),
+    ("flush_data_cache", 0x2B),
+    ("map_physical_memory", 0x2C),
+    ("unmap_physical_memory", 0x2D),
+    ("get_debug_future_thread_info", 0x2E),
+    ("get_last_thread_info", 0x2F),
+    ("get_resource_limit_limit_value", 0x30),
+    ("get_resource_limit_current_value", 0x31),
+    ("set_thread_activity", 0x32),
+    ("get_thread_context3", 0x33),
+    ("wait_for_address", 0x34),
+    ("signal_to_address", 0x35),
+    ("synchronize_preemption_state", 0x36),
+    ("get_resource_limit_peak_value", 0x37),
+    ("create_io_pool", 0x39),
+    ("create_io_region", 0x3A),
+    ("dump_info", 0x3C),
+    ("kernel_debug", 0x3C),
+    ("change_kernel_trace_state", 0x3D),
+    ("create_session", 0x40),
+    ("accept_session", 0x41),
+    ("reply_and_receive_light", 0x42),
+    ("reply_and_receive", 0x43),
+    ("reply_and_receive_with_user_buffer", 0x44),
+    ("create_event", 0x45),
+    ("map_io_region", 0x46),
+    ("unmap_io_region", 0x47),
+    ("map_physical_memory_unsafe", 0x48),
+    ("unmap_physical_memory_unsafe", 0x49),
+    ("set_unsafe_limit", 0x4A),
+    ("create_code_memory", 0x4B),
+    ("control_code_memory", 0x4C),
+    ("sleep_system", 0x4D),
+    ("read_write_register", 0x4E),
+    ("set_process_activity", 0x4F),
+    ("create_shared_memory", 0x50),
+    ("map_transfer_memory", 0x51),
+    ("unmap_transfer_memory", 0x52),
+    ("create_interrupt_event", 0x53),
+    ("query_physical_address", 0x54),
+    ("query_io_mapping", 0x55),
+    ("legacy_query_io_mapping", 0x55),
+    ("create_device_address_space", 0x56),
+    ("attach_device_address_space", 0x57),
+    ("detach_device_address_space", 0x58),
+    ("map_device_address_space_by_force", 0x59),
+    ("map_device_address_space_aligned", 0x5A),
+    ("map_device_address_space", 0x5B),
+    ("unmap_device_address_space", 0x5C),
+    ("invalidate_process_data_cache", 0x5D),
+    ("store_process_data_cache", 0x5E),
+    ("flush_process_data_cache", 0x5F),
+    ("debug_active_process", 0x60),
+    ("break_debug_process", 0x61),
+    ("terminate_debug_process", 0x62),
+    ("get_debug_event", 0x63),
+    ("legacy_continue_debug_event", 0x64),
+    ("continue_debug_event", 0x64),
+    ("get_process_list", 0x65),
+    ("get_thread_list", 0x66),
+    ("get_debug_thread_context", 0x67),
+    ("set_debug_thread_context", 0x68),
+    ("query_debug_process_memory", 0x69),
+    ("read_debug_process_memory", 0x6A),
+    ("write_debug_process_memory", 0x6B),
+    ("set_hardware_break_point", 0x6C),
+    ("get_debug_thread_param", 0x6D),
+    ("get_system_info", 0x6F),
+    ("create_port", 0x70),
+    ("manage_named_port", 0x71),
+    ("connect_to_port", 0x72),
+    ("set_process_memory_permission", 0x73),
+    ("map_process_memory", 0x74),
+    ("unmap_process_memory", 0x75),
+    ("query_process_memory", 0x76),
+    ("map_process_code_memory", 0x77),
+    ("unmap_process_code_memory", 0x78),
+    ("create_process", 0x79),
+    ("start_process", 0x7A),
+    ("terminate_process", 0x7B),
+    ("get_process_info", 0x7C),
+    ("create_resource_limit", 0x7D),
+    ("set_resource_limit_limit_value", 0x7E),
+    ("call_secure_monitor", 0x7F),
+    ("map_insecure_physical_memory", 0x90),
+    ("unmap_insecure_physical_memory", 0x91)
+];
+
+// Host builds (tests, fuzzing) have no kernel, thus every SVC (see svc.s) fails there: only code paths which don't reach the kernel (like the loopback IPC transport) actually work
+// Note: the stubs ignore their arguments (and leave output values untouched), which is fine for the C ABI of the host
+
+#[cfg(not(target_arch = "aarch64"))]
+macro_rules! define_host_svc_stubs {
+    ($( $name:ident ),*) => {
+        paste::paste! {
+            $(
+                #[no_mangle]
+                extern "C" fn [<__nx_svc_ $name>]() -> ResultCode {
+                    crate::results::lib::ResultNotSupported::make()
+                }
+            )*
+        }
+
+        #[cfg(test)]
+        const HOST_SVC_STUB_NAMES: &[&str] = &[$( stringify!($name) ),*];
+    };
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+define_host_svc_stubs!(
+    set_heap_size, set_memory_permission, set_memory_attribute, map_memory, unmap_memory, query_memory, create_thread, start_thread, sleep_thread,
+    get_thread_priority, set_thread_priority, get_thread_core_mask, set_thread_core_mask, get_current_processor_number, signal_event, clear_event,
+    map_shared_memory, unmap_shared_memory, create_transfer_memory, close_handle, reset_signal, wait_synchronization, cancel_synchronization, arbitrate_lock,
+    arbitrate_unlock, wait_process_wide_key_atomic, signal_process_wide_key, get_system_tick, connect_to_named_port, send_sync_request_light, send_sync_request,
+    send_sync_request_with_user_buffer, send_async_request_with_user_buffer, get_process_id, get_thread_id, output_debug_string, return_from_exception,
+    get_info, flush_entire_data_cache, flush_data_cache, map_physical_memory, unmap_physical_memory, get_debug_future_thread_info, get_last_thread_info,
+    get_resource_limit_limit_value, get_resource_limit_current_value, set_thread_activity, get_thread_context3, wait_for_address, signal_to_address,
+    synchronize_preemption_state, get_resource_limit_peak_value, create_io_pool, create_io_region, dump_info, kernel_debug, change_kernel_trace_state,
+    create_session, accept_session, reply_and_receive_light, reply_and_receive, reply_and_receive_with_user_buffer, create_event, map_io_region,
+    unmap_io_region, map_physical_memory_unsafe, unmap_physical_memory_unsafe, set_unsafe_limit, create_code_memory, control_code_memory, sleep_system,
+    read_write_register, set_process_activity, create_shared_memory, map_transfer_memory, unmap_transfer_memory, create_interrupt_event, query_physical_address,
+    query_io_mapping, legacy_query_io_mapping, create_device_address_space, attach_device_address_space, detach_device_address_space,
+    map_device_address_space_by_force, map_device_address_space_aligned, map_device_address_space, unmap_device_address_space, invalidate_process_data_cache,
+    store_process_data_cache, flush_process_data_cache, debug_active_process, break_debug_process, terminate_debug_process, get_debug_event,
+    legacy_continue_debug_event, continue_debug_event, get_process_list, get_thread_list, get_debug_thread_context, set_debug_thread_context,
+    query_debug_process_memory, read_debug_process_memory, write_debug_process_memory, set_hardware_break_point, get_debug_thread_param, get_system_info,
+    create_port, manage_named_port, connect_to_port, set_process_memory_permission, map_process_memory, unmap_process_memory, query_process_memory,
+    map_process_code_memory, unmap_process_code_memory, create_process, start_process, terminate_process, get_process_info, create_resource_limit,
+    set_resource_limit_limit_value, call_secure_monitor, map_insecure_physical_memory, unmap_insecure_physical_memory
+);
+
+// These never return on the console
+
+#[cfg(not(target_arch = "aarch64"))]
+#[no_mangle]
+extern "C" fn __nx_svc_exit_process() -> ! {
+    panic!("Exited the process");
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+#[no_mangle]
+extern "C" fn __nx_svc_exit_thread() -> ! {
+    panic!("Exited the thread");
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+#[no_mangle]
+extern "C" fn __nx_svc_break(_reason: BreakReason, _arg: Address, _size: Size) -> ! {
+    panic!("Break");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Official SVC numbers, as documented in https://switchbrew.org/wiki/SVC
+    const HORIZON_SVC_NUMBERS: &[(&str, u32)] = &[
+        ("SetHeapSize", 0x1),
+        ("SetMemoryPermission", 0x2),
+        ("SetMemoryAttribute", 0x3),
+        ("MapMemory", 0x4),
+        ("UnmapMemory", 0x5),
+        ("QueryMemory", 0x6),
+        ("ExitProcess", 0x7),
+        ("CreateThread", 0x8),
+        ("StartThread", 0x9),
+        ("ExitThread", 0xA),
+        ("SleepThread", 0xB),
+        ("GetThreadPriority", 0xC),
+        ("SetThreadPriority", 0xD),
+        ("GetThreadCoreMask", 0xE),
+        ("SetThreadCoreMask", 0xF),
+        ("GetCurrentProcessorNumber", 0x10),
+        ("SignalEvent", 0x11),
+        ("ClearEvent", 0x12),
+        ("MapSharedMemory", 0x13),
+        ("UnmapSharedMemory", 0x14),
+        ("CreateTransferMemory", 0x15),
+        ("CloseHandle", 0x16),
+        ("ResetSignal", 0x17),
+        ("WaitSynchronization", 0x18),
+        ("CancelSynchronization", 0x19),
+        ("ArbitrateLock", 0x1A),
+        ("ArbitrateUnlock", 0x1B),
+        ("WaitProcessWideKeyAtomic", 0x1C),
+        ("SignalProcessWideKey", 0x1D),
+        ("GetSystemTick", 0x1E),
+        ("ConnectToNamedPort", 0x1F),
+        ("SendSyncRequestLight", 0x20),
+        ("SendSyncRequest", 0x21),
+        ("SendSyncRequestWithUserBuffer", 0x22),
+        ("SendAsyncRequestWithUserBuffer", 0x23),
+        ("GetProcessId", 0x24),
+        ("GetThreadId", 0x25),
+        ("Break", 0x26),
+        ("OutputDebugString", 0x27),
+        ("ReturnFromException", 0x28),
+        ("GetInfo", 0x29),
+        ("FlushEntireDataCache", 0x2A),
+        ("FlushDataCache", 0x2B),
+        ("MapPhysicalMemory", 0x2C),
+        ("UnmapPhysicalMemory", 0x2D),
+        ("GetDebugFutureThreadInfo", 0x2E),
+        ("GetLastThreadInfo", 0x2F),
+        ("GetResourceLimitLimitValue", 0x30),
+        ("GetResourceLimitCurrentValue", 0x31),
+        ("SetThreadActivity", 0x32),
+        ("GetThreadContext3", 0x33),
+        ("WaitForAddress", 0x34),
+        ("SignalToAddress", 0x35),
+        ("SynchronizePreemptionState", 0x36),
+        ("GetResourceLimitPeakValue", 0x37),
+        ("CreateIoPool", 0x39),
+        ("CreateIoRegion", 0x3A),
+        ("DumpInfo", 0x3C),
+        ("KernelDebug", 0x3C),
+        ("ChangeKernelTraceState", 0x3D),
+        ("CreateSession", 0x40),
+        ("AcceptSession", 0x41),
+        ("ReplyAndReceiveLight", 0x42),
+        ("ReplyAndReceive", 0x43),
+        ("ReplyAndReceiveWithUserBuffer", 0x44),
+        ("CreateEvent", 0x45),
+        ("MapIoRegion", 0x46),
+        ("UnmapIoRegion", 0x47),
+        ("MapPhysicalMemoryUnsafe", 0x48),
+        ("UnmapPhysicalMemoryUnsafe", 0x49),
+        ("SetUnsafeLimit", 0x4A),
+        ("CreateCodeMemory", 0x4B),
+        ("ControlCodeMemory", 0x4C),
+        ("SleepSystem", 0x4D),
+        ("ReadWriteRegister", 0x4E),
+        ("SetProcessActivity", 0x4F),
+        ("CreateSharedMemory", 0x50),
+        ("MapTransferMemory", 0x51),
+        ("UnmapTransferMemory", 0x52),
+        ("CreateInterruptEvent", 0x53),
+        ("QueryPhysicalAddress", 0x54),
+        ("QueryIoMapping", 0x55),
+        ("CreateDeviceAddressSpace", 0x56),
+        ("AttachDeviceAddressSpace", 0x57),
+        ("DetachDeviceAddressSpace", 0x58),
+        ("MapDeviceAddressSpaceByForce", 0x59),
+        ("MapDeviceAddressSpaceAligned", 0x5A),
+        ("MapDeviceAddressSpace", 0x5B),
+        ("UnmapDeviceAddressSpace", 0x5C),
+        ("InvalidateProcessDataCache", 0x5D),
+        ("StoreProcessDataCache", 0x5E),
+        ("FlushProcessDataCache", 0x5F),
+        ("DebugActiveProcess", 0x60),
+        ("BreakDebugProcess", 0x61),
+        ("TerminateDebugProcess", 0x62),
+        ("GetDebugEvent", 0x63),
+        ("ContinueDebugEvent", 0x64),
+        ("GetProcessList", 0x65),
+        ("GetThreadList", 0x66),
+        ("GetDebugThreadContext", 0x67),
+        ("SetDebugThreadContext", 0x68),
+        ("QueryDebugProcessMemory", 0x69),
+        ("ReadDebugProcessMemory", 0x6A),
+        ("WriteDebugProcessMemory", 0x6B),
+        ("SetHardwareBreakPoint", 0x6C),
+        ("GetDebugThreadParam", 0x6D),
+        ("GetSystemInfo", 0x6F),
+        ("CreatePort", 0x70),
+        ("ManageNamedPort", 0x71),
+        ("ConnectToPort", 0x72),
+        ("SetProcessMemoryPermission", 0x73),
+        ("MapProcessMemory", 0x74),
+        ("UnmapProcessMemory", 0x75),
+        ("QueryProcessMemory", 0x76),
+        ("MapProcessCodeMemory", 0x77),
+        ("UnmapProcessCodeMemory", 0x78),
+        ("CreateProcess", 0x79),
+        ("StartProcess", 0x7A),
+        ("TerminateProcess", 0x7B),
+        ("GetProcessInfo", 0x7C),
+        ("CreateResourceLimit", 0x7D),
+        ("SetResourceLimitLimitValue", 0x7E),
+        ("CallSecureMonitor", 0x7F),
+        ("MapInsecurePhysicalMemory", 0x90),
+        ("UnmapInsecurePhysicalMemory", 0x91)
+    ];
+
+    // SVCs which were renamed or reworked across system versions, thus intentionally sharing their number
+    // Legacy stubs have no official name of their own, thus they're checked against the SVC they're paired with (the second one)
+    const SHARED_SVC_NUMBER_PAIRS: &[(&str, &str)] = &[
+        ("dump_info", "kernel_debug"),
+        ("legacy_query_io_mapping", "query_io_mapping"),
+        ("legacy_continue_debug_event", "continue_debug_event")
+    ];
+
+    fn to_horizon_name(name: &str) -> String {
+        let mut horizon_name = String::new();
+        for word in name.split('_') {
+            let mut chars = word.chars();
+            if let Some(first_char) = chars.next() {
+                horizon_name.push(first_char.to_ascii_uppercase());
+                horizon_name.extend(chars);
+            }
+        }
+        horizon_name
+    }
+
+    fn parse_stubs(stubs: &str) -> Vec<(String, u32)> {
+        let mut parsed_stubs: Vec<(String, u32)> = Vec::new();
+        for line in stubs.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix("FN_START __nx_svc_") {
+                parsed_stubs.push((String::from(name), u32::MAX));
+            }
+            else if let Some(number) = line.strip_prefix("svc 0x") {
+                let (name, stub_number) = parsed_stubs.last_mut().expect("SVC instruction outside of a stub");
+                assert_eq!(*stub_number, u32::MAX, "stub {} has more than one SVC instruction", name);
+                *stub_number = u32::from_str_radix(number, 16).unwrap();
+            }
+        }
+        parsed_stubs
+    }
+
+    #[test]
+    fn svc_table_matches_stubs() {
+        let stubs = parse_stubs(include_str!("svc.s"));
+        assert_eq!(stubs.len(), SVC_TABLE.len());
+        for ((stub_name, stub_number), (name, number)) in stubs.iter().zip(SVC_TABLE) {
+            assert_eq!(stub_name, name);
+            assert_eq!(stub_number, number, "stub {} doesn't use its table SVC number", name);
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "aarch64"))]
+    fn host_stubs_match_stubs() {
+        let stubs = parse_stubs(include_str!("svc.s"));
+        for (stub_name, _) in stubs.iter() {
+            let is_noreturn = ["exit_process", "exit_thread", "break"].contains(&stub_name.as_str());
+            assert!(is_noreturn || HOST_SVC_STUB_NAMES.contains(&stub_name.as_str()), "stub {} has no host fallback", stub_name);
+        }
+        assert_eq!(HOST_SVC_STUB_NAMES.len() + 3, stubs.len());
+    }
+
+    fn find_horizon_svc_number(name: &str) -> Option<u32> {
+        let horizon_name = to_horizon_name(name);
+        HORIZON_SVC_NUMBERS.iter().find(|(cur_name, _)| *cur_name == horizon_name).map(|(_, cur_number)| *cur_number)
+    }
+
+    #[test]
+    fn svc_table_matches_horizon() {
+        for (name, number) in SVC_TABLE {
+            let horizon_name = to_horizon_name(name);
+            let horizon_number = find_horizon_svc_number(name).or_else(|| {
+                let shared_name = SHARED_SVC_NUMBER_PAIRS.iter().find(|(cur_name, _)| cur_name == name).map(|(_, cur_shared_name)| *cur_shared_name)?;
+                find_horizon_svc_number(shared_name)
+            });
+            assert_eq!(horizon_number, Some(*number), "unexpected SVC number for {}", horizon_name);
+        }
+    }
+
+    #[test]
+    fn svc_table_has_no_duplicate_numbers() {
+        for (i, (name, number)) in SVC_TABLE.iter().enumerate() {
+            for (other_name, other_number) in &SVC_TABLE[i + 1..] {
+                if number == other_number {
+                    let is_shared_pair = SHARED_SVC_NUMBER_PAIRS.iter().any(|pair| (*pair == (*name, *other_name)) || (*pair == (*other_name, *name)));
+                    assert!(is_shared_pair, "{} and {} share SVC number {:#X}", name, other_name, number);
+                }
+            }
+        }
+    }
+}
@@ -411,12 +411,12 @@ FN_START __nx_svc_create_event
 FN_END
 
 FN_START __nx_svc_map_io_region
-	svc 0x48
+	svc 0x46
 	ret
 FN_END
 
 FN_START __nx_svc_unmap_io_region
-	svc 0x49
+	svc 0x47
 	ret
 FN_END
 
@@ -550,7 +550,7 @@ FN_START __nx_svc_map_device_address_space
 	str x0, [sp, #-16]!
 	svc 0x5B
 	ldr x2, [sp], #16
-	str w1, [x2]
+	str x1, [x2]
 	ret
 FN_END
 
@@ -753,7 +753,7 @@ FN_START __nx_svc_get_process_info
 	str x0, [sp, #-16]!
 	svc 0x7C
 	ldr x2, [sp], #16
-	str w1, [x2]
+	str x1, [x2]
 	ret
 FN_END
 
@@ -785,3 +785,13 @@ FN_START __nx_svc_call_secure_monitor
 	stp x6, x7, [x8, #0x30]
 	ret
 FN_END
+
+FN_START __nx_svc_map_insecure_physical_memory
+	svc 0x90
+	ret
+FN_END
+
+FN_START __nx_svc_unmap_insecure_physical_memory
+	svc 0x91
+	ret
+FN_END
@@ -1,8 +1,13 @@
+#[cfg(target_arch = "aarch64")]
 use crate::svc;
 use crate::thread;
 use core::cell::UnsafeCell;
+#[cfg(target_arch = "aarch64")]
 use core::arch::asm;
+#[cfg(not(target_arch = "aarch64"))]
+use core::sync::atomic::{AtomicU32, Ordering};
 
+#[cfg(target_arch = "aarch64")]
 const HANDLE_WAIT_MASK: u32 = 0x40000000;
 
 #[inline(always)]
@@ -10,6 +15,7 @@ fn get_current_thread_handle() -> u32 {
     thread::get_current_thread().get_handle()
 }
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 fn load_exclusive(ptr: *mut u32) -> u32 {
     let value: u32;
@@ -23,6 +29,7 @@ fn load_exclusive(ptr: *mut u32) -> u32 {
     value
 }
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 fn store_exclusive(ptr: *mut u32, value: u32) -> i32 {
     let res: i32;
@@ -37,6 +44,7 @@ fn store_exclusive(ptr: *mut u32, value: u32) -> i32 {
     res
 }
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 fn clear_exclusive() {
     unsafe {
@@ -44,6 +52,7 @@ fn clear_exclusive() {
     }
 }
 
+#[cfg(target_arch = "aarch64")]
 fn lock_impl(handle_ref: *mut u32) {
     let thr_handle = get_current_thread_handle();
     
@@ -72,6 +81,7 @@ fn lock_impl(handle_ref: *mut u32) {
     }
 }
 
+#[cfg(target_arch = "aarch64")]
 fn unlock_impl(handle_ref: *mut u32) {
     let thr_handle = get_current_thread_handle();
     
@@ -93,6 +103,7 @@ fn unlock_impl(handle_ref: *mut u32) {
     }
 }
 
+#[cfg(target_arch = "aarch64")]
 fn try_lock_impl(handle_ref: *mut u32) -> bool {
     let thr_handle = get_current_thread_handle();
 
@@ -110,6 +121,31 @@ fn try_lock_impl(handle_ref: *mut u32) -> bool {
     false
 }
 
+// Host builds (tests, fuzzing) have neither exclusive monitors nor a kernel to arbitrate with, thus the lock is just spun on there
+
+#[cfg(not(target_arch = "aarch64"))]
+fn lock_impl(handle_ref: *mut u32) {
+    let thr_handle = get_current_thread_handle();
+    let value = unsafe { AtomicU32::from_ptr(handle_ref) };
+    while value.compare_exchange_weak(0, thr_handle, Ordering::Acquire, Ordering::Relaxed).is_err() {
+        core::hint::spin_loop();
+    }
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+fn unlock_impl(handle_ref: *mut u32) {
+    let thr_handle = get_current_thread_handle();
+    let value = unsafe { AtomicU32::from_ptr(handle_ref) };
+    let _ = value.compare_exchange(thr_handle, 0, Ordering::Release, Ordering::Relaxed);
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+fn try_lock_impl(handle_ref: *mut u32) -> bool {
+    let thr_handle = get_current_thread_handle();
+    let value = unsafe { AtomicU32::from_ptr(handle_ref) };
+    value.compare_exchange(0, thr_handle, Ordering::Acquire, Ordering::Relaxed).is_ok()
+}
+
 pub struct Mutex {
     value: u32,
     is_recursive: bool,
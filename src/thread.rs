@@ -5,7 +5,10 @@ use crate::mem::alloc;
 use crate::wait;
 use crate::util;
 use core::ptr;
+#[cfg(target_arch = "aarch64")]
 use core::arch::asm;
+#[cfg(not(target_arch = "aarch64"))]
+use core::sync::atomic::{AtomicU32, Ordering};
 
 pub type ThreadName = util::CString<0x20>;
 
@@ -62,6 +65,10 @@ pub const PRIORITY_AUTO: i32 = -1;
 // Note: our thread type attempts to kind-of mimic the official nn::os::ThreadType struct, at least so that the thread name is properly accessible from TLS by, for instance, creport -- thus all the reserved fields
 // TODO: TLS slots
 
+// The first unused TLS slots are used by the library itself, since they are per-thread and need no locking (see ipc::transport)
+pub const IPC_TRANSPORT_TLS_SLOT: usize = 0;
+pub const IPC_MESSAGE_LOGGING_TLS_SLOT: usize = 1;
+
 #[repr(C)]
 pub struct Thread {
     pub self_ref: *mut Thread,
@@ -153,7 +160,8 @@ impl Thread {
             priority_value = get_current_thread().get_priority()?;
         }
 
-        self.handle = svc::create_thread(self.entry.as_ref().unwrap().entry_impl, self as *mut _ as *mut u8, (self.stack as usize + self.stack_size) as *const u8, priority_value, processor_id)?;
+        // Note: the raw handle is kept (see the layout note above), and closed on drop unless the thread is remote
+        self.handle = svc::create_thread(self.entry.as_ref().unwrap().entry_impl, self as *mut _ as *mut u8, (self.stack as usize + self.stack_size) as *const u8, priority_value, processor_id)?.into_raw();
         
         self.state = ThreadState::Initialized;
         Ok(())
@@ -162,7 +170,7 @@ impl Thread {
     pub fn start(&mut self) -> Result<()> {
         result_return_unless!((self.state == ThreadState::Initialized) || (self.state == ThreadState::Terminated), results::lib::thread::ResultInvalidState);
 
-        svc::start_thread(self.handle)?;
+        svc::start_thread(self.get_borrowed_handle())?;
 
         self.state = ThreadState::Started;
         Ok(())
@@ -185,16 +193,22 @@ impl Thread {
         self.handle
     }
 
+    pub fn get_borrowed_handle(&self) -> svc::BorrowedHandle<'_> {
+        unsafe {
+            svc::BorrowedHandle::borrow_raw(self.handle)
+        }
+    }
+
     pub fn get_priority(&self) -> Result<i32> {
         result_return_unless!(self.state != ThreadState::NotInitialized, results::lib::thread::ResultInvalidState);
 
-        svc::get_thread_priority(self.handle)
+        svc::get_thread_priority(self.get_borrowed_handle())
     }
 
     pub fn get_id(&self) -> Result<u64> {
         result_return_unless!(self.state != ThreadState::NotInitialized, results::lib::thread::ResultInvalidState);
         
-        svc::get_thread_id(self.handle)
+        svc::get_thread_id(self.get_borrowed_handle())
     } 
 }
 
@@ -238,6 +252,7 @@ pub struct ThreadLocalRegion {
 }
 const_assert!(core::mem::size_of::<ThreadLocalRegion>() == 0x200);
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 pub fn get_thread_local_region() -> *mut ThreadLocalRegion {
     let tlr: *mut ThreadLocalRegion;
@@ -250,6 +265,32 @@ pub fn get_thread_local_region() -> *mut ThreadLocalRegion {
     tlr
 }
 
+// Host builds (tests, fuzzing) have no kernel-provided TLR, thus every host thread gets its own one, along with a placeholder thread holding a unique fake handle
+
+#[cfg(not(target_arch = "aarch64"))]
+#[thread_local]
+static mut G_HOST_THREAD_LOCAL_REGION: ThreadLocalRegion = unsafe { core::mem::zeroed() };
+
+#[cfg(not(target_arch = "aarch64"))]
+#[thread_local]
+static mut G_HOST_THREAD: Thread = Thread::empty();
+
+#[cfg(not(target_arch = "aarch64"))]
+static G_HOST_NEXT_THREAD_HANDLE: AtomicU32 = AtomicU32::new(1);
+
+#[cfg(not(target_arch = "aarch64"))]
+pub fn get_thread_local_region() -> *mut ThreadLocalRegion {
+    unsafe {
+        let tlr = ptr::addr_of_mut!(G_HOST_THREAD_LOCAL_REGION);
+        if (*tlr).thread_ref.is_null() {
+            let thread_ref = ptr::addr_of_mut!(G_HOST_THREAD);
+            (*thread_ref).handle = G_HOST_NEXT_THREAD_HANDLE.fetch_add(1, Ordering::Relaxed);
+            (*tlr).thread_ref = thread_ref;
+        }
+        tlr
+    }
+}
+
 pub fn set_current_thread(thread_ref: *mut Thread) {
     unsafe {
         (*thread_ref).self_ref = thread_ref;
@@ -9,6 +9,9 @@ use core::str;
 use core::ptr;
 use core::fmt;
 use core::panic;
+use core::mem;
+use core::ops;
+use core::slice;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 #[repr(C)]
@@ -239,6 +242,164 @@ impl<const S: usize> CString16<S> {
     }
 }
 
+// Vec-like storage with a fixed capacity, which never allocates
+
+pub struct FixedVec<T, const N: usize> {
+    items: [mem::MaybeUninit<T>; N],
+    len: usize
+}
+
+impl<T, const N: usize> FixedVec<T, N> {
+    pub fn new() -> Self {
+        // An array of uninitialized items doesn't need initialization
+        Self { items: unsafe { mem::MaybeUninit::<[mem::MaybeUninit<T>; N]>::uninit().assume_init() }, len: 0 }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn push(&mut self, item: T) -> Result<()> {
+        result_return_if!(self.is_full(), results::lib::util::ResultFixedVecFull);
+
+        self.items[self.len] = mem::MaybeUninit::new(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match self.is_empty() {
+            true => None,
+            false => {
+                self.len -= 1;
+                Some(unsafe { self.items[self.len].as_ptr().read() })
+            }
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        unsafe {
+            let item_ptr = self.items.as_mut_ptr().add(index) as *mut T;
+            let item = item_ptr.read();
+            ptr::copy(item_ptr.add(1), item_ptr, self.len - index - 1);
+            self.len -= 1;
+            Some(item)
+        }
+    }
+
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut index = 0;
+        while index < self.len {
+            match f(&self[index]) {
+                true => index += 1,
+                false => {
+                    self.remove(index);
+                }
+            };
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    // Moves as many items as possible from the other vec, failing if some of them didn't fit (those are left there)
+    pub fn append<const M: usize>(&mut self, other: &mut FixedVec<T, M>) -> Result<()> {
+        while !other.is_empty() {
+            result_return_if!(self.is_full(), results::lib::util::ResultFixedVecFull);
+            if let Some(item) = other.remove(0) {
+                self.push(item)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> ops::Deref for FixedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.items.as_ptr() as *const T, self.len) }
+    }
+}
+
+impl<T, const N: usize> ops::DerefMut for FixedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.items.as_mut_ptr() as *mut T, self.len) }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a FixedVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut FixedVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for FixedVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut vec = Self::new();
+        for item in self.iter() {
+            // Same capacity, thus every item fits
+            let _ = vec.push(item.clone());
+        }
+        vec
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for FixedVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for FixedVec<T, N> {}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for FixedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const N: usize> Default for FixedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for FixedVec<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
 pub fn str_ptr_len(str_ptr: *const u8) -> usize {
     unsafe {
         let mut iter_ptr = str_ptr as *mut u8;
@@ -58,6 +58,44 @@ impl fmt::Display for Version {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct VersionInterval {
+    pub min: Option<Version>,
+    pub max: Option<Version>
+}
+
+impl VersionInterval {
+    pub const fn all() -> Self {
+        Self { min: None, max: None }
+    }
+
+    pub const fn from(min: Version) -> Self {
+        Self { min: Some(min), max: None }
+    }
+
+    pub const fn to(max: Version) -> Self {
+        Self { min: None, max: Some(max) }
+    }
+
+    pub const fn from_to(min: Version, max: Version) -> Self {
+        Self { min: Some(min), max: Some(max) }
+    }
+
+    pub fn contains(&self, ver: Version) -> bool {
+        if let Some(min_v) = self.min {
+            if ver < min_v {
+                return false;
+            }
+        }
+        if let Some(max_v) = self.max {
+            if ver > max_v {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 static mut G_VERSION: sync::Locked<Version> = sync::Locked::new(false, Version::empty());
 
 pub fn set_version(version: Version) {
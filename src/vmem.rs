@@ -61,8 +61,8 @@ pub fn get_legacy_alias_region() -> VirtualRegion {
 }
 
 fn read_region_info(region: &mut VirtualRegion, address_info_id: svc::InfoId, size_info_id: svc::InfoId) -> Result<()> {
-    let address = svc::get_info(address_info_id, svc::CURRENT_PROCESS_PSEUDO_HANDLE, 0)? as usize;
-    let size = svc::get_info(size_info_id, svc::CURRENT_PROCESS_PSEUDO_HANDLE, 0)? as usize;
+    let address = svc::get_info(address_info_id, svc::BorrowedHandle::current_process(), 0)? as usize;
+    let size = svc::get_info(size_info_id, svc::BorrowedHandle::current_process(), 0)? as usize;
 
     region.start = address;
     region.end = address + size;
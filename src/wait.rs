@@ -4,42 +4,40 @@ use crate::svc;
 use crate::arm;
 
 pub struct RemoteEvent {
-    pub handle: svc::Handle
+    pub handle: svc::OwnedHandle
 }
 
 impl RemoteEvent {
     pub const fn empty() -> Self {
-        Self { handle: svc::INVALID_HANDLE }
+        Self { handle: svc::OwnedHandle::invalid() }
     }
     
-    pub const fn new(handle: svc::Handle) -> Self {
+    pub const fn new(handle: svc::OwnedHandle) -> Self {
         Self { handle }
     }
 
     pub fn reset(&self) -> Result<()> {
-        svc::reset_signal(self.handle)
+        svc::reset_signal(self.handle.as_borrowed())
     }
 
     pub fn wait(&self, timeout: i64) -> Result<()> {
-        wait_handles(&[self.handle], timeout)?;
+        wait_handles(&[self.handle.as_raw()], timeout)?;
         self.reset()
     }
-}
 
-impl Drop for RemoteEvent {
-    fn drop(&mut self) {
-        let _ = svc::close_handle(self.handle);
+    pub fn get_waiter(&self) -> Waiter<'_> {
+        Waiter::from_handle_with_clear(self.handle.as_borrowed())
     }
 }
 
 pub struct SystemEvent {
-    pub server_handle: svc::Handle,
-    pub client_handle: svc::Handle
+    pub server_handle: svc::OwnedHandle,
+    pub client_handle: svc::OwnedHandle
 }
 
 impl SystemEvent {
     pub const fn empty() -> Self {
-        Self { server_handle: 0, client_handle: 0 }
+        Self { server_handle: svc::OwnedHandle::invalid(), client_handle: svc::OwnedHandle::invalid() }
     }
     
     pub fn new() -> Result<Self> {
@@ -48,17 +46,15 @@ impl SystemEvent {
     }
 
     pub fn signal(&self) -> Result<()> {
-        svc::signal_event(self.server_handle)
+        svc::signal_event(self.server_handle.as_borrowed())
     }
-}
 
-impl Drop for SystemEvent {
-    fn drop(&mut self) {
-        let _ = svc::close_handle(self.client_handle);
-        let _ = svc::close_handle(self.server_handle);
+    pub fn get_waiter(&self) -> Waiter<'_> {
+        Waiter::from_handle_with_clear(self.client_handle.as_borrowed())
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum WaiterType {
     Handle,
     HandleWithClear
@@ -66,24 +62,38 @@ pub enum WaiterType {
 
 pub const MAX_OBJECT_COUNT: u32 = 0x40;
 
-#[allow(dead_code)]
-pub struct Waiter {
-    handle: svc::Handle,
+pub struct Waiter<'a> {
+    handle: svc::BorrowedHandle<'a>,
     wait_type: WaiterType
 }
 
-impl Waiter {
-    pub const fn from(handle: svc::Handle, wait_type: WaiterType) -> Self {
+impl<'a> Waiter<'a> {
+    pub const fn from(handle: svc::BorrowedHandle<'a>, wait_type: WaiterType) -> Self {
         Self { handle, wait_type }
     }
     
-    pub const fn from_handle(handle: svc::Handle) -> Self {
+    pub const fn from_handle(handle: svc::BorrowedHandle<'a>) -> Self {
         Self::from(handle, WaiterType::Handle)
     }
 
-    pub const fn from_handle_with_clear(handle: svc::Handle) -> Self {
+    pub const fn from_handle_with_clear(handle: svc::BorrowedHandle<'a>) -> Self {
         Self::from(handle, WaiterType::HandleWithClear)
     }
+
+    pub const fn get_handle(&self) -> svc::BorrowedHandle<'a> {
+        self.handle
+    }
+
+    pub const fn get_type(&self) -> WaiterType {
+        self.wait_type
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        match self.wait_type {
+            WaiterType::Handle => Ok(()),
+            WaiterType::HandleWithClear => svc::reset_signal(self.handle)
+        }
+    }
 }
 
 type WaitFn<W> = fn(&[W], i64) -> Result<usize>;
@@ -92,7 +102,7 @@ fn handles_wait_fn(handles: &[svc::Handle], timeout: i64) -> Result<usize> {
     Ok(svc::wait_synchronization(handles.as_ptr(), handles.len() as u32, timeout)? as usize)
 }
 
-fn waiters_wait_fn(_waiters: &[Waiter], _timeout: i64) -> Result<usize> {
+fn waiters_wait_fn(_waiters: &[Waiter<'_>], _timeout: i64) -> Result<usize> {
     todo!();
 }
 
@@ -127,7 +137,7 @@ fn wait_impl<W>(wait_objects: &[W], timeout: i64, wait_fn: WaitFn<W>) -> Result<
     }
 }
 
-pub fn wait(waiters: &[Waiter], timeout: i64) -> Result<usize> {
+pub fn wait(waiters: &[Waiter<'_>], timeout: i64) -> Result<usize> {
     wait_impl(waiters, timeout, waiters_wait_fn)
 }
 